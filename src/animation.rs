@@ -0,0 +1,241 @@
+use bevy_app::{App, Plugin, PostUpdate};
+use bevy_asset::{Asset, AssetApp, Assets, Handle};
+use bevy_ecs::prelude::*;
+use bevy_reflect::Reflect;
+use bevy_time::Time;
+
+use crate::render::sprite::TextureAtlasSprite;
+
+/// How an [`AnimationClip`] continues after its last frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum AnimationMode {
+	/// Starts over from the first frame
+	#[default]
+	Loop,
+	/// Plays the frames backwards down to the first one, then forwards again
+	PingPong,
+	/// Stops on the last frame
+	Once,
+}
+
+/// A single frame of an [`AnimationClip`]
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct AnimationFrame {
+	/// Index of the region in the [`TextureAtlas`](crate::asset::TextureAtlas)
+	pub index: usize,
+	/// How long the frame is shown, in seconds
+	pub duration: f32,
+	/// Sends an [`AnimationEvent::Marker`] with this name every time the frame is shown
+	pub marker: Option<String>,
+}
+
+/// A sequence of [`TextureAtlas`](crate::asset::TextureAtlas) regions, played on entities by an [`AnimationPlayer`]
+#[derive(Asset, Debug, Clone, PartialEq, Default, Reflect)]
+pub struct AnimationClip {
+	pub frames: Vec<AnimationFrame>,
+	pub mode: AnimationMode,
+}
+
+impl AnimationClip {
+	/// Creates a clip without any frames
+	pub fn new(mode: AnimationMode) -> Self {
+		Self { frames: Vec::new(), mode }
+	}
+
+	/// Creates a clip showing every atlas region for the same `frame_duration` in seconds
+	pub fn from_indices(indices: impl IntoIterator<Item = usize>, frame_duration: f32, mode: AnimationMode) -> Self {
+		let frames = indices.into_iter().map(|index| AnimationFrame { index, duration: frame_duration, marker: None }).collect();
+		Self { frames, mode }
+	}
+
+	/// Appends a frame showing the atlas region at `index` for `duration` seconds
+	pub fn with_frame(mut self, index: usize, duration: f32) -> Self {
+		self.frames.push(AnimationFrame { index, duration, marker: None });
+		self
+	}
+
+	/// Sets the marker of the frame at `frame`. Does nothing if there's no such frame
+	pub fn with_marker(mut self, frame: usize, marker: impl Into<String>) -> Self {
+		if let Some(frame) = self.frames.get_mut(frame) {
+			frame.marker = Some(marker.into());
+		}
+		self
+	}
+
+	/// Duration of all the frames played once, in seconds
+	pub fn duration(&self) -> f32 {
+		self.frames.iter().map(|frame| frame.duration).sum()
+	}
+}
+
+/// Plays an [`AnimationClip`], updating the index of the entity's [`TextureAtlasSprite`].
+///
+/// Advances on the [`Time`] resource in [`PostUpdate`], which [`QuadifyPlugins`](crate::QuadifyPlugins) adds with [`bevy_time::TimePlugin`].
+#[derive(Debug, Clone, Component)]
+pub struct AnimationPlayer {
+	clip: Handle<AnimationClip>,
+	/// Playback speed multiplier
+	pub speed: f32,
+	pub paused: bool,
+	frame: usize,
+	elapsed: f32,
+	backwards: bool,
+	started: bool,
+	finished: bool,
+}
+
+impl AnimationPlayer {
+	pub fn new(clip: Handle<AnimationClip>) -> Self {
+		Self {
+			clip,
+			speed: 1.0,
+			paused: false,
+			frame: 0,
+			elapsed: 0.0,
+			backwards: false,
+			started: false,
+			finished: false,
+		}
+	}
+
+	/// Switches to another clip, playing it from the start. Does nothing if the clip is already playing
+	pub fn play(&mut self, clip: Handle<AnimationClip>) {
+		if self.clip != clip {
+			self.clip = clip;
+			self.restart();
+		}
+	}
+
+	/// Plays the current clip from the start
+	pub fn restart(&mut self) {
+		self.frame = 0;
+		self.elapsed = 0.0;
+		self.backwards = false;
+		self.started = false;
+		self.finished = false;
+	}
+
+	pub fn clip(&self) -> &Handle<AnimationClip> {
+		&self.clip
+	}
+
+	/// Position of the current frame in the clip
+	pub fn frame(&self) -> usize {
+		self.frame
+	}
+
+	/// Whether an [`AnimationMode::Once`] clip reached its last frame
+	pub fn is_finished(&self) -> bool {
+		self.finished
+	}
+
+	/// Moves to the next frame, returning whether the clip reached its end
+	fn next_frame(&mut self, mode: AnimationMode, len: usize) -> bool {
+		let last = len - 1;
+
+		match mode {
+			AnimationMode::PingPong if len > 1 => {
+				if self.backwards {
+					self.frame -= 1;
+					self.backwards = self.frame != 0;
+					return !self.backwards;
+				}
+
+				self.frame += 1;
+				self.backwards = self.frame == last;
+				false
+			}
+			AnimationMode::Once if self.frame == last => {
+				self.finished = true;
+				true
+			}
+			_ if self.frame == last => {
+				self.frame = 0;
+				true
+			}
+			_ => {
+				self.frame += 1;
+				false
+			}
+		}
+	}
+}
+
+/// Sent by [`AnimationPlayer`]s
+#[derive(Debug, Clone, PartialEq, Event)]
+pub enum AnimationEvent {
+	/// A frame with a [`marker`](AnimationFrame::marker) was shown
+	Marker {
+		entity: Entity,
+		/// Position of the frame in the clip
+		frame: usize,
+		name: String,
+	},
+	/// The clip played to its end. Sent after every cycle of [`AnimationMode::Loop`] and [`AnimationMode::PingPong`] clips
+	End { entity: Entity },
+}
+
+/// Systems advancing [`AnimationPlayer`]s, in [`PostUpdate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct AnimationUpdate;
+
+/// Adds [`AnimationClip`] assets and [`AnimationPlayer`] playback
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_asset::<AnimationClip>()
+			.register_asset_reflect::<AnimationClip>()
+			.add_event::<AnimationEvent>()
+			.add_systems(PostUpdate, advance_animations.in_set(AnimationUpdate));
+	}
+}
+
+fn advance_animations(time: Res<Time>, clips: Res<Assets<AnimationClip>>, mut players: Query<(Entity, &mut AnimationPlayer, &mut TextureAtlasSprite)>, mut events: EventWriter<AnimationEvent>) {
+	for (entity, mut player, mut sprite) in players.iter_mut() {
+		if player.paused || player.finished {
+			continue;
+		}
+
+		// The clip could be still loading
+		let clip = match clips.get(&player.clip) {
+			Some(clip) if !clip.frames.is_empty() => clip,
+			_ => continue,
+		};
+
+		let marker = |frame: usize| clip.frames[frame].marker.clone().map(|name| AnimationEvent::Marker { entity, frame, name });
+
+		// The frame could be out of bounds if the clip was modified
+		if player.frame >= clip.frames.len() {
+			player.restart();
+		}
+
+		// A newly started clip shows its first frame for the whole frame duration
+		if !player.started {
+			player.started = true;
+			events.send_batch(marker(0));
+		} else if clip.duration() > 0.0 {
+			// A clip without duration would never leave its frames
+			// After a long frame, skip ahead instead of catching up on more than one pass over the clip
+			player.elapsed = (player.elapsed + time.delta_seconds() * player.speed.max(0.0)).min(clip.duration());
+
+			while player.elapsed >= clip.frames[player.frame].duration {
+				player.elapsed -= clip.frames[player.frame].duration;
+
+				if player.next_frame(clip.mode, clip.frames.len()) {
+					events.send(AnimationEvent::End { entity });
+				}
+				if player.finished {
+					player.elapsed = 0.0;
+					break;
+				}
+				events.send_batch(marker(player.frame));
+			}
+		}
+
+		let index = clip.frames[player.frame].index;
+		if sprite.index != index {
+			sprite.index = index;
+		}
+	}
+}
@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use bevy_asset::{io::Reader, Asset, AssetLoader, Assets, AsyncReadExt, BoxedFuture, Handle, LoadContext, ReadAssetBytesError};
+use bevy_ecs::system::{NonSendMut, ResMut};
+use bevy_reflect::TypePath;
+use glam::{vec2, vec4, Vec2, Vec4};
+use image::RgbaImage;
+use serde::Deserialize;
+
+use super::io::{decode_image, dependency_path};
+use super::{Texture, TextureAtlas};
+use crate::animation::{AnimationClip, AnimationFrame, AnimationMode};
+use crate::render::RenderingBackend;
+
+/// Playback direction of an [`AsepriteTag`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AsepriteDirection {
+	#[default]
+	Forward,
+	Reverse,
+	PingPong,
+	PingPongReverse,
+}
+
+/// A frame of the sheet, in the order of the export
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsepriteFrame {
+	/// The `filename` of the frame, or its key in the hash format
+	pub name: String,
+	/// Region of the frame in the image as `(x, y, width, height)` in pixels
+	pub rect: Vec4,
+	/// Duration of the frame in seconds
+	pub duration: f32,
+}
+
+/// A range of frames tagged in Aseprite, played as an [`AnimationClip`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsepriteTag {
+	pub name: String,
+	pub from: usize,
+	/// Last frame of the tag, inclusive
+	pub to: usize,
+	pub direction: AsepriteDirection,
+}
+
+/// A named region of the sprite, that can change over frames
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsepriteSlice {
+	pub name: String,
+	/// Keyframes of the slice, ordered by frame
+	pub keys: Vec<AsepriteSliceKey>,
+}
+
+impl AsepriteSlice {
+	/// The key in effect on the given frame, which is the last key starting at or before it
+	pub fn key(&self, frame: usize) -> Option<&AsepriteSliceKey> {
+		self.keys.iter().rev().find(|key| key.frame <= frame)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsepriteSliceKey {
+	/// First frame the key applies to
+	pub frame: usize,
+	/// Region of the slice in the sprite as `(x, y, width, height)` in pixels
+	pub bounds: Vec4,
+	/// Center of a nine-patch slice as `(x, y, width, height)` in pixels, relative to the bounds
+	pub center: Option<Vec4>,
+	/// Pivot of the slice in pixels, relative to the bounds
+	pub pivot: Option<Vec2>,
+}
+
+/// Error of parsing and loading Aseprite sheets
+#[derive(Debug)]
+pub enum AsepriteError {
+	Io(std::io::Error),
+	Json(serde_json::Error),
+	/// The `frames` field is neither an array nor a hash
+	InvalidFrames,
+	/// A tag references a frame that doesn't exist
+	InvalidTag { name: String },
+	/// The sheet image couldn't be loaded
+	Image(ReadAssetBytesError),
+	Decode(image::ImageError),
+}
+
+impl std::fmt::Display for AsepriteError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Io(err) => write!(f, "Failed to read the Aseprite sheet: {}", err),
+			Self::Json(err) => write!(f, "Invalid Aseprite sheet: {}", err),
+			Self::InvalidFrames => write!(f, "The frames of the Aseprite sheet are neither an array nor a hash"),
+			Self::InvalidTag { name } => write!(f, "The frame tag {:?} is out of the sheet frames", name),
+			Self::Image(err) => write!(f, "Failed to load the Aseprite sheet image: {}", err),
+			Self::Decode(err) => write!(f, "Failed to decode the Aseprite sheet image: {}", err),
+		}
+	}
+}
+
+impl std::error::Error for AsepriteError {}
+
+impl From<std::io::Error> for AsepriteError {
+	fn from(err: std::io::Error) -> Self {
+		Self::Io(err)
+	}
+}
+
+impl From<serde_json::Error> for AsepriteError {
+	fn from(err: serde_json::Error) -> Self {
+		Self::Json(err)
+	}
+}
+
+impl From<ReadAssetBytesError> for AsepriteError {
+	fn from(err: ReadAssetBytesError) -> Self {
+		Self::Image(err)
+	}
+}
+
+impl From<image::ImageError> for AsepriteError {
+	fn from(err: image::ImageError) -> Self {
+		Self::Decode(err)
+	}
+}
+
+/// JSON sheet exported by Aseprite, in either the hash or the array format
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsepriteFile {
+	/// Path of the sheet image, relative to the JSON file
+	pub image: String,
+	/// Size of the sheet image in pixels
+	pub size: Vec2,
+	pub frames: Vec<AsepriteFrame>,
+	pub tags: Vec<AsepriteTag>,
+	pub slices: Vec<AsepriteSlice>,
+}
+
+impl AsepriteFile {
+	pub fn from_slice(bytes: &[u8]) -> Result<Self, AsepriteError> {
+		let raw: RawFile = serde_json::from_slice(bytes)?;
+
+		let frames: Vec<(String, RawFrame)> = match raw.frames {
+			serde_json::Value::Array(frames) => frames
+				.into_iter()
+				.enumerate()
+				.map(|(i, frame)| serde_json::from_value::<RawFrame>(frame).map(|frame| (frame.filename.clone().unwrap_or_else(|| i.to_string()), frame)))
+				.collect::<Result<_, _>>()?,
+			// Frame order comes from the key order, which `serde_json` preserves
+			serde_json::Value::Object(frames) => frames.into_iter().map(|(name, frame)| serde_json::from_value(frame).map(|frame| (name, frame))).collect::<Result<_, _>>()?,
+			_ => return Err(AsepriteError::InvalidFrames),
+		};
+
+		let frames: Vec<_> = frames
+			.into_iter()
+			.map(|(name, frame)| {
+				if frame.rotated {
+					#[cfg(feature = "log")]
+					bevy_log::warn!("Rotated Aseprite frame {:?} isn't supported, it will be drawn sideways", name);
+				}
+				AsepriteFrame { name, rect: frame.frame.into(), duration: frame.duration / 1000.0 }
+			})
+			.collect();
+
+		let tags = raw
+			.meta
+			.frame_tags
+			.into_iter()
+			.map(|tag| {
+				if tag.from > tag.to || tag.to >= frames.len() {
+					return Err(AsepriteError::InvalidTag { name: tag.name });
+				}
+
+				let direction = match tag.direction.as_str() {
+					"reverse" => AsepriteDirection::Reverse,
+					"pingpong" => AsepriteDirection::PingPong,
+					"pingpong_reverse" => AsepriteDirection::PingPongReverse,
+					_ => AsepriteDirection::Forward,
+				};
+				Ok(AsepriteTag { name: tag.name, from: tag.from, to: tag.to, direction })
+			})
+			.collect::<Result<_, _>>()?;
+
+		let slices = raw
+			.meta
+			.slices
+			.into_iter()
+			.map(|slice| AsepriteSlice {
+				name: slice.name,
+				keys: slice
+					.keys
+					.into_iter()
+					.map(|key| AsepriteSliceKey {
+						frame: key.frame,
+						bounds: key.bounds.into(),
+						center: key.center.map(Vec4::from),
+						pivot: key.pivot.map(|pivot| vec2(pivot.x, pivot.y)),
+					})
+					.collect(),
+			})
+			.collect();
+
+		Ok(Self {
+			image: raw.meta.image,
+			size: vec2(raw.meta.size.w, raw.meta.size.h),
+			frames,
+			tags,
+			slices,
+		})
+	}
+
+	/// Atlas of the frames, where frame names can be looked up with [`TextureAtlas::index_of`]
+	pub fn atlas(&self, texture: Handle<Texture>) -> TextureAtlas {
+		let mut atlas = TextureAtlas::new(texture, self.size);
+		for frame in &self.frames {
+			atlas.add_named_rect(frame.name.clone(), frame.rect);
+		}
+		atlas
+	}
+
+	/// Clip of the tagged frames, indexing the regions of [`AsepriteFile::atlas`]
+	pub fn clip(&self, tag: &AsepriteTag) -> AnimationClip {
+		let mut frames: Vec<_> = (tag.from..=tag.to)
+			.map(|index| AnimationFrame {
+				index,
+				duration: self.frames[index].duration,
+				marker: None,
+			})
+			.collect();
+
+		if matches!(tag.direction, AsepriteDirection::Reverse | AsepriteDirection::PingPongReverse) {
+			frames.reverse();
+		}
+		let mode = match tag.direction {
+			AsepriteDirection::PingPong | AsepriteDirection::PingPongReverse => AnimationMode::PingPong,
+			_ => AnimationMode::Loop,
+		};
+
+		AnimationClip { frames, mode }
+	}
+}
+
+/// Aseprite sheet loaded by the [`AsepriteLoader`]. Its texture is uploaded to the GPU in [`PreUpdate`](bevy_app::PreUpdate) of the frame the sheet is loaded,
+/// until then the atlas texture is the default handle.
+#[derive(Asset, TypePath, Debug)]
+pub struct AsepriteSheet {
+	/// Atlas of the frames, labeled `atlas`
+	pub atlas: Handle<TextureAtlas>,
+	/// Clips of the frame tags by tag name, labeled `tags/<name>`
+	pub clips: HashMap<String, Handle<AnimationClip>>,
+	pub slices: HashMap<String, AsepriteSlice>,
+	image: Option<RgbaImage>,
+}
+
+/// Loads `.aseprite.json` sheets exported from Aseprite. The sheet image is read from the same asset source, next to the sheet.
+#[derive(Default)]
+pub struct AsepriteLoader;
+
+impl AssetLoader for AsepriteLoader {
+	type Asset = AsepriteSheet;
+	type Settings = ();
+	type Error = AsepriteError;
+
+	fn load<'a>(&'a self, reader: &'a mut Reader, _settings: &'a (), load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<AsepriteSheet, AsepriteError>> {
+		Box::pin(async move {
+			let mut bytes = Vec::new();
+			reader.read_to_end(&mut bytes).await?;
+			let file = AsepriteFile::from_slice(&bytes)?;
+
+			let image = load_context.read_asset_bytes(dependency_path(load_context.path(), &file.image)).await?;
+			let image = decode_image(&image, None)?;
+
+			let clips = file.tags.iter().map(|tag| (tag.name.clone(), load_context.add_labeled_asset(format!("tags/{}", tag.name), file.clip(tag)))).collect();
+			let atlas = load_context.add_labeled_asset("atlas".to_string(), file.atlas(Handle::default()));
+			let slices = file.slices.into_iter().map(|slice| (slice.name.clone(), slice)).collect();
+
+			Ok(AsepriteSheet {
+				atlas,
+				clips,
+				slices,
+				image: Some(image),
+			})
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["aseprite.json"]
+	}
+}
+
+/// Uploads the images of newly loaded sheets and assigns them to their atlases
+pub(crate) fn upload_aseprite_textures(
+	mut backend: NonSendMut<RenderingBackend>,
+	mut sheets: ResMut<Assets<AsepriteSheet>>,
+	mut atlases: ResMut<Assets<TextureAtlas>>,
+	mut textures: ResMut<Assets<Texture>>,
+) {
+	let pending: Vec<_> = sheets.iter().filter(|(_, sheet)| sheet.image.is_some()).map(|(id, _)| id).collect();
+
+	for id in pending {
+		let sheet = sheets.get_mut(id).unwrap();
+		let image = sheet.image.take().unwrap();
+
+		let texture = backend.new_texture_from_rgba8(image.width() as u16, image.height() as u16, &image.into_raw());
+		if let Some(atlas) = atlases.get_mut(&sheet.atlas) {
+			atlas.texture = textures.add(Texture::new(texture));
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct RawFile {
+	frames: serde_json::Value,
+	meta: RawMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMeta {
+	image: String,
+	size: RawSize,
+	#[serde(default)]
+	frame_tags: Vec<RawTag>,
+	#[serde(default)]
+	slices: Vec<RawSlice>,
+}
+
+#[derive(Deserialize)]
+struct RawFrame {
+	filename: Option<String>,
+	frame: RawRect,
+	#[serde(default)]
+	rotated: bool,
+	#[serde(default = "default_duration")]
+	duration: f32,
+}
+
+fn default_duration() -> f32 {
+	100.0
+}
+
+#[derive(Deserialize)]
+struct RawTag {
+	name: String,
+	from: usize,
+	to: usize,
+	#[serde(default)]
+	direction: String,
+}
+
+#[derive(Deserialize)]
+struct RawSlice {
+	name: String,
+	keys: Vec<RawSliceKey>,
+}
+
+#[derive(Deserialize)]
+struct RawSliceKey {
+	frame: usize,
+	bounds: RawRect,
+	center: Option<RawRect>,
+	pivot: Option<RawPoint>,
+}
+
+#[derive(Deserialize)]
+struct RawRect {
+	x: f32,
+	y: f32,
+	w: f32,
+	h: f32,
+}
+
+impl From<RawRect> for Vec4 {
+	fn from(rect: RawRect) -> Self {
+		vec4(rect.x, rect.y, rect.w, rect.h)
+	}
+}
+
+#[derive(Deserialize)]
+struct RawSize {
+	w: f32,
+	h: f32,
+}
+
+#[derive(Deserialize)]
+struct RawPoint {
+	x: f32,
+	y: f32,
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use bevy_asset::{Asset, Assets, Handle};
+use bevy_reflect::Reflect;
+use glam::{vec2, vec4, Vec2, Vec4};
+use image::{GenericImage, RgbaImage};
+
+use super::Texture;
+use crate::render::RenderingBackend;
+
+/// Regions of a single [`Texture`], addressed by index or by name. Rects are `(x, y, width, height)` in pixels, the same as [`Sprite::rect`](crate::prelude::Sprite::rect).
+///
+/// Built either from a grid with [`TextureAtlas::from_grid`], or by packing many images into one texture with a [`TextureAtlasBuilder`].
+/// Drawing many regions of one texture instead of many textures keeps the geometry in a single draw call.
+#[derive(Asset, Clone, Debug, Reflect)]
+pub struct TextureAtlas {
+	pub texture: Handle<Texture>,
+	/// Size of the texture in pixels
+	pub size: Vec2,
+	rects: Vec<Vec4>,
+	names: HashMap<String, usize>,
+}
+
+impl TextureAtlas {
+	/// Creates an atlas without any regions
+	pub fn new(texture: Handle<Texture>, size: Vec2) -> Self {
+		Self {
+			texture,
+			size,
+			rects: Vec::new(),
+			names: HashMap::new(),
+		}
+	}
+
+	/// Slices the texture into `columns * rows` tiles of `tile_size`, indexed row by row. `padding` is the space between tiles,
+	/// and `offset` is the position of the first tile.
+	pub fn from_grid(texture: Handle<Texture>, size: Vec2, tile_size: Vec2, columns: usize, rows: usize, padding: Option<Vec2>, offset: Option<Vec2>) -> Self {
+		let (padding, offset) = (padding.unwrap_or(Vec2::ZERO), offset.unwrap_or(Vec2::ZERO));
+		let mut atlas = Self::new(texture, size);
+
+		for row in 0..rows {
+			for column in 0..columns {
+				let position = offset + vec2(column as f32, row as f32) * (tile_size + padding);
+				atlas.add_rect(vec4(position.x, position.y, tile_size.x, tile_size.y));
+			}
+		}
+
+		atlas
+	}
+
+	/// Adds a region, returning its index
+	pub fn add_rect(&mut self, rect: Vec4) -> usize {
+		self.rects.push(rect);
+		self.rects.len() - 1
+	}
+
+	/// Adds a region that can be also looked up by name, returning its index
+	pub fn add_named_rect(&mut self, name: impl Into<String>, rect: Vec4) -> usize {
+		let index = self.add_rect(rect);
+		self.names.insert(name.into(), index);
+		index
+	}
+
+	pub fn len(&self) -> usize {
+		self.rects.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.rects.is_empty()
+	}
+
+	pub fn rects(&self) -> &[Vec4] {
+		&self.rects
+	}
+
+	/// Region in pixels
+	pub fn rect(&self, index: usize) -> Option<Vec4> {
+		self.rects.get(index).copied()
+	}
+
+	/// Region in UV coordinates (`0..1`), for example for meshes or [`InstanceData::with_uv_rect`](crate::prelude::InstanceData::with_uv_rect)
+	pub fn uv_rect(&self, index: usize) -> Option<Vec4> {
+		self.rect(index).map(|rect| rect / vec4(self.size.x, self.size.y, self.size.x, self.size.y))
+	}
+
+	/// Index of the region added with [`TextureAtlas::add_named_rect`]
+	pub fn index_of(&self, name: &str) -> Option<usize> {
+		self.names.get(name).copied()
+	}
+}
+
+/// Packs images into a single texture at runtime, creating a [`TextureAtlas`] of them.
+///
+/// Regions keep the order in which the images were added.
+pub struct TextureAtlasBuilder {
+	images: Vec<(Option<String>, RgbaImage)>,
+	padding: u32,
+	max_size: (u32, u32),
+}
+
+impl Default for TextureAtlasBuilder {
+	fn default() -> Self {
+		Self {
+			images: Vec::new(),
+			padding: 0,
+			max_size: (2048, 2048),
+		}
+	}
+}
+
+/// Error returned by [`TextureAtlasBuilder`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureAtlasBuilderError {
+	/// The images don't fit into a texture of the max size
+	NotEnoughSpace { max_size: (u32, u32) },
+}
+
+impl std::fmt::Display for TextureAtlasBuilderError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::NotEnoughSpace { max_size } => write!(f, "The images don't fit into a {}x{} texture", max_size.0, max_size.1),
+		}
+	}
+}
+
+impl std::error::Error for TextureAtlasBuilderError {}
+
+impl TextureAtlasBuilder {
+	/// Adds an image, which region will have the next index
+	pub fn add_image(&mut self, image: RgbaImage) -> &mut Self {
+		self.images.push((None, image));
+		self
+	}
+
+	/// Adds an image, which region can be also looked up by name
+	pub fn add_named_image(&mut self, name: impl Into<String>, image: RgbaImage) -> &mut Self {
+		self.images.push((Some(name.into()), image));
+		self
+	}
+
+	/// Transparent space between the packed images, in pixels. Useful to avoid texture bleeding with linear filtering. Default is `0`
+	pub fn padding(&mut self, padding: u32) -> &mut Self {
+		self.padding = padding;
+		self
+	}
+
+	/// The biggest texture the images can be packed into. Default is `2048x2048`
+	pub fn max_size(&mut self, width: u32, height: u32) -> &mut Self {
+		self.max_size = (width, height);
+		self
+	}
+
+	/// Packs the images into one image, returning it with the regions of the images (in the order of addition).
+	/// The image size is the smallest power of two that fits all of them.
+	pub fn pack(&self) -> Result<(RgbaImage, Vec<Vec4>), TextureAtlasBuilderError> {
+		let sizes: Vec<_> = self.images.iter().map(|(_, image)| (image.width() + self.padding, image.height() + self.padding)).collect();
+		let area: u32 = sizes.iter().map(|(w, h)| w * h).sum();
+
+		let mut width = (area as f32).sqrt().ceil().max(1.0) as u32;
+		width = width.next_power_of_two().min(self.max_size.0);
+		let mut height = width.min(self.max_size.1);
+
+		loop {
+			if let Some(positions) = pack_shelves(&sizes, width, height) {
+				let mut packed = RgbaImage::new(width, height);
+				let mut rects = Vec::with_capacity(self.images.len());
+
+				for ((_, image), (x, y)) in self.images.iter().zip(positions) {
+					packed.copy_from(image, x, y).expect("Packed image is out of bounds");
+					rects.push(vec4(x as f32, y as f32, image.width() as f32, image.height() as f32));
+				}
+
+				return Ok((packed, rects));
+			}
+
+			// Grow the smaller side first, keeping the texture close to a square
+			if width <= height && width < self.max_size.0 {
+				width = (width * 2).min(self.max_size.0);
+			} else if height < self.max_size.1 {
+				height = (height * 2).min(self.max_size.1);
+			} else if width < self.max_size.0 {
+				width = (width * 2).min(self.max_size.0);
+			} else {
+				return Err(TextureAtlasBuilderError::NotEnoughSpace { max_size: self.max_size });
+			}
+		}
+	}
+
+	/// Packs the images, uploads the result to the GPU and adds it to the textures
+	pub fn build(&self, backend: &mut RenderingBackend, textures: &mut Assets<Texture>) -> Result<TextureAtlas, TextureAtlasBuilderError> {
+		let (image, rects) = self.pack()?;
+		let (width, height) = image.dimensions();
+
+		let texture = backend.new_texture_from_rgba8(width as u16, height as u16, &image.into_raw());
+		let mut atlas = TextureAtlas::new(textures.add(Texture::new(texture)), vec2(width as f32, height as f32));
+
+		for ((name, _), rect) in self.images.iter().zip(rects) {
+			match name {
+				Some(name) => atlas.add_named_rect(name.clone(), rect),
+				None => atlas.add_rect(rect),
+			};
+		}
+
+		Ok(atlas)
+	}
+}
+
+/// Places the rectangles on shelves, tallest first. Returns their positions in the original order, or `None` if they don't fit
+fn pack_shelves(sizes: &[(u32, u32)], width: u32, height: u32) -> Option<Vec<(u32, u32)>> {
+	let mut order: Vec<usize> = (0..sizes.len()).collect();
+	order.sort_by(|a, b| sizes[*b].1.cmp(&sizes[*a].1).then(sizes[*b].0.cmp(&sizes[*a].0)));
+
+	let mut positions = vec![(0, 0); sizes.len()];
+	let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+	for index in order {
+		let (w, h) = sizes[index];
+		if w > width {
+			return None;
+		}
+
+		if x + w > width {
+			y += shelf_height;
+			x = 0;
+			shelf_height = 0;
+		}
+		if y + h > height {
+			return None;
+		}
+
+		positions[index] = (x, y);
+		x += w;
+		shelf_height = shelf_height.max(h);
+	}
+
+	Some(positions)
+}
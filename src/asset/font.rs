@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+use bevy_asset::{io::Reader, Asset, AssetLoader, Assets, AsyncReadExt, BoxedFuture, Handle, LoadContext, ReadAssetBytesError};
+use bevy_ecs::system::{NonSendMut, ResMut};
+use bevy_reflect::TypePath;
+use glam::{vec2, vec3, vec4, Vec2, Vec4};
+use image::RgbaImage;
+
+use super::io::{decode_image, dependency_path};
+use super::Texture;
+use crate::render::{geometry::Vertex, rgba::Rgba, RenderingBackend};
+
+/// A character of a [`Font`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+	/// Region of the glyph in its page as `(x, y, width, height)` in pixels
+	pub rect: Vec4,
+	/// Offset of the glyph's top left corner from the pen position, in pixels (`y` points down)
+	pub offset: Vec2,
+	/// How far the pen moves after the glyph, in pixels
+	pub advance: f32,
+	/// Index of the page texture holding the glyph
+	pub page: usize,
+}
+
+/// A textured quad of a single glyph, as produced by [`Font::glyph_quads`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphQuad {
+	/// Index of the page texture to draw the quad with
+	pub page: usize,
+	/// Top-left, top-right, bottom-left and bottom-right corners, drawn with [`Sprite::INDICES`](crate::prelude::Sprite::INDICES)
+	pub vertices: [Vertex; 4],
+}
+
+/// How the distances of a [`DistanceField`] font are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceFieldType {
+	/// A single channel, copied into every color channel
+	#[default]
+	Sdf,
+	/// A single channel of perpendicular distances
+	Psdf,
+	/// Three channels, with the distance being their median
+	Msdf,
+	/// Three channels like [`Msdf`](DistanceFieldType::Msdf), plus the true distance in alpha
+	Mtsdf,
+}
+
+impl DistanceFieldType {
+	/// Parses the lowercase name used by msdfgen tools
+	pub(crate) fn from_name(name: &str) -> Result<Self, FontError> {
+		match name {
+			"sdf" => Ok(Self::Sdf),
+			"psdf" => Ok(Self::Psdf),
+			"msdf" => Ok(Self::Msdf),
+			"mtsdf" => Ok(Self::Mtsdf),
+			_ => Err(FontError::Invalid(format!("unknown distance field type `{}`", name))),
+		}
+	}
+}
+
+/// Marks the pages of a [`Font`] as distance fields, to be drawn with the [SDF material](crate::prelude::RenderingBackend::request_sdf_material)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceField {
+	pub field_type: DistanceFieldType,
+	/// Distance covered by the field around the glyph edges, in pixels of the pages
+	pub range: f32,
+}
+
+/// A bitmap font, with its glyphs pre-rendered into page textures
+#[derive(Asset, TypePath, Debug)]
+pub struct Font {
+	/// Font size the glyphs were rendered at, in pixels
+	pub size: f32,
+	/// Distance between two lines, in pixels
+	pub line_height: f32,
+	/// Distance from the top of a line to the baseline, in pixels
+	pub base: f32,
+	/// Size of every page texture in pixels
+	pub page_size: Vec2,
+	/// Page textures. Loaded fonts get them in [`PreUpdate`](bevy_app::PreUpdate) of the frame the font is loaded
+	pub pages: Vec<Handle<Texture>>,
+	/// Set for fonts whose pages hold distance fields instead of coverage
+	pub distance_field: Option<DistanceField>,
+	glyphs: HashMap<char, Glyph>,
+	kerning: HashMap<(char, char), f32>,
+	pub(crate) page_images: Vec<RgbaImage>,
+}
+
+impl Font {
+	pub fn glyph(&self, c: char) -> Option<&Glyph> {
+		self.glyphs.get(&c)
+	}
+
+	/// Additional advance between two consecutive characters, in pixels
+	pub fn kerning(&self, first: char, second: char) -> f32 {
+		self.kerning.get(&(first, second)).copied().unwrap_or(0.0)
+	}
+
+	/// Quads of the text glyphs, where `position` is the top left corner of the first line. Every line moves down by the line height,
+	/// and `scale` multiplies the font's pixel size. Characters missing in the font are skipped.
+	pub fn glyph_quads(&self, text: &str, position: Vec2, scale: f32, color: Rgba) -> Vec<GlyphQuad> {
+		let mut quads = Vec::with_capacity(text.len());
+		let mut pen = position;
+		let mut previous = None;
+
+		for c in text.chars() {
+			if c == '\n' {
+				pen = vec2(position.x, pen.y - self.line_height * scale);
+				previous = None;
+				continue;
+			}
+
+			let glyph = match self.glyph(c) {
+				Some(glyph) => glyph,
+				None => continue,
+			};
+			if let Some(previous) = previous {
+				pen.x += self.kerning(previous, c) * scale;
+			}
+			previous = Some(c);
+
+			if let Some(quad) = self.glyph_quad(glyph, pen, scale, color) {
+				quads.push(quad);
+			}
+			pen.x += glyph.advance * scale;
+		}
+
+		quads
+	}
+
+	/// Quad of the glyph with the pen at the top of the line, or `None` for empty glyphs like spaces
+	pub(crate) fn glyph_quad(&self, glyph: &Glyph, pen: Vec2, scale: f32, color: Rgba) -> Option<GlyphQuad> {
+		if glyph.rect.z <= 0.0 || glyph.rect.w <= 0.0 {
+			return None;
+		}
+
+		let (x, y) = (pen.x + glyph.offset.x * scale, pen.y - glyph.offset.y * scale);
+		let (w, h) = (glyph.rect.z * scale, glyph.rect.w * scale);
+		let uv = glyph.rect / vec4(self.page_size.x, self.page_size.y, self.page_size.x, self.page_size.y);
+		let corner = |dx: f32, dy: f32, u: f32, v: f32| Vertex::new(vec3(x + dx, y - dy, 0.0), vec2(u, v), color);
+
+		Some(GlyphQuad {
+			page: glyph.page,
+			vertices: [
+				corner(0.0, 0.0, uv.x, uv.y),
+				corner(w, 0.0, uv.x + uv.z, uv.y),
+				corner(0.0, h, uv.x, uv.y + uv.w),
+				corner(w, h, uv.x + uv.z, uv.y + uv.w),
+			],
+		})
+	}
+}
+
+/// Error of parsing and loading BMFont files
+#[derive(Debug)]
+pub enum FontError {
+	Io(std::io::Error),
+	/// A required tag or attribute is missing or malformed
+	Invalid(String),
+	/// A distance field atlas descriptor isn't valid JSON
+	Json(serde_json::Error),
+	/// A page texture couldn't be loaded
+	Page(ReadAssetBytesError),
+	Decode(image::ImageError),
+}
+
+impl std::fmt::Display for FontError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Io(err) => write!(f, "Failed to read the font: {}", err),
+			Self::Invalid(reason) => write!(f, "Invalid font file: {}", reason),
+			Self::Json(err) => write!(f, "Failed to parse the atlas descriptor: {}", err),
+			Self::Page(err) => write!(f, "Failed to load a font page: {}", err),
+			Self::Decode(err) => write!(f, "Failed to decode a font page: {}", err),
+		}
+	}
+}
+
+impl std::error::Error for FontError {}
+
+impl From<std::io::Error> for FontError {
+	fn from(err: std::io::Error) -> Self {
+		Self::Io(err)
+	}
+}
+
+impl From<serde_json::Error> for FontError {
+	fn from(err: serde_json::Error) -> Self {
+		Self::Json(err)
+	}
+}
+
+impl From<ReadAssetBytesError> for FontError {
+	fn from(err: ReadAssetBytesError) -> Self {
+		Self::Page(err)
+	}
+}
+
+impl From<image::ImageError> for FontError {
+	fn from(err: image::ImageError) -> Self {
+		Self::Decode(err)
+	}
+}
+
+/// An AngelCode BMFont descriptor (`.fnt`), in either the text or the XML format.
+/// Distance field fonts are recognized by the `distanceField` tag written by msdf-bmfont
+#[derive(Debug, Clone, PartialEq)]
+pub struct BmFont {
+	pub size: f32,
+	pub line_height: f32,
+	pub base: f32,
+	pub page_size: Vec2,
+	/// Page image paths by page id, relative to the `.fnt` file
+	pub pages: Vec<String>,
+	pub glyphs: HashMap<char, Glyph>,
+	pub kerning: HashMap<(char, char), f32>,
+	pub distance_field: Option<DistanceField>,
+}
+
+impl BmFont {
+	pub fn from_slice(bytes: &[u8]) -> Result<Self, FontError> {
+		let source = String::from_utf8_lossy(bytes);
+		let mut font = Self {
+			size: 0.0,
+			line_height: 0.0,
+			base: 0.0,
+			page_size: Vec2::ZERO,
+			pages: Vec::new(),
+			glyphs: HashMap::new(),
+			kerning: HashMap::new(),
+			distance_field: None,
+		};
+		let mut has_common = false;
+
+		for (tag, attributes) in tags(&source) {
+			let get = |name: &str| attributes.iter().find(|(key, _)| *key == name).map(|(_, value)| *value);
+			let number = |name: &str| -> Result<f32, FontError> {
+				get(name)
+					.and_then(|value| value.parse().ok())
+					.ok_or_else(|| FontError::Invalid(format!("`{}` needs a numeric `{}` attribute", tag, name)))
+			};
+			let character = |name: &str| number(name).and_then(|id| char::from_u32(id as u32).ok_or_else(|| FontError::Invalid(format!("{} isn't a character", id))));
+
+			match tag {
+				// Sizes can be negative to tell a matching of the cell height instead of the character height
+				"info" => font.size = number("size")?.abs(),
+				"common" => {
+					font.line_height = number("lineHeight")?;
+					font.base = number("base")?;
+					font.page_size = vec2(number("scaleW")?, number("scaleH")?);
+					has_common = true;
+				}
+				"page" => {
+					let id = number("id")? as usize;
+					let file = get("file").ok_or_else(|| FontError::Invalid("`page` needs a `file` attribute".to_string()))?;
+					if font.pages.len() <= id {
+						font.pages.resize(id + 1, String::new());
+					}
+					font.pages[id] = file.to_string();
+				}
+				"char" => {
+					let glyph = Glyph {
+						rect: vec4(number("x")?, number("y")?, number("width")?, number("height")?),
+						offset: vec2(number("xoffset")?, number("yoffset")?),
+						advance: number("xadvance")?,
+						page: number("page").unwrap_or(0.0) as usize,
+					};
+					font.glyphs.insert(character("id")?, glyph);
+				}
+				"kerning" => {
+					font.kerning.insert((character("first")?, character("second")?), number("amount")?);
+				}
+				"distanceField" => {
+					font.distance_field = Some(DistanceField {
+						field_type: DistanceFieldType::from_name(get("fieldType").unwrap_or("sdf"))?,
+						range: number("distanceRange")?,
+					});
+				}
+				_ => {}
+			}
+		}
+
+		if !has_common {
+			return Err(FontError::Invalid("the `common` tag is missing".to_string()));
+		}
+		if let Some(glyph) = font.glyphs.values().find(|glyph| glyph.page >= font.pages.len()) {
+			return Err(FontError::Invalid(format!("page {} isn't declared", glyph.page)));
+		}
+
+		Ok(font)
+	}
+
+	/// Creates the font asset with the page textures, in the order of [`BmFont::pages`]
+	pub fn into_font(self, pages: Vec<Handle<Texture>>) -> Font {
+		Font {
+			size: self.size,
+			line_height: self.line_height,
+			base: self.base,
+			page_size: self.page_size,
+			pages,
+			distance_field: self.distance_field,
+			glyphs: self.glyphs,
+			kerning: self.kerning,
+			page_images: Vec::new(),
+		}
+	}
+}
+
+/// Splits both the text and the XML format into tags with their `key=value` attributes.
+/// Text lines and XML elements look the same after dropping the angle brackets
+fn tags(source: &str) -> Vec<(&str, Vec<(&str, &str)>)> {
+	let is_xml = source.trim_start().starts_with('<');
+	let elements: Vec<&str> = match is_xml {
+		true => source.split('<').map(|element| element.split('>').next().unwrap_or("").trim_end_matches('/')).collect(),
+		false => source.lines().collect(),
+	};
+
+	elements
+		.into_iter()
+		.filter_map(|element| {
+			let element = element.trim();
+			let (tag, mut rest) = element.split_once(char::is_whitespace).unwrap_or((element, ""));
+			if tag.is_empty() || tag.starts_with(['?', '!', '/']) {
+				return None;
+			}
+
+			let mut attributes = Vec::new();
+			while let Some((key, value)) = rest.split_once('=') {
+				let value = value.trim_start();
+				let (value, remaining) = match value.strip_prefix('"') {
+					Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+					None => value.split_once(char::is_whitespace).unwrap_or((value, "")),
+				};
+				attributes.push((key.trim(), value));
+				rest = remaining;
+			}
+
+			Some((tag, attributes))
+		})
+		.collect()
+}
+
+/// Loads AngelCode BMFont `.fnt` files, in the text or XML format. The page images are read from the same asset source, relative to the font.
+#[derive(Default)]
+pub struct BmFontLoader;
+
+impl AssetLoader for BmFontLoader {
+	type Asset = Font;
+	type Settings = ();
+	type Error = FontError;
+
+	fn load<'a>(&'a self, reader: &'a mut Reader, _settings: &'a (), load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<Font, FontError>> {
+		Box::pin(async move {
+			let mut bytes = Vec::new();
+			reader.read_to_end(&mut bytes).await?;
+			let descriptor = BmFont::from_slice(&bytes)?;
+
+			let mut page_images = Vec::with_capacity(descriptor.pages.len());
+			for page in &descriptor.pages {
+				let image = load_context.read_asset_bytes(dependency_path(load_context.path(), page)).await?;
+				page_images.push(decode_image(&image, None)?);
+			}
+
+			let mut font = descriptor.into_font(Vec::new());
+			font.page_images = page_images;
+			Ok(font)
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["fnt"]
+	}
+}
+
+/// Uploads the page images of newly loaded fonts
+pub(crate) fn upload_font_pages(mut backend: NonSendMut<RenderingBackend>, mut fonts: ResMut<Assets<Font>>, mut textures: ResMut<Assets<Texture>>) {
+	let pending: Vec<_> = fonts.iter().filter(|(_, font)| !font.page_images.is_empty()).map(|(id, _)| id).collect();
+
+	for id in pending {
+		let font = fonts.get_mut(id).unwrap();
+		for image in std::mem::take(&mut font.page_images) {
+			let texture = backend.new_texture_from_rgba8(image.width() as u16, image.height() as u16, &image.into_raw());
+			font.pages.push(textures.add(Texture::new(texture)));
+		}
+	}
+}
@@ -21,29 +21,33 @@ fn load_texture(path: impl Into<&'static str>, format: Option<image::ImageFormat
 		}
 	};
 
-	let img = if let Some(fmt) = format {
-		match image::load_from_memory_with_format(&bytes, fmt) {
-			Ok(img) => img.to_rgba8(),
-			Err(err) => {
-				#[cfg(feature = "log")]
-				bevy_log::error!("{:?}", err);
-				return None;
-			}
-		}
-	} else {
-		match image::load_from_memory(&bytes) {
-			Ok(img) => img.to_rgba8(),
-			Err(err) => {
-				#[cfg(feature = "log")]
-				bevy_log::error!("{:?}", err);
-				return None;
-			}
+	let img = match decode_image(&bytes, format) {
+		Ok(img) => img,
+		Err(err) => {
+			#[cfg(feature = "log")]
+			bevy_log::error!("{:?}", err);
+			return None;
 		}
 	};
 	let texture = backend.new_texture_from_rgba8(img.width() as u16, img.height() as u16, &img.into_raw());
 	Some(texture)
 }
 
+/// Decodes an image into RGBA8 pixels, guessing the format from the bytes if it's not provided
+pub(crate) fn decode_image(bytes: &[u8], format: Option<image::ImageFormat>) -> Result<image::RgbaImage, image::ImageError> {
+	let img = match format {
+		Some(fmt) => image::load_from_memory_with_format(bytes, fmt)?,
+		None => image::load_from_memory(bytes)?,
+	};
+	Ok(img.to_rgba8())
+}
+
+/// Path of a file referenced by an asset, like a sheet image, relative to the directory of the asset.
+/// The result is an asset path, to be read with [`LoadContext::read_asset_bytes`](bevy_asset::LoadContext::read_asset_bytes)
+pub(crate) fn dependency_path(asset: &std::path::Path, path: &str) -> std::path::PathBuf {
+	asset.parent().map_or_else(|| path.into(), |parent| parent.join(path))
+}
+
 struct Null;
 
 /// A syntatic sugar for loading files, textures and materials on the same thread. This simplifies marking systems as exclusive, and
@@ -1,14 +1,29 @@
-use bevy_app::Plugin;
+use bevy_app::{Last, Plugin, PreUpdate};
 use bevy_asset::{Asset, AssetApp, Assets};
 use bevy_asset::{AssetLoader, AssetPlugin as BevyAssetPlugin};
 use bevy_reflect::Reflect;
+use bevy_tasks::{AsyncComputeTaskPool, ComputeTaskPool, IoTaskPool, TaskPool};
 use miniquad::{ShaderMeta, ShaderSource, TextureId};
 
 use crate::prelude::material::Material;
 use crate::prelude::Mesh;
 
+pub mod aseprite;
+pub mod atlas;
+pub mod font;
 pub mod io;
+pub mod obj;
+pub mod sdf;
+pub mod svg;
+pub mod ttf;
+pub use aseprite::*;
+pub use atlas::*;
+pub use font::*;
 pub use io::*;
+pub use obj::*;
+pub use sdf::*;
+pub use svg::*;
+pub use ttf::*;
 
 // ? I'm using Option here to workaround rendering types not implementing Default trait. If there's a better way
 // ? of course - it would be great!
@@ -30,15 +45,40 @@ impl Texture {
 	}
 }
 
+/// The asset server loads files on the global task pools, which bevy normally sets up (and ticks) in `TaskPoolPlugin`.
+/// The pools are single threaded here, so their tasks run on the main thread at the end of each frame
+fn tick_task_pools() {
+	#[cfg(not(target_arch = "wasm32"))]
+	bevy_tasks::tick_global_task_pools_on_main_thread();
+}
+
 pub struct AssetPlugin;
 impl Plugin for AssetPlugin {
 	fn build(&self, app: &mut bevy_app::App) {
+		ComputeTaskPool::get_or_init(TaskPool::default);
+		AsyncComputeTaskPool::get_or_init(TaskPool::default);
+		IoTaskPool::get_or_init(TaskPool::default);
+
 		app.add_plugins(BevyAssetPlugin::default())
+			.init_asset::<Mesh>()
 			.register_asset_reflect::<Mesh>()
-			.init_resource::<Assets<Mesh>>()
 			.register_asset_reflect::<Texture>()
 			.init_resource::<Assets<Texture>>()
 			.register_asset_reflect::<Material>()
-			.init_resource::<Assets<Material>>();
+			.init_resource::<Assets<Material>>()
+			.init_asset::<TextureAtlas>()
+			.register_asset_reflect::<TextureAtlas>()
+			.init_asset::<AsepriteSheet>()
+			.init_asset_loader::<AsepriteLoader>()
+			.init_asset::<Font>()
+			.init_asset_loader::<BmFontLoader>()
+			.init_asset_loader::<SdfAtlasLoader>()
+			.init_asset::<TtfFont>()
+			.init_asset_loader::<TtfFontLoader>()
+			.init_asset::<Svg>()
+			.init_asset_loader::<SvgLoader>()
+			.init_asset_loader::<ObjLoader>()
+			.add_systems(PreUpdate, (aseprite::upload_aseprite_textures, font::upload_font_pages))
+			.add_systems(Last, tick_task_pools);
 	}
 }
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use bevy_asset::{io::Reader, AssetLoader, AsyncReadExt, BoxedFuture, LoadContext, ReadAssetBytesError};
+use glam::{vec2, vec3, Vec2, Vec3};
+
+use super::io::dependency_path;
+use crate::render::geometry::{Mesh, Vertex};
+use crate::render::rgba::{Rgba, WHITE};
+
+#[derive(Debug)]
+pub enum ObjError {
+	Io(std::io::Error),
+	/// A statement is malformed, or a face points at a missing vertex
+	Invalid(String),
+	/// A material library couldn't be loaded
+	Material(ReadAssetBytesError),
+}
+
+impl std::fmt::Display for ObjError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Io(err) => write!(f, "Failed to read the OBJ file: {}", err),
+			Self::Invalid(reason) => write!(f, "Invalid OBJ file: {}", reason),
+			Self::Material(err) => write!(f, "Failed to load a material library: {}", err),
+		}
+	}
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<std::io::Error> for ObjError {
+	fn from(err: std::io::Error) -> Self {
+		Self::Io(err)
+	}
+}
+
+impl From<ReadAssetBytesError> for ObjError {
+	fn from(err: ReadAssetBytesError) -> Self {
+		Self::Material(err)
+	}
+}
+
+/// Diffuse colors of the materials of a Wavefront `.mtl` library by name, from `Kd` with the `d` (or `1 - Tr`) dissolve as alpha
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MtlFile {
+	pub materials: HashMap<String, Rgba>,
+}
+
+impl MtlFile {
+	pub fn from_slice(bytes: &[u8]) -> Result<Self, ObjError> {
+		let source = String::from_utf8_lossy(bytes);
+		let mut materials = HashMap::new();
+		let mut current: Option<(String, Vec3, f32)> = None;
+
+		for (number, line) in statements(&source) {
+			let mut words = line.split_whitespace();
+			match words.next() {
+				Some("newmtl") => {
+					if let Some((name, diffuse, alpha)) = current.take() {
+						materials.insert(name, color(diffuse, alpha));
+					}
+					current = Some((words.collect::<Vec<_>>().join(" "), Vec3::ONE, 1.0));
+				}
+				Some("Kd") => match &mut current {
+					Some((_, diffuse, _)) => *diffuse = vec3(float(&mut words, number)?, float(&mut words, number)?, float(&mut words, number)?),
+					None => return Err(invalid(number, "Kd before newmtl")),
+				},
+				Some(keyword @ ("d" | "Tr")) => match &mut current {
+					Some((_, _, alpha)) => {
+						let value = float(&mut words, number)?;
+						*alpha = if keyword == "d" { value } else { 1.0 - value };
+					}
+					None => return Err(invalid(number, "dissolve before newmtl")),
+				},
+				_ => {}
+			}
+		}
+		if let Some((name, diffuse, alpha)) = current {
+			materials.insert(name, color(diffuse, alpha));
+		}
+
+		Ok(Self { materials })
+	}
+}
+
+/// A corner of a face, pointing into the attributes of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Corner {
+	position: usize,
+	uv: Option<usize>,
+	material: Option<usize>,
+}
+
+/// Triangles of an `o` object or a `g` group
+#[derive(Debug, Clone, PartialEq)]
+struct Object {
+	name: String,
+	corners: Vec<Corner>,
+	indices: Vec<u32>,
+	lookup: HashMap<Corner, u32>,
+}
+
+/// A Wavefront `.obj` model with positions, texture coordinates and triangulated faces.
+/// Vertex colors are read from the common `v x y z r g b` extension, and polygons are split into triangle fans, so they should be convex.
+/// Normals, lines and free-form geometry are skipped.
+///
+/// Objects and groups are kept apart, with faces before any of them going to an unnamed object
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjFile {
+	/// Names of the `.mtl` files of `mtllib` statements, relative to the model
+	pub material_libraries: Vec<String>,
+	positions: Vec<Vec3>,
+	colors: Vec<Option<Vec3>>,
+	uvs: Vec<Vec2>,
+	/// Names of the `usemtl` materials
+	materials: Vec<String>,
+	objects: Vec<Object>,
+}
+
+impl ObjFile {
+	pub fn from_slice(bytes: &[u8]) -> Result<Self, ObjError> {
+		let source = String::from_utf8_lossy(bytes);
+		let mut file = Self {
+			material_libraries: Vec::new(),
+			positions: Vec::new(),
+			colors: Vec::new(),
+			uvs: Vec::new(),
+			materials: Vec::new(),
+			objects: Vec::new(),
+		};
+		let mut object = 0;
+		let mut material = None;
+
+		for (number, line) in statements(&source) {
+			let mut words = line.split_whitespace();
+			match words.next() {
+				Some("v") => {
+					let values = words.map(|word| word.parse::<f32>().map_err(|_| invalid(number, "malformed vertex"))).collect::<Result<Vec<_>, _>>()?;
+					let (position, color) = match values[..] {
+						// The fourth value is a rarely used weight
+						[x, y, z] | [x, y, z, _] => (vec3(x, y, z), None),
+						[x, y, z, r, g, b] | [x, y, z, r, g, b, _] => (vec3(x, y, z), Some(vec3(r, g, b))),
+						_ => return Err(invalid(number, "a vertex needs 3 coordinates, and optionally 3 color channels")),
+					};
+					file.positions.push(position);
+					file.colors.push(color);
+				}
+				Some("vt") => {
+					let u = float(&mut words, number)?;
+					// Texture coordinates point up in OBJ files, and down in textures
+					let v = match words.next() {
+						Some(word) => word.parse::<f32>().map_err(|_| invalid(number, "malformed texture coordinate"))?,
+						None => 0.0,
+					};
+					file.uvs.push(vec2(u, 1.0 - v));
+				}
+				Some("f") => {
+					let corners = words.map(|word| file.corner(word, material, number)).collect::<Result<Vec<_>, _>>()?;
+					if corners.len() < 3 {
+						return Err(invalid(number, "a face needs at least 3 corners"));
+					}
+					if file.objects.is_empty() {
+						file.objects.push(Object::new(String::new()));
+					}
+					let target = &mut file.objects[object];
+					let indices: Vec<u32> = corners.into_iter().map(|corner| target.index(corner)).collect();
+					target.indices.extend((1..indices.len() - 1).flat_map(|i| [indices[0], indices[i], indices[i + 1]]));
+				}
+				Some("o" | "g") => {
+					let name = words.collect::<Vec<_>>().join(" ");
+					// Groups may be split over the file
+					object = match file.objects.iter().position(|existing| existing.name == name) {
+						Some(existing) => existing,
+						None => {
+							file.objects.push(Object::new(name));
+							file.objects.len() - 1
+						}
+					};
+				}
+				Some("usemtl") => {
+					let name = words.collect::<Vec<_>>().join(" ");
+					material = Some(match file.materials.iter().position(|existing| *existing == name) {
+						Some(existing) => existing,
+						None => {
+							file.materials.push(name);
+							file.materials.len() - 1
+						}
+					});
+				}
+				Some("mtllib") => file.material_libraries.extend(words.map(str::to_string)),
+				_ => {}
+			}
+		}
+
+		file.objects.retain(|object| !object.indices.is_empty());
+		Ok(file)
+	}
+
+	/// Meshes of the objects by name in file order, colored by the diffuse color of their materials multiplied by the vertex colors.
+	/// Faces without a material, or with one missing from the library, are white
+	pub fn meshes(&self, materials: &MtlFile) -> Vec<(String, Mesh)> {
+		self.objects
+			.iter()
+			.map(|object| {
+				let vertices = object
+					.corners
+					.iter()
+					.map(|corner| {
+						let diffuse = match corner.material.and_then(|material| materials.materials.get(&self.materials[material])) {
+							Some(diffuse) => *diffuse,
+							None => WHITE,
+						};
+						let color = match self.colors[corner.position] {
+							Some(color) => {
+								let tint = diffuse.to_float();
+								self::color(color * tint.truncate(), tint.w)
+							}
+							None => diffuse,
+						};
+						let uv = corner.uv.map(|uv| self.uvs[uv]).unwrap_or(Vec2::ZERO);
+						Vertex::new(self.positions[corner.position], uv, color)
+					})
+					.collect();
+				(object.name.clone(), Mesh { vertices, indices: object.indices.clone() })
+			})
+			.collect()
+	}
+
+	/// All the objects merged into one mesh, see [`meshes`](ObjFile::meshes)
+	pub fn mesh(&self, materials: &MtlFile) -> Mesh {
+		let meshes = self.meshes(materials);
+		Mesh::merge(meshes.iter().map(|(_, mesh)| mesh))
+	}
+
+	/// Parses a `position/uv/normal` face corner, with negative indices counting back from the last attribute
+	fn corner(&self, word: &str, material: Option<usize>, number: usize) -> Result<Corner, ObjError> {
+		let mut parts = word.split('/');
+		let index = |part: Option<&str>, count: usize| -> Result<Option<usize>, ObjError> {
+			let part = match part {
+				Some(part) if !part.is_empty() => part,
+				_ => return Ok(None),
+			};
+			let index: i64 = part.parse().map_err(|_| invalid(number, "malformed face"))?;
+			let resolved = match index {
+				1.. => index - 1,
+				..=-1 => count as i64 + index,
+				0 => return Err(invalid(number, "face indices start at 1")),
+			};
+			match resolved >= 0 && (resolved as usize) < count {
+				true => Ok(Some(resolved as usize)),
+				false => Err(invalid(number, &format!("face index {} is out of range", index))),
+			}
+		};
+
+		let position = index(parts.next(), self.positions.len())?.ok_or_else(|| invalid(number, "a face corner needs a position"))?;
+		let uv = index(parts.next(), self.uvs.len())?;
+		Ok(Corner { position, uv, material })
+	}
+}
+
+impl Object {
+	fn new(name: String) -> Self {
+		Self {
+			name,
+			corners: Vec::new(),
+			indices: Vec::new(),
+			lookup: HashMap::new(),
+		}
+	}
+
+	/// Index of the vertex of the corner, shared by the faces using the same attributes
+	fn index(&mut self, corner: Corner) -> u32 {
+		let corners = &mut self.corners;
+		*self.lookup.entry(corner).or_insert_with(|| {
+			corners.push(corner);
+			corners.len() as u32 - 1
+		})
+	}
+}
+
+/// Non-empty lines without comments, with their line numbers
+fn statements(source: &str) -> impl Iterator<Item = (usize, &str)> {
+	source
+		.lines()
+		.enumerate()
+		.map(|(i, line)| (i + 1, line.split('#').next().unwrap_or_default().trim()))
+		.filter(|(_, line)| !line.is_empty())
+}
+
+fn invalid(line: usize, reason: &str) -> ObjError {
+	ObjError::Invalid(format!("line {}: {}", line, reason))
+}
+
+fn float<'a>(words: &mut impl Iterator<Item = &'a str>, line: usize) -> Result<f32, ObjError> {
+	words.next().and_then(|word| word.parse().ok()).ok_or_else(|| invalid(line, "expected a number"))
+}
+
+fn color(rgb: Vec3, alpha: f32) -> Rgba {
+	let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+	Rgba::new(channel(rgb.x), channel(rgb.y), channel(rgb.z), channel(alpha))
+}
+
+/// Loads Wavefront `.obj` models as a [`Mesh`] of all their objects, see [`ObjFile`] for the supported features. Each named object or group
+/// is also labeled `objects/<name>`. Material libraries are read from the same asset source, relative to the model.
+#[derive(Default)]
+pub struct ObjLoader;
+
+impl AssetLoader for ObjLoader {
+	type Asset = Mesh;
+	type Settings = ();
+	type Error = ObjError;
+
+	fn load<'a>(&'a self, reader: &'a mut Reader, _settings: &'a (), load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<Mesh, ObjError>> {
+		Box::pin(async move {
+			let mut bytes = Vec::new();
+			reader.read_to_end(&mut bytes).await?;
+			let file = ObjFile::from_slice(&bytes)?;
+
+			let mut materials = MtlFile::default();
+			for library in &file.material_libraries {
+				let bytes = load_context.read_asset_bytes(dependency_path(load_context.path(), library)).await?;
+				materials.materials.extend(MtlFile::from_slice(&bytes)?.materials);
+			}
+
+			let meshes = file.meshes(&materials);
+			for (name, mesh) in meshes.iter().filter(|(name, _)| !name.is_empty()) {
+				load_context.add_labeled_asset(format!("objects/{}", name), mesh.clone());
+			}
+			Ok(Mesh::merge(meshes.iter().map(|(_, mesh)| mesh)))
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["obj"]
+	}
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use bevy_asset::{io::Reader, AssetLoader, AsyncReadExt, BoxedFuture, LoadContext};
+use glam::{vec2, vec4};
+use serde::Deserialize;
+
+use super::io::{decode_image, dependency_path};
+use super::{BmFont, DistanceField, DistanceFieldType, Font, FontError, Glyph};
+
+impl BmFont {
+	/// Parses the JSON descriptor of an msdf-atlas-gen atlas, which has a single page image at `page`.
+	/// Metrics in em units are converted to pixels of the atlas glyph size
+	pub fn from_sdf_atlas(bytes: &[u8], page: String) -> Result<Self, FontError> {
+		let raw: RawAtlas = serde_json::from_slice(bytes)?;
+		let scale = match raw.metrics.em_size > 0.0 {
+			true => raw.atlas.size / raw.metrics.em_size,
+			false => raw.atlas.size,
+		};
+		let y_up = raw.atlas.y_origin != "top";
+		let base = raw.metrics.ascender * scale;
+
+		let mut glyphs = HashMap::with_capacity(raw.glyphs.len());
+		for glyph in raw.glyphs {
+			let c = char::from_u32(glyph.unicode).ok_or_else(|| FontError::Invalid(format!("{} isn't a character", glyph.unicode)))?;
+			let (rect, offset) = match (glyph.atlas_bounds, glyph.plane_bounds) {
+				(Some(atlas), Some(plane)) => {
+					let (low, high) = (atlas.top.min(atlas.bottom), atlas.top.max(atlas.bottom));
+					let y = match y_up {
+						true => raw.atlas.height - high,
+						false => low,
+					};
+					// Plane bounds point up from the baseline unless the origin is at the top
+					let plane_top = match y_up {
+						true => plane.top,
+						false => -plane.top,
+					};
+					(vec4(atlas.left, y, atlas.right - atlas.left, high - low), vec2(plane.left * scale, base - plane_top * scale))
+				}
+				_ => (vec4(0.0, 0.0, 0.0, 0.0), vec2(0.0, 0.0)),
+			};
+
+			glyphs.insert(
+				c,
+				Glyph {
+					rect,
+					offset,
+					advance: glyph.advance * scale,
+					page: 0,
+				},
+			);
+		}
+
+		let mut kerning = HashMap::with_capacity(raw.kerning.len());
+		for pair in raw.kerning {
+			match (char::from_u32(pair.unicode1), char::from_u32(pair.unicode2)) {
+				(Some(first), Some(second)) => kerning.insert((first, second), pair.advance * scale),
+				_ => return Err(FontError::Invalid(format!("kerning of {} and {} isn't between characters", pair.unicode1, pair.unicode2))),
+			};
+		}
+
+		Ok(Self {
+			size: scale,
+			line_height: raw.metrics.line_height * scale,
+			base,
+			page_size: vec2(raw.atlas.width, raw.atlas.height),
+			pages: vec![page],
+			glyphs,
+			kerning,
+			distance_field: Some(DistanceField {
+				field_type: DistanceFieldType::from_name(&raw.atlas.kind)?,
+				range: raw.atlas.distance_range,
+			}),
+		})
+	}
+}
+
+/// Loads distance field atlases generated by msdf-atlas-gen with `-json`, under the `.sdf.json` extension.
+/// The atlas image is expected next to the descriptor, with the same name ending in `.sdf.png`
+#[derive(Default)]
+pub struct SdfAtlasLoader;
+
+impl AssetLoader for SdfAtlasLoader {
+	type Asset = Font;
+	type Settings = ();
+	type Error = FontError;
+
+	fn load<'a>(&'a self, reader: &'a mut Reader, _settings: &'a (), load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<Font, FontError>> {
+		Box::pin(async move {
+			let mut bytes = Vec::new();
+			reader.read_to_end(&mut bytes).await?;
+
+			let page = load_context.path().with_extension("png").file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+			let descriptor = BmFont::from_sdf_atlas(&bytes, page)?;
+			let image = load_context.read_asset_bytes(dependency_path(load_context.path(), &descriptor.pages[0])).await?;
+
+			let mut font = descriptor.into_font(Vec::new());
+			font.page_images = vec![decode_image(&image, None)?];
+			Ok(font)
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["sdf.json"]
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAtlas {
+	atlas: RawAtlasInfo,
+	metrics: RawMetrics,
+	glyphs: Vec<RawGlyph>,
+	#[serde(default)]
+	kerning: Vec<RawKerning>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAtlasInfo {
+	#[serde(rename = "type")]
+	kind: String,
+	distance_range: f32,
+	size: f32,
+	width: f32,
+	height: f32,
+	#[serde(default)]
+	y_origin: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMetrics {
+	#[serde(default)]
+	em_size: f32,
+	line_height: f32,
+	ascender: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawGlyph {
+	unicode: u32,
+	advance: f32,
+	plane_bounds: Option<RawBounds>,
+	atlas_bounds: Option<RawBounds>,
+}
+
+#[derive(Deserialize)]
+struct RawBounds {
+	left: f32,
+	bottom: f32,
+	right: f32,
+	top: f32,
+}
+
+#[derive(Deserialize)]
+struct RawKerning {
+	unicode1: u32,
+	unicode2: u32,
+	advance: f32,
+}
@@ -0,0 +1,1037 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use bevy_asset::{io::Reader, Asset, AssetLoader, AsyncReadExt, BoxedFuture, Handle, LoadContext};
+use bevy_reflect::TypePath;
+use glam::{vec2, Affine2, Vec2};
+
+use crate::render::geometry::Mesh;
+use crate::render::path::{FillRule, Path, PathBuilder};
+use crate::render::rgba::Rgba;
+use crate::render::stroke::{LineCap, LineJoin, StrokeStyle};
+
+/// Largest distance between curves and their flattened segments, in units of the loaded meshes
+const TOLERANCE: f32 = 0.1;
+
+/// The CSS named colors, sorted by name
+const NAMED_COLORS: [(&str, u32); 148] = [
+	("aliceblue", 0xf0f8ff),
+	("antiquewhite", 0xfaebd7),
+	("aqua", 0x00ffff),
+	("aquamarine", 0x7fffd4),
+	("azure", 0xf0ffff),
+	("beige", 0xf5f5dc),
+	("bisque", 0xffe4c4),
+	("black", 0x000000),
+	("blanchedalmond", 0xffebcd),
+	("blue", 0x0000ff),
+	("blueviolet", 0x8a2be2),
+	("brown", 0xa52a2a),
+	("burlywood", 0xdeb887),
+	("cadetblue", 0x5f9ea0),
+	("chartreuse", 0x7fff00),
+	("chocolate", 0xd2691e),
+	("coral", 0xff7f50),
+	("cornflowerblue", 0x6495ed),
+	("cornsilk", 0xfff8dc),
+	("crimson", 0xdc143c),
+	("cyan", 0x00ffff),
+	("darkblue", 0x00008b),
+	("darkcyan", 0x008b8b),
+	("darkgoldenrod", 0xb8860b),
+	("darkgray", 0xa9a9a9),
+	("darkgreen", 0x006400),
+	("darkgrey", 0xa9a9a9),
+	("darkkhaki", 0xbdb76b),
+	("darkmagenta", 0x8b008b),
+	("darkolivegreen", 0x556b2f),
+	("darkorange", 0xff8c00),
+	("darkorchid", 0x9932cc),
+	("darkred", 0x8b0000),
+	("darksalmon", 0xe9967a),
+	("darkseagreen", 0x8fbc8f),
+	("darkslateblue", 0x483d8b),
+	("darkslategray", 0x2f4f4f),
+	("darkslategrey", 0x2f4f4f),
+	("darkturquoise", 0x00ced1),
+	("darkviolet", 0x9400d3),
+	("deeppink", 0xff1493),
+	("deepskyblue", 0x00bfff),
+	("dimgray", 0x696969),
+	("dimgrey", 0x696969),
+	("dodgerblue", 0x1e90ff),
+	("firebrick", 0xb22222),
+	("floralwhite", 0xfffaf0),
+	("forestgreen", 0x228b22),
+	("fuchsia", 0xff00ff),
+	("gainsboro", 0xdcdcdc),
+	("ghostwhite", 0xf8f8ff),
+	("gold", 0xffd700),
+	("goldenrod", 0xdaa520),
+	("gray", 0x808080),
+	("green", 0x008000),
+	("greenyellow", 0xadff2f),
+	("grey", 0x808080),
+	("honeydew", 0xf0fff0),
+	("hotpink", 0xff69b4),
+	("indianred", 0xcd5c5c),
+	("indigo", 0x4b0082),
+	("ivory", 0xfffff0),
+	("khaki", 0xf0e68c),
+	("lavender", 0xe6e6fa),
+	("lavenderblush", 0xfff0f5),
+	("lawngreen", 0x7cfc00),
+	("lemonchiffon", 0xfffacd),
+	("lightblue", 0xadd8e6),
+	("lightcoral", 0xf08080),
+	("lightcyan", 0xe0ffff),
+	("lightgoldenrodyellow", 0xfafad2),
+	("lightgray", 0xd3d3d3),
+	("lightgreen", 0x90ee90),
+	("lightgrey", 0xd3d3d3),
+	("lightpink", 0xffb6c1),
+	("lightsalmon", 0xffa07a),
+	("lightseagreen", 0x20b2aa),
+	("lightskyblue", 0x87cefa),
+	("lightslategray", 0x778899),
+	("lightslategrey", 0x778899),
+	("lightsteelblue", 0xb0c4de),
+	("lightyellow", 0xffffe0),
+	("lime", 0x00ff00),
+	("limegreen", 0x32cd32),
+	("linen", 0xfaf0e6),
+	("magenta", 0xff00ff),
+	("maroon", 0x800000),
+	("mediumaquamarine", 0x66cdaa),
+	("mediumblue", 0x0000cd),
+	("mediumorchid", 0xba55d3),
+	("mediumpurple", 0x9370db),
+	("mediumseagreen", 0x3cb371),
+	("mediumslateblue", 0x7b68ee),
+	("mediumspringgreen", 0x00fa9a),
+	("mediumturquoise", 0x48d1cc),
+	("mediumvioletred", 0xc71585),
+	("midnightblue", 0x191970),
+	("mintcream", 0xf5fffa),
+	("mistyrose", 0xffe4e1),
+	("moccasin", 0xffe4b5),
+	("navajowhite", 0xffdead),
+	("navy", 0x000080),
+	("oldlace", 0xfdf5e6),
+	("olive", 0x808000),
+	("olivedrab", 0x6b8e23),
+	("orange", 0xffa500),
+	("orangered", 0xff4500),
+	("orchid", 0xda70d6),
+	("palegoldenrod", 0xeee8aa),
+	("palegreen", 0x98fb98),
+	("paleturquoise", 0xafeeee),
+	("palevioletred", 0xdb7093),
+	("papayawhip", 0xffefd5),
+	("peachpuff", 0xffdab9),
+	("peru", 0xcd853f),
+	("pink", 0xffc0cb),
+	("plum", 0xdda0dd),
+	("powderblue", 0xb0e0e6),
+	("purple", 0x800080),
+	("rebeccapurple", 0x663399),
+	("red", 0xff0000),
+	("rosybrown", 0xbc8f8f),
+	("royalblue", 0x4169e1),
+	("saddlebrown", 0x8b4513),
+	("salmon", 0xfa8072),
+	("sandybrown", 0xf4a460),
+	("seagreen", 0x2e8b57),
+	("seashell", 0xfff5ee),
+	("sienna", 0xa0522d),
+	("silver", 0xc0c0c0),
+	("skyblue", 0x87ceeb),
+	("slateblue", 0x6a5acd),
+	("slategray", 0x708090),
+	("slategrey", 0x708090),
+	("snow", 0xfffafa),
+	("springgreen", 0x00ff7f),
+	("steelblue", 0x4682b4),
+	("tan", 0xd2b48c),
+	("teal", 0x008080),
+	("thistle", 0xd8bfd8),
+	("tomato", 0xff6347),
+	("turquoise", 0x40e0d0),
+	("violet", 0xee82ee),
+	("wheat", 0xf5deb3),
+	("white", 0xffffff),
+	("whitesmoke", 0xf5f5f5),
+	("yellow", 0xffff00),
+	("yellowgreen", 0x9acd32),
+];
+
+#[derive(Debug)]
+pub enum SvgError {
+	Io(std::io::Error),
+	/// The document isn't well-formed, or has a malformed attribute
+	Invalid(String),
+}
+
+impl std::fmt::Display for SvgError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Io(err) => write!(f, "Failed to read the SVG file: {}", err),
+			Self::Invalid(reason) => write!(f, "Invalid SVG file: {}", reason),
+		}
+	}
+}
+
+impl std::error::Error for SvgError {}
+
+impl From<std::io::Error> for SvgError {
+	fn from(err: std::io::Error) -> Self {
+		Self::Io(err)
+	}
+}
+
+/// A filled and stroked element of an [`SvgFile`]
+#[derive(Clone, PartialEq)]
+pub struct SvgShape {
+	/// The `id` attribute of the element
+	pub id: Option<String>,
+	/// Fill and stroke of the element, with the stroke drawn over the fill
+	pub mesh: Mesh,
+}
+
+/// The shapes of an SVG document, tessellated into vertex colored meshes.
+///
+/// Supports `path`, `rect`, `circle`, `ellipse`, `line`, `polyline` and `polygon` elements inside nested `g` groups, with their `transform`s,
+/// solid `fill` and `stroke` colors, opacities, fill rules and stroke widths, joins and caps. Presentation attributes can also be set through `style`.
+/// Gradients, patterns, text and `use` references aren't supported: `url()` paints are skipped, and so are the elements. Other paints and stroke widths
+/// that can't be used are skipped with a warning.
+///
+/// Meshes are in the units of the document's `width` and `height` (or of its `viewBox` without them), with the origin at the bottom left
+/// of the view box and `y` pointing up
+#[derive(Clone, PartialEq)]
+pub struct SvgFile {
+	pub size: Vec2,
+	/// The visible shapes in drawing order
+	pub shapes: Vec<SvgShape>,
+}
+
+impl SvgFile {
+	pub fn from_slice(bytes: &[u8]) -> Result<Self, SvgError> {
+		let source = String::from_utf8_lossy(bytes);
+		let root = xml::parse(&source)?;
+		if root.name != "svg" {
+			return Err(SvgError::Invalid(format!("the root element is <{}> instead of <svg>", root.name)));
+		}
+
+		let view_box = match root.attribute("viewBox") {
+			Some(value) => {
+				let numbers = number_list(value)?;
+				match numbers[..] {
+					[x, y, width, height] if width > 0.0 && height > 0.0 => Some((vec2(x, y), vec2(width, height))),
+					_ => return Err(SvgError::Invalid(format!("malformed viewBox \"{}\"", value))),
+				}
+			}
+			None => None,
+		};
+		let width = root.attribute("width").and_then(length);
+		let height = root.attribute("height").and_then(length);
+		let (origin, view_size) = view_box.unwrap_or((Vec2::ZERO, vec2(width.unwrap_or(0.0), height.unwrap_or(0.0))));
+		let size = vec2(width.unwrap_or(view_size.x), height.unwrap_or(view_size.y));
+
+		// Maps the view box onto the size, flipping it upside down
+		let scale = match view_size.x > 0.0 && view_size.y > 0.0 {
+			true => size / view_size,
+			false => Vec2::ONE,
+		};
+		let transform = Affine2::from_cols_array(&[scale.x, 0.0, 0.0, -scale.y, -scale.x * origin.x, size.y + scale.y * origin.y]);
+
+		let mut shapes = Vec::new();
+		let style = Style { transform, ..Default::default() }.with_element(&root)?;
+		for child in root.children.iter() {
+			collect_shapes(child, &style, &mut shapes)?;
+		}
+		Ok(Self { size, shapes })
+	}
+
+	/// All the shapes merged into one mesh, drawn in order
+	pub fn mesh(&self) -> Mesh {
+		Mesh::merge(self.shapes.iter().map(|shape| &shape.mesh))
+	}
+}
+
+/// An SVG document loaded by the [`SvgLoader`]
+#[derive(Asset, TypePath, Debug)]
+pub struct Svg {
+	pub size: Vec2,
+	/// All the shapes of the document in one mesh, labeled `mesh`
+	pub mesh: Handle<Mesh>,
+	/// Meshes of the shapes with an `id`, labeled `shapes/<id>`
+	pub shapes: HashMap<String, Handle<Mesh>>,
+}
+
+/// Loads `.svg` files into meshes, see [`SvgFile`] for the supported features
+#[derive(Default)]
+pub struct SvgLoader;
+
+impl AssetLoader for SvgLoader {
+	type Asset = Svg;
+	type Settings = ();
+	type Error = SvgError;
+
+	fn load<'a>(&'a self, reader: &'a mut Reader, _settings: &'a (), load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<Svg, SvgError>> {
+		Box::pin(async move {
+			let mut bytes = Vec::new();
+			reader.read_to_end(&mut bytes).await?;
+			let file = SvgFile::from_slice(&bytes)?;
+
+			let mesh = load_context.add_labeled_asset("mesh".to_string(), file.mesh());
+			let mut shapes = HashMap::new();
+			for shape in file.shapes {
+				if let Some(id) = shape.id {
+					let handle = load_context.add_labeled_asset(format!("shapes/{}", id), shape.mesh);
+					shapes.insert(id, handle);
+				}
+			}
+
+			Ok(Svg { size: file.size, mesh, shapes })
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["svg"]
+	}
+}
+
+/// Inherited presentation attributes, and the transform to the mesh space
+#[derive(Clone)]
+struct Style {
+	transform: Affine2,
+	color: Rgba,
+	fill: Option<Rgba>,
+	fill_opacity: f32,
+	fill_rule: FillRule,
+	stroke: Option<Rgba>,
+	stroke_opacity: f32,
+	stroke_style: StrokeStyle,
+	/// Product of the opacities of the element and its groups
+	opacity: f32,
+	visible: bool,
+}
+
+impl Default for Style {
+	fn default() -> Self {
+		Self {
+			transform: Affine2::IDENTITY,
+			color: Rgba::new(0, 0, 0, 255),
+			fill: Some(Rgba::new(0, 0, 0, 255)),
+			fill_opacity: 1.0,
+			fill_rule: FillRule::NonZero,
+			stroke: None,
+			stroke_opacity: 1.0,
+			stroke_style: StrokeStyle::default(),
+			opacity: 1.0,
+			visible: true,
+		}
+	}
+}
+
+impl Style {
+	/// The style of the element inside of this one, with its presentation attributes and then its `style` declarations applied
+	fn with_element(&self, element: &xml::Element) -> Result<Self, SvgError> {
+		let mut style = self.clone();
+		style.visible = true;
+		for (name, value) in element.attributes.iter() {
+			style.apply(name, value)?;
+		}
+		if let Some(declarations) = element.attribute("style") {
+			for declaration in declarations.split(';') {
+				if let Some((name, value)) = declaration.split_once(':') {
+					style.apply(name.trim(), value.trim())?;
+				}
+			}
+		}
+		Ok(style)
+	}
+
+	fn apply(&mut self, name: &str, value: &str) -> Result<(), SvgError> {
+		if value == "inherit" {
+			return Ok(());
+		}
+		match name {
+			"transform" => self.transform *= transform(value)?,
+			"color" => self.color = paint(name, value, self.color).unwrap_or(self.color),
+			"fill" => self.fill = paint(name, value, self.color),
+			"fill-opacity" => self.fill_opacity = opacity(value)?,
+			"fill-rule" => {
+				self.fill_rule = match value {
+					"evenodd" => FillRule::EvenOdd,
+					_ => FillRule::NonZero,
+				}
+			}
+			"stroke" => self.stroke = paint(name, value, self.color),
+			"stroke-opacity" => self.stroke_opacity = opacity(value)?,
+			"stroke-width" => match length(value) {
+				Some(width) => self.stroke_style.width = width,
+				None => unsupported(name, value),
+			},
+			"stroke-miterlimit" => self.stroke_style.miter_limit = number(value).ok_or_else(|| invalid(name, value))?,
+			"stroke-linejoin" => {
+				self.stroke_style.join = match value {
+					"round" => LineJoin::Round,
+					"bevel" => LineJoin::Bevel,
+					_ => LineJoin::Miter,
+				}
+			}
+			"stroke-linecap" => {
+				self.stroke_style.cap = match value {
+					"round" => LineCap::Round,
+					"square" => LineCap::Square,
+					_ => LineCap::Butt,
+				}
+			}
+			"opacity" => self.opacity *= opacity(value)?,
+			"display" | "visibility" => self.visible = !matches!(value, "none" | "hidden" | "collapse"),
+			_ => {}
+		}
+		Ok(())
+	}
+
+	/// Fills and strokes the path, which is in the units of the element
+	fn mesh(&self, path: &Path) -> Mesh {
+		let mut mesh = Mesh { vertices: Vec::new(), indices: Vec::new() };
+		if let Some(fill) = self.fill {
+			mesh.append(&path.fill(self.fill_rule, faded(fill, self.fill_opacity * self.opacity)));
+		}
+		if let Some(stroke) = self.stroke {
+			if self.stroke_style.width > 0.0 {
+				mesh.append(&path.stroke(&self.stroke_style, faded(stroke, self.stroke_opacity * self.opacity)));
+			}
+		}
+
+		for vertex in mesh.vertices.iter_mut() {
+			vertex.position = self.transform.transform_point2(vertex.position.truncate()).extend(0.0);
+		}
+		mesh
+	}
+
+	/// Flattening tolerance in the units of the element, so curves are as smooth in the mesh at any scale
+	fn tolerance(&self) -> f32 {
+		let scale = self.transform.matrix2.determinant().abs().sqrt();
+		match scale > 0.0 {
+			true => TOLERANCE / scale,
+			false => TOLERANCE,
+		}
+	}
+}
+
+fn faded(color: Rgba, opacity: f32) -> Rgba {
+	Rgba::new(color.r, color.g, color.b, (color.a as f32 * opacity.clamp(0.0, 1.0)).round() as u8)
+}
+
+fn invalid(name: &str, value: &str) -> SvgError {
+	SvgError::Invalid(format!("malformed {} \"{}\"", name, value))
+}
+
+/// Attribute values that can't be used are ignored, instead of failing the whole document
+fn unsupported(_name: &str, _value: &str) {
+	#[cfg(feature = "log")]
+	bevy_log::warn!("Ignoring unsupported SVG {} \"{}\"", _name, _value);
+}
+
+/// A paint of a `fill`, `stroke` or `color` attribute, skipped if it isn't supported
+fn paint(name: &str, value: &str, current_color: Rgba) -> Option<Rgba> {
+	color(value, current_color).unwrap_or_else(|_| {
+		unsupported(name, value);
+		None
+	})
+}
+
+fn collect_shapes(element: &xml::Element, parent: &Style, shapes: &mut Vec<SvgShape>) -> Result<(), SvgError> {
+	let style = parent.with_element(element)?;
+	if !style.visible {
+		return Ok(());
+	}
+
+	let mut builder = PathBuilder::default();
+	builder.tolerance(style.tolerance());
+	let attribute = |name: &str| element.attribute(name).and_then(length).unwrap_or(0.0);
+
+	match element.name.as_str() {
+		"g" | "a" | "svg" => {
+			for child in element.children.iter() {
+				collect_shapes(child, &style, shapes)?;
+			}
+			return Ok(());
+		}
+		"path" => {
+			if let Err(_err) = path_data(&mut builder, element.attribute("d").unwrap_or_default()) {
+				#[cfg(feature = "log")]
+				bevy_log::warn!("{}", _err);
+			}
+		}
+		"rect" => {
+			let (position, size) = (vec2(attribute("x"), attribute("y")), vec2(attribute("width"), attribute("height")));
+			// A missing corner radius is the same as the other one
+			let (rx, ry) = (element.attribute("rx").and_then(length), element.attribute("ry").and_then(length));
+			let radii = vec2(rx.or(ry).unwrap_or(0.0), ry.or(rx).unwrap_or(0.0)).min(size / 2.0);
+			rectangle(&mut builder, position, size, radii);
+		}
+		"circle" => {
+			let radius = attribute("r");
+			if radius > 0.0 {
+				builder.arc(vec2(attribute("cx"), attribute("cy")), radius, 0.0, TAU).close();
+			}
+		}
+		"ellipse" => {
+			let radii = vec2(attribute("rx"), attribute("ry"));
+			if radii.x > 0.0 && radii.y > 0.0 {
+				builder.elliptical_arc(vec2(attribute("cx"), attribute("cy")), radii, 0.0, 0.0, TAU).close();
+			}
+		}
+		"line" => {
+			builder.move_to(vec2(attribute("x1"), attribute("y1"))).line_to(vec2(attribute("x2"), attribute("y2")));
+		}
+		"polyline" | "polygon" => {
+			let numbers = number_list(element.attribute("points").unwrap_or_default())?;
+			for (i, point) in numbers.chunks_exact(2).enumerate() {
+				match i {
+					0 => builder.move_to(vec2(point[0], point[1])),
+					_ => builder.line_to(vec2(point[0], point[1])),
+				};
+			}
+			if element.name == "polygon" {
+				builder.close();
+			}
+		}
+		_ => return Ok(()),
+	}
+
+	let path = builder.build();
+	if !path.contours.is_empty() {
+		shapes.push(SvgShape {
+			id: element.attribute("id").map(str::to_string),
+			mesh: style.mesh(&path),
+		});
+	}
+	Ok(())
+}
+
+fn rectangle(builder: &mut PathBuilder, position: Vec2, size: Vec2, radii: Vec2) {
+	if size.x <= 0.0 || size.y <= 0.0 {
+		return;
+	}
+	if radii.x <= 0.0 || radii.y <= 0.0 {
+		builder.polygon(&[position, position + vec2(size.x, 0.0), position + size, position + vec2(0.0, size.y)]);
+		return;
+	}
+
+	// Corner arcs, going around the same way as the plain rectangle
+	let (min, max) = (position + radii, position + size - radii);
+	let quarter = TAU / 4.0;
+	builder
+		.elliptical_arc(vec2(max.x, min.y), radii, 0.0, -quarter, quarter)
+		.elliptical_arc(max, radii, 0.0, 0.0, quarter)
+		.elliptical_arc(vec2(min.x, max.y), radii, 0.0, quarter, quarter)
+		.elliptical_arc(min, radii, 0.0, quarter * 2.0, quarter)
+		.close();
+}
+
+/// Adds the commands of a path's `d` attribute. Parsing stops at the first error, keeping the path up to it, the way browsers render it
+fn path_data(builder: &mut PathBuilder, data: &str) -> Result<(), SvgError> {
+	let mut numbers = Numbers::new(data);
+	let (mut current, mut start) = (Vec2::ZERO, Vec2::ZERO);
+	// Control point of the previous curve, reflected by the smooth curve commands
+	let mut last_control: Option<(char, Vec2)> = None;
+	let mut command = None;
+
+	loop {
+		numbers.skip_separators();
+		command = match numbers.command() {
+			Some(next) => Some(next),
+			None if numbers.is_empty() => return Ok(()),
+			// Numbers after a command repeat it, with moves turning into lines
+			None => match command {
+				Some('M') => Some('L'),
+				Some('m') => Some('l'),
+				Some('Z' | 'z') | None => return Err(SvgError::Invalid(format!("path data \"{}\" has numbers without a command", data))),
+				other => other,
+			},
+		};
+
+		let name = command.unwrap_or_default();
+		let relative = name.is_ascii_lowercase();
+		let origin = if relative { current } else { Vec2::ZERO };
+		let point = |numbers: &mut Numbers| -> Result<Vec2, SvgError> { Ok(origin + vec2(numbers.number()?, numbers.number()?)) };
+		let mut control = None;
+
+		match name.to_ascii_uppercase() {
+			'M' => {
+				current = point(&mut numbers)?;
+				start = current;
+				builder.move_to(current);
+			}
+			'L' => {
+				current = point(&mut numbers)?;
+				builder.line_to(current);
+			}
+			'H' => {
+				current.x = origin.x + numbers.number()?;
+				builder.line_to(current);
+			}
+			'V' => {
+				current.y = origin.y + numbers.number()?;
+				builder.line_to(current);
+			}
+			'Q' | 'T' => {
+				let first = match name.to_ascii_uppercase() {
+					'Q' => point(&mut numbers)?,
+					_ => match last_control {
+						Some(('Q' | 'T', previous)) => current * 2.0 - previous,
+						_ => current,
+					},
+				};
+				current = point(&mut numbers)?;
+				builder.quadratic_to(first, current);
+				control = Some(('Q', first));
+			}
+			'C' | 'S' => {
+				let first = match name.to_ascii_uppercase() {
+					'C' => point(&mut numbers)?,
+					_ => match last_control {
+						Some(('C', previous)) => current * 2.0 - previous,
+						_ => current,
+					},
+				};
+				let second = point(&mut numbers)?;
+				current = point(&mut numbers)?;
+				builder.cubic_to(first, second, current);
+				control = Some(('C', second));
+			}
+			'A' => {
+				let radii = vec2(numbers.number()?, numbers.number()?);
+				let rotation = numbers.number()?.to_radians();
+				let (large_arc, sweep) = (numbers.flag()?, numbers.flag()?);
+				let end = point(&mut numbers)?;
+				arc(builder, current, end, radii, rotation, large_arc, sweep);
+				current = end;
+			}
+			'Z' => {
+				builder.close();
+				current = start;
+			}
+			_ => return Err(SvgError::Invalid(format!("unknown path command {}", name))),
+		}
+		last_control = control;
+	}
+}
+
+/// Adds an arc given by its end points the way SVG does, by finding its center
+fn arc(builder: &mut PathBuilder, from: Vec2, to: Vec2, radii: Vec2, rotation: f32, large_arc: bool, sweep: bool) {
+	let mut radii = radii.abs();
+	if from == to {
+		return;
+	}
+	if radii.x == 0.0 || radii.y == 0.0 {
+		builder.line_to(to);
+		return;
+	}
+
+	// The middle of the chord, in the coordinates of the unrotated ellipse
+	let rotation_vector = Vec2::from_angle(rotation);
+	let half = Vec2::from_angle(-rotation).rotate((from - to) / 2.0);
+
+	// Radii too small to reach between the points are scaled up
+	let reach = (half / radii).length_squared();
+	if reach > 1.0 {
+		radii *= reach.sqrt();
+	}
+
+	let (rx2, ry2, hx2, hy2) = (radii.x * radii.x, radii.y * radii.y, half.x * half.x, half.y * half.y);
+	let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+	let factor = sign * ((rx2 * ry2 - rx2 * hy2 - ry2 * hx2) / (rx2 * hy2 + ry2 * hx2)).max(0.0).sqrt();
+	let center_rotated = vec2(radii.x * half.y / radii.y, -radii.y * half.x / radii.x) * factor;
+	let center = rotation_vector.rotate(center_rotated) + (from + to) / 2.0;
+
+	let start = (half - center_rotated) / radii;
+	let end = (-half - center_rotated) / radii;
+	let mut angle = start.angle_between(end);
+	if !sweep && angle > 0.0 {
+		angle -= TAU;
+	} else if sweep && angle < 0.0 {
+		angle += TAU;
+	}
+
+	builder.elliptical_arc(center, radii, rotation, Vec2::X.angle_between(start), angle);
+}
+
+/// Reads the numbers, flags and commands of path data
+struct Numbers<'a> {
+	source: &'a str,
+	position: usize,
+}
+
+impl<'a> Numbers<'a> {
+	fn new(source: &'a str) -> Self {
+		Self { source, position: 0 }
+	}
+
+	fn rest(&self) -> &'a str {
+		&self.source[self.position..]
+	}
+
+	fn is_empty(&self) -> bool {
+		self.rest().is_empty()
+	}
+
+	fn skip_separators(&mut self) {
+		let rest = self.rest();
+		self.position += rest.len() - rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',').len();
+	}
+
+	fn command(&mut self) -> Option<char> {
+		let c = self.rest().chars().next().filter(|c| c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E'))?;
+		self.position += 1;
+		Some(c)
+	}
+
+	fn number(&mut self) -> Result<f32, SvgError> {
+		self.skip_separators();
+		let bytes = self.rest().as_bytes();
+		let mut end = 0;
+		if matches!(bytes.first(), Some(b'+' | b'-')) {
+			end += 1;
+		}
+		let mut seen_dot = false;
+		while let Some(&byte) = bytes.get(end) {
+			match byte {
+				b'0'..=b'9' => end += 1,
+				// A second dot starts the next number, like in `0.5.5`
+				b'.' if !seen_dot => {
+					seen_dot = true;
+					end += 1;
+				}
+				b'e' | b'E' if end > 0 && matches!(bytes.get(end + 1), Some(b'0'..=b'9' | b'+' | b'-')) => {
+					end += 2;
+					while matches!(bytes.get(end), Some(b'0'..=b'9')) {
+						end += 1;
+					}
+					break;
+				}
+				_ => break,
+			}
+		}
+
+		let text = &self.rest()[..end];
+		match text.parse() {
+			Ok(number) => {
+				self.position += end;
+				Ok(number)
+			}
+			Err(_) => Err(SvgError::Invalid(format!("expected a number at \"{}\"", self.rest()))),
+		}
+	}
+
+	/// Arc flags are a single digit, which may be followed by the next number without a separator
+	fn flag(&mut self) -> Result<bool, SvgError> {
+		self.skip_separators();
+		let flag = match self.rest().chars().next() {
+			Some('0') => false,
+			Some('1') => true,
+			_ => return Err(SvgError::Invalid(format!("expected an arc flag at \"{}\"", self.rest()))),
+		};
+		self.position += 1;
+		Ok(flag)
+	}
+}
+
+fn number(value: &str) -> Option<f32> {
+	value.trim().parse().ok()
+}
+
+/// A length in user units, with an optional absolute unit. Percentages and font relative units aren't supported
+fn length(value: &str) -> Option<f32> {
+	let value = value.trim();
+	let split = value.find(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E').unwrap_or(value.len());
+	let scale = match &value[split..] {
+		"" | "px" => 1.0,
+		"in" => 96.0,
+		"cm" => 96.0 / 2.54,
+		"mm" => 9.6 / 2.54,
+		"pt" => 96.0 / 72.0,
+		"pc" => 16.0,
+		_ => return None,
+	};
+	number(&value[..split]).map(|length| length * scale)
+}
+
+fn number_list(value: &str) -> Result<Vec<f32>, SvgError> {
+	let mut numbers = Numbers::new(value);
+	let mut list = Vec::new();
+	loop {
+		numbers.skip_separators();
+		if numbers.is_empty() {
+			return Ok(list);
+		}
+		list.push(numbers.number()?);
+	}
+}
+
+fn opacity(value: &str) -> Result<f32, SvgError> {
+	let value = value.trim();
+	let parsed = match value.strip_suffix('%') {
+		Some(percent) => number(percent).map(|percent| percent / 100.0),
+		None => number(value),
+	};
+	parsed.map(|opacity| opacity.clamp(0.0, 1.0)).ok_or_else(|| invalid("opacity", value))
+}
+
+/// Parses a paint, `None` meaning nothing is drawn
+fn color(value: &str, current_color: Rgba) -> Result<Option<Rgba>, SvgError> {
+	let value = value.trim();
+	if let Some(hex) = value.strip_prefix('#') {
+		let digits: Vec<u8> = match hex.chars().map(|c| c.to_digit(16).map(|digit| digit as u8)).collect::<Option<Vec<u8>>>() {
+			Some(digits) => digits,
+			None => return Err(invalid("color", value)),
+		};
+		return match digits[..] {
+			[r, g, b] => Ok(Some(Rgba::new(r * 17, g * 17, b * 17, 255))),
+			[r, g, b, a] => Ok(Some(Rgba::new(r * 17, g * 17, b * 17, a * 17))),
+			[r1, r2, g1, g2, b1, b2] => Ok(Some(Rgba::new(r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2, 255))),
+			[r1, r2, g1, g2, b1, b2, a1, a2] => Ok(Some(Rgba::new(r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2, a1 * 16 + a2))),
+			_ => Err(invalid("color", value)),
+		};
+	}
+
+	if let Some(arguments) = value.strip_prefix("rgba(").or_else(|| value.strip_prefix("rgb(")).and_then(|rest| rest.strip_suffix(')')) {
+		let channels: Vec<&str> = arguments.split(|c: char| c == ',' || c == '/' || c.is_whitespace()).filter(|part| !part.is_empty()).collect();
+		let channel = |part: &str| match part.strip_suffix('%') {
+			Some(percent) => number(percent).map(|percent| percent * 2.55),
+			None => number(part),
+		};
+		let alpha = match channels.get(3) {
+			Some(alpha) => opacity(alpha)?,
+			None => 1.0,
+		};
+		return match channels[..] {
+			[r, g, b] | [r, g, b, _] => match (channel(r), channel(g), channel(b)) {
+				(Some(r), Some(g), Some(b)) => Ok(Some(Rgba::new(r.round().clamp(0.0, 255.0) as u8, g.round().clamp(0.0, 255.0) as u8, b.round().clamp(0.0, 255.0) as u8, (alpha * 255.0).round() as u8))),
+				_ => Err(invalid("color", value)),
+			},
+			_ => Err(invalid("color", value)),
+		};
+	}
+
+	if let Some(arguments) = value.strip_prefix("hsla(").or_else(|| value.strip_prefix("hsl(")).and_then(|rest| rest.strip_suffix(')')) {
+		let channels: Vec<&str> = arguments.split(|c: char| c == ',' || c == '/' || c.is_whitespace()).filter(|part| !part.is_empty()).collect();
+		let percent = |part: &str| part.strip_suffix('%').and_then(number).map(|percent| (percent / 100.0).clamp(0.0, 1.0));
+		let alpha = match channels.get(3) {
+			Some(alpha) => opacity(alpha)?,
+			None => 1.0,
+		};
+		return match channels[..] {
+			[h, s, l] | [h, s, l, _] => match (number(h.trim_end_matches("deg")), percent(s), percent(l)) {
+				(Some(h), Some(s), Some(l)) => {
+					// https://www.w3.org/TR/css-color-4/#hsl-to-rgb
+					let channel = |n: f32| {
+						let k = (n + h.rem_euclid(360.0) / 30.0) % 12.0;
+						let a = s * l.min(1.0 - l);
+						((l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)) * 255.0).round() as u8
+					};
+					Ok(Some(Rgba::new(channel(0.0), channel(8.0), channel(4.0), (alpha * 255.0).round() as u8)))
+				}
+				_ => Err(invalid("color", value)),
+			},
+			_ => Err(invalid("color", value)),
+		};
+	}
+
+	match value.to_ascii_lowercase().as_str() {
+		// Gradients and patterns aren't supported
+		"none" | "transparent" => Ok(None),
+		url if url.starts_with("url(") => Ok(None),
+		"currentcolor" => Ok(Some(current_color)),
+		name => match NAMED_COLORS.binary_search_by_key(&name, |&(name, _)| name) {
+			Ok(index) => {
+				let [_, r, g, b] = NAMED_COLORS[index].1.to_be_bytes();
+				Ok(Some(Rgba::new(r, g, b, 255)))
+			}
+			Err(_) => Err(invalid("color", value)),
+		},
+	}
+}
+
+/// Parses a transform list into the matrix applying them from right to left
+fn transform(value: &str) -> Result<Affine2, SvgError> {
+	let mut result = Affine2::IDENTITY;
+	let mut rest = value.trim();
+	while !rest.is_empty() {
+		let (name, arguments) = match rest.split_once('(') {
+			Some((name, after)) => match after.split_once(')') {
+				Some((arguments, after)) => {
+					rest = after.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+					(name.trim(), number_list(arguments)?)
+				}
+				None => return Err(invalid("transform", value)),
+			},
+			None => return Err(invalid("transform", value)),
+		};
+
+		let matrix = match (name, &arguments[..]) {
+			("matrix", &[a, b, c, d, e, f]) => Affine2::from_cols_array(&[a, b, c, d, e, f]),
+			("translate", &[x]) => Affine2::from_translation(vec2(x, 0.0)),
+			("translate", &[x, y]) => Affine2::from_translation(vec2(x, y)),
+			("scale", &[scale]) => Affine2::from_scale(Vec2::splat(scale)),
+			("scale", &[x, y]) => Affine2::from_scale(vec2(x, y)),
+			("rotate", &[angle]) => Affine2::from_angle(angle.to_radians()),
+			("rotate", &[angle, x, y]) => Affine2::from_translation(vec2(x, y)) * Affine2::from_angle(angle.to_radians()) * Affine2::from_translation(vec2(-x, -y)),
+			("skewX", &[angle]) => Affine2::from_cols_array(&[1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0]),
+			("skewY", &[angle]) => Affine2::from_cols_array(&[1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0]),
+			_ => return Err(invalid("transform", value)),
+		};
+		result *= matrix;
+	}
+	Ok(result)
+}
+
+/// Just enough of XML to read SVG documents: elements and their attributes, skipping text, comments, declarations and CDATA
+mod xml {
+	use super::SvgError;
+
+	pub struct Element {
+		/// Name of the element without its namespace prefix
+		pub name: String,
+		pub attributes: Vec<(String, String)>,
+		pub children: Vec<Element>,
+	}
+
+	impl Element {
+		pub fn attribute(&self, name: &str) -> Option<&str> {
+			self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+		}
+	}
+
+	fn error(reason: &str) -> SvgError {
+		SvgError::Invalid(reason.to_string())
+	}
+
+	/// Returns the root element
+	pub fn parse(source: &str) -> Result<Element, SvgError> {
+		let mut stack: Vec<Element> = Vec::new();
+		let mut rest = source;
+
+		loop {
+			rest = match rest.find('<') {
+				Some(start) => &rest[start..],
+				None => return Err(error("the document has no root element")),
+			};
+
+			let skipped = [("<?", "?>"), ("<!--", "-->"), ("<![CDATA[", "]]>")].iter().find(|(open, _)| rest.starts_with(open));
+			if let Some((_, close)) = skipped {
+				rest = match rest.find(close) {
+					Some(end) => &rest[end + close.len()..],
+					None => return Err(error("unterminated comment or declaration")),
+				};
+				continue;
+			}
+			if rest.starts_with("<!") {
+				// Doctypes may have an internal subset in brackets
+				let end = match (rest.find('['), rest.find('>')) {
+					(Some(bracket), Some(end)) if bracket < end => rest.find("]>").map(|end| end + 1),
+					(_, end) => end,
+				};
+				rest = match end {
+					Some(end) => &rest[end + 1..],
+					None => return Err(error("unterminated declaration")),
+				};
+				continue;
+			}
+
+			if let Some(after) = rest.strip_prefix("</") {
+				let end = after.find('>').ok_or_else(|| error("unterminated closing tag"))?;
+				rest = &after[end + 1..];
+				let element = stack.pop().ok_or_else(|| error("closing tag without an element"))?;
+				match stack.last_mut() {
+					Some(parent) => parent.children.push(element),
+					None => return Ok(element),
+				}
+				continue;
+			}
+
+			let (element, closed, after) = start_tag(&rest[1..])?;
+			rest = after;
+			match (closed, stack.last_mut()) {
+				(false, _) => stack.push(element),
+				(true, Some(parent)) => parent.children.push(element),
+				(true, None) => return Ok(element),
+			}
+		}
+	}
+
+	/// Parses a start tag after its `<`, returning whether it's self-closing and the source after it
+	fn start_tag(source: &str) -> Result<(Element, bool, &str), SvgError> {
+		let name_end = source.find(|c: char| c.is_whitespace() || c == '/' || c == '>').ok_or_else(|| error("unterminated tag"))?;
+		let name = &source[..name_end];
+		let name = name.rsplit(':').next().unwrap_or(name).to_string();
+		let mut element = Element {
+			name,
+			attributes: Vec::new(),
+			children: Vec::new(),
+		};
+
+		let mut rest = &source[name_end..];
+		loop {
+			rest = rest.trim_start();
+			if let Some(after) = rest.strip_prefix("/>") {
+				return Ok((element, true, after));
+			}
+			if let Some(after) = rest.strip_prefix('>') {
+				return Ok((element, false, after));
+			}
+
+			let key_end = rest.find(|c: char| c.is_whitespace() || c == '=').ok_or_else(|| error("unterminated tag"))?;
+			let key = rest[..key_end].to_string();
+			rest = rest[key_end..].trim_start().strip_prefix('=').ok_or_else(|| error("attribute without a value"))?.trim_start();
+
+			let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'').ok_or_else(|| error("unquoted attribute value"))?;
+			let value_end = rest[1..].find(quote).ok_or_else(|| error("unterminated attribute value"))?;
+			element.attributes.push((key, unescape(&rest[1..value_end + 1])));
+			rest = &rest[value_end + 2..];
+		}
+	}
+
+	fn unescape(value: &str) -> String {
+		let mut result = String::with_capacity(value.len());
+		let mut rest = value;
+		while let Some(start) = rest.find('&') {
+			result.push_str(&rest[..start]);
+			rest = &rest[start..];
+			let end = match rest.find(';') {
+				Some(end) => end,
+				None => break,
+			};
+			let entity = &rest[1..end];
+			let c = match entity {
+				"amp" => Some('&'),
+				"lt" => Some('<'),
+				"gt" => Some('>'),
+				"quot" => Some('"'),
+				"apos" => Some('\''),
+				_ => match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+					Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+					None => entity.strip_prefix('#').and_then(|decimal| decimal.parse().ok()).and_then(char::from_u32),
+				},
+			};
+			match c {
+				Some(c) => {
+					result.push(c);
+					rest = &rest[end + 1..];
+				}
+				None => {
+					result.push('&');
+					rest = &rest[1..];
+				}
+			}
+		}
+		result.push_str(rest);
+		result
+	}
+}
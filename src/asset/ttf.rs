@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use bevy_asset::{io::Reader, Asset, AssetLoader, AsyncReadExt, BoxedFuture, LoadContext};
+use bevy_reflect::TypePath;
+
+use super::FontError;
+
+static NEXT_FONT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A TrueType or OpenType font, rasterized on the CPU at any size. Its glyphs are drawn through a [`GlyphCache`](crate::prelude::GlyphCache)
+#[derive(Asset, TypePath, Clone)]
+pub struct TtfFont {
+	id: u64,
+	font: Arc<fontdue::Font>,
+}
+
+impl std::fmt::Debug for TtfFont {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("TtfFont").field("id", &self.id).field("name", &self.font.name()).finish()
+	}
+}
+
+impl TtfFont {
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, FontError> {
+		let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default()).map_err(|err| FontError::Invalid(err.to_string()))?;
+		Ok(Self {
+			id: NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed),
+			font: Arc::new(font),
+		})
+	}
+
+	/// Unique id of the font, distinguishing its glyphs in the cache. Clones share the id
+	pub fn id(&self) -> u64 {
+		self.id
+	}
+
+	/// The parsed font, for metrics and rasterization
+	pub fn font(&self) -> &fontdue::Font {
+		&self.font
+	}
+
+	/// Distance between two lines at the given pixel size
+	pub fn line_height(&self, size: f32) -> f32 {
+		self.font.horizontal_line_metrics(size).map(|metrics| metrics.new_line_size).unwrap_or(size)
+	}
+
+	/// Distance from the top of a line to the baseline at the given pixel size
+	pub fn ascent(&self, size: f32) -> f32 {
+		self.font.horizontal_line_metrics(size).map(|metrics| metrics.ascent).unwrap_or(size)
+	}
+
+	/// How far the pen moves after the character at the given pixel size
+	pub fn advance(&self, c: char, size: f32) -> f32 {
+		self.font.metrics(c, size).advance_width
+	}
+
+	/// Additional advance between two consecutive characters at the given pixel size
+	pub fn kerning(&self, first: char, second: char, size: f32) -> f32 {
+		self.font.horizontal_kern(first, second, size).unwrap_or(0.0)
+	}
+}
+
+/// Loads `.ttf` and `.otf` fonts
+#[derive(Default)]
+pub struct TtfFontLoader;
+
+impl AssetLoader for TtfFontLoader {
+	type Asset = TtfFont;
+	type Settings = ();
+	type Error = FontError;
+
+	fn load<'a>(&'a self, reader: &'a mut Reader, _settings: &'a (), _load_context: &'a mut LoadContext) -> BoxedFuture<'a, Result<TtfFont, FontError>> {
+		Box::pin(async move {
+			let mut bytes = Vec::new();
+			reader.read_to_end(&mut bytes).await?;
+			TtfFont::from_bytes(&bytes)
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["ttf", "otf"]
+	}
+}
@@ -1,22 +1,21 @@
-use std::{io, task};
+use std::{future::Future, io, task};
 
 use miniquad::fs;
 use oneshot::Receiver;
 
 struct FileLoadingFuture(Receiver<Result<Vec<u8>, fs::Error>>);
 
-impl std::future::Future for FileLoadingFuture {
+impl Future for FileLoadingFuture {
 	type Output = Result<Vec<u8>, fs::Error>;
 
-	fn poll(self: std::pin::Pin<&mut Self>, _: &mut task::Context<'_>) -> task::Poll<Self::Output> {
-		match self.0.try_recv() {
-			Ok(res) => task::Poll::Ready(res),
-			Err(oneshot::TryRecvError::Empty) => task::Poll::Pending,
-			Err(oneshot::TryRecvError::Disconnected) => {
-				let error = io::Error::new(io::ErrorKind::Other, "File loading future was dropped");
-				task::Poll::Ready(Err(fs::Error::IOError(error)))
-			}
-		}
+	fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Self::Output> {
+		// Polling the receiver registers the waker, so the task is woken up once miniquad's callback sends the file
+		std::pin::Pin::new(&mut self.0).poll(cx).map(|res| {
+			res.unwrap_or_else(|_| {
+				let error = io::Error::other("File loading future was dropped");
+				Err(fs::Error::IOError(error))
+			})
+		})
 	}
 }
 
@@ -1,8 +1,12 @@
 /// Simplified import for all the crate's types and functions
 pub mod prelude {
 	pub use crate::io::*;
-	pub use crate::render::{camera::*, geometry::*, *};
-	pub use crate::window::{events::*, icon::*, state::*, *};
+	pub use crate::render::{camera::*, geometry::*, instancing::*, layout::*, material::*, mesh::*, path::*, pipeline::{GlPipeline, PipelineError}, sdf::*, shapes::*, sprite::*, stroke::*, text::*, *};
+	pub use crate::window::{events::*, icon::*, input::*, state::*, *};
+	#[cfg(feature = "testing")]
+	pub use crate::{render::recording::*, window::headless::*};
+	pub use crate::animation::*;
+	pub use crate::transform::*;
 	pub use crate::QuadifyPlugins;
 	pub use crate::render::RenderBackendPlugin;
 
@@ -14,9 +18,11 @@ pub mod color {
 	pub use crate::render::rgba::*;
 }
 
+pub(crate) mod animation;
 pub mod asset;
 pub(crate) mod io;
 pub(crate) mod render;
+pub(crate) mod transform;
 pub(crate) mod window;
 
 // Create Default plugin bundle
@@ -29,9 +35,12 @@ impl PluginGroup for QuadifyPlugins {
 		#[allow(unused_mut)]
 		let mut builder = PluginGroupBuilder::start::<Self>()
 			.add(bevy_input::InputPlugin)
+			.add(bevy_time::TimePlugin)
+			.add(transform::TransformPlugin)
 			.add(render::RenderBackendPlugin::default())
 			.add(window::WindowPlugin::default())
-			.add(asset::AssetPlugin);
+			.add(asset::AssetPlugin)
+			.add(animation::AnimationPlugin);
 
 		#[cfg(feature = "log")]
 		{
@@ -1,11 +1,35 @@
-use std::f32::consts::PI;
+use std::collections::HashSet;
+use std::f32::consts::{PI, TAU};
 
 use bevy_asset::Asset;
 use bevy_reflect::Reflect;
-use glam::{vec2, vec3, Vec2, Vec3, Quat};
+use glam::{vec2, vec3, Affine2, Mat4, Quat, UVec2, Vec2, Vec3, Vec4};
 use miniquad::{VertexAttribute, VertexFormat};
 
 use super::rgba::Rgba;
+use super::stroke::StrokeStyle;
+use super::GeometryError;
+
+/// Vertex types that can be drawn with [`RenderingBackend::geometry`](super::RenderingBackend::geometry). Materials declare their vertex layout
+/// on creation with [`RenderingBackend::request_material_with_layout`](super::RenderingBackend::request_material_with_layout),
+/// which allows passing normals, additional UV sets or any custom per-vertex data to shaders.
+///
+/// It also describes per-instance data of [`RenderingBackend::draw_instanced`](super::RenderingBackend::draw_instanced), in which case
+/// the buffer index of the attributes is ignored.
+///
+/// # Safety
+/// Vertices are uploaded to the GPU as raw bytes, so the type must be `#[repr(C)]` without any padding bytes,
+/// and [`ATTRIBUTES`](VertexLayout::ATTRIBUTES) must describe its fields in order.
+pub unsafe trait VertexLayout: Copy + 'static {
+	/// Vertex attributes, matching the attribute names of the shader
+	const ATTRIBUTES: &'static [VertexAttribute];
+}
+
+/// Reinterprets the vertices as bytes, the way they're uploaded to vertex buffers
+pub(crate) fn vertex_bytes<V: VertexLayout>(vertices: &[V]) -> &[u8] {
+	// SAFETY: `VertexLayout` types have no padding bytes
+	unsafe { std::slice::from_raw_parts(vertices.as_ptr() as *const u8, std::mem::size_of_val(vertices)) }
+}
 
 #[repr(C)]
 #[derive(Clone, Debug, Copy, Reflect, PartialEq)]
@@ -30,10 +54,15 @@ impl Vertex {
 	}
 }
 
-#[derive(Asset, Clone, PartialEq, Reflect)]
+// SAFETY: `Vertex` is `#[repr(C)]` of 24 bytes (Vec3, Vec2 and 4 bytes of color), so it doesn't have any padding
+unsafe impl VertexLayout for Vertex {
+	const ATTRIBUTES: &'static [VertexAttribute] = &Vertex::attributes();
+}
+
+#[derive(Asset, Clone, Debug, PartialEq, Reflect)]
 pub struct Mesh {
 	pub vertices: Vec<Vertex>,
-	pub indices: Vec<u16>,
+	pub indices: Vec<u32>,
 }
 
 impl Mesh {
@@ -53,23 +82,136 @@ impl Mesh {
 	/// Makes a circle mesh, with a specified amount of points
 	fn circle(pos: Vec3, r: f32, npoints: u32, color: Rgba) -> Self {
 		debug_assert!(npoints >= 3, "Not enough points to represent a circle mesh. Minimum is 3");
-		let mut indices: Vec<u16> = vec![];
+		let mut indices: Vec<u32> = vec![];
 		let mut vertices: Vec<Vertex> = vec![];
 
 		let circle_piece = 2.0 * PI / (npoints as f32);
 		for i in 0..npoints {
 			let degrees = (i as f32) * circle_piece;
 			let (x, y) = (degrees.cos(), degrees.sin());
-			vertices.push(Vertex::new(vec3(pos.x+x*r, pos.y+y*r, pos.z), vec2(x, y), color));
+			vertices.push(Vertex::new(vec3(pos.x+x*r, pos.y+y*r, pos.z), vec2(0.5 + x * 0.5, 0.5 - y * 0.5), color));
 
 			if i < npoints - 2 {
-				let i = i as u16;
 				indices.append(&mut vec![0, i + 1, i + 2]);
 			}
 		}
 		Self { vertices, indices }
 	}
 
+	/// Makes a mesh out of points relative to `pos`, with the texture spanning `bounds` (the minimum and maximum corner)
+	fn planar(pos: Vec3, points: &[Vec2], bounds: (Vec2, Vec2), indices: Vec<u32>, color: Rgba) -> Self {
+		let (min, max) = bounds;
+		let size = (max - min).max(Vec2::splat(f32::EPSILON));
+		let vertices = points
+			.iter()
+			.map(|point| Vertex::new(pos + point.extend(0.0), vec2(point.x - min.x, max.y - point.y) / size, color))
+			.collect();
+		Self { vertices, indices }
+	}
+
+	/// Makes a convex mesh fanning out from the center to the outline points
+	fn fan(pos: Vec3, outline: &[Vec2], bounds: (Vec2, Vec2), color: Rgba) -> Self {
+		let count = outline.len() as u32;
+		let points: Vec<Vec2> = std::iter::once(Vec2::ZERO).chain(outline.iter().copied()).collect();
+		let indices = (0..count).flat_map(|i| [0, i + 1, (i + 1) % count + 1]).collect();
+		Self::planar(pos, &points, bounds, indices, color)
+	}
+
+	/// Makes an ellipse mesh, with a specified amount of points on its outline
+	fn ellipse(pos: Vec3, radii: Vec2, npoints: u32, color: Rgba) -> Self {
+		let outline: Vec<Vec2> = (0..npoints).map(|i| Vec2::from_angle(TAU * i as f32 / npoints as f32) * radii).collect();
+		Self::fan(pos, &outline, (-radii, radii), color)
+	}
+
+	/// Makes a rectangle mesh with rounded corners, each one made of a quarter of `npoints`
+	fn rounded_rect(pos: Vec3, size: Vec2, radius: f32, npoints: u32, color: Rgba) -> Self {
+		let half = size / 2.0;
+		let radius = radius.clamp(0.0, half.x.min(half.y));
+		let corner_points = (npoints / 4).max(1);
+
+		let mut outline: Vec<Vec2> = Vec::with_capacity(4 * (corner_points as usize + 1));
+		for (corner, center) in [vec2(1.0, 1.0), vec2(-1.0, 1.0), vec2(-1.0, -1.0), vec2(1.0, -1.0)].into_iter().enumerate() {
+			let center = center * (half - radius);
+			for i in 0..=corner_points {
+				let angle = (corner as f32 + i as f32 / corner_points as f32) * PI / 2.0;
+				let point = center + Vec2::from_angle(angle) * radius;
+				// Corners without radius and full round sides share their end points
+				if outline.last() != Some(&point) && outline.first() != Some(&point) {
+					outline.push(point);
+				}
+			}
+		}
+		Self::fan(pos, &outline, (-half, half), color)
+	}
+
+	/// Makes a ring between the two radii, with a specified amount of points on each edge
+	fn ring(pos: Vec3, inner_radius: f32, outer_radius: f32, npoints: u32, color: Rgba) -> Self {
+		Self::arc(pos, inner_radius, outer_radius, 0.0, TAU, npoints, color)
+	}
+
+	/// Makes a part of a ring going counter-clockwise from `start` for `sweep` radians, or a circle sector without the inner radius.
+	/// The texture spans the whole circle, so the arc shows the matching part of it
+	fn arc(pos: Vec3, inner_radius: f32, outer_radius: f32, start: f32, sweep: f32, npoints: u32, color: Rgba) -> Self {
+		let sweep = sweep.clamp(-TAU, TAU);
+		let segments = ((npoints as f32 * sweep.abs() / TAU).ceil() as u32).max(1);
+		let closed = sweep.abs() >= TAU;
+		let bounds = (Vec2::splat(-outer_radius), Vec2::splat(outer_radius));
+		let edge = |radius: f32| (0..=segments).map(move |i| Vec2::from_angle(start + sweep * i as f32 / segments as f32) * radius);
+
+		if inner_radius <= 0.0 {
+			let mut outline: Vec<Vec2> = edge(outer_radius).collect();
+			if closed {
+				outline.pop();
+				return Self::fan(pos, &outline, bounds, color);
+			}
+			let points: Vec<Vec2> = std::iter::once(Vec2::ZERO).chain(outline).collect();
+			let indices = (1..=segments).flat_map(|i| [0, i, i + 1]).collect();
+			return Self::planar(pos, &points, bounds, indices, color);
+		}
+
+		// Outer and inner points alternate, each pair of them making a quad with the next one
+		let points: Vec<Vec2> = edge(outer_radius).zip(edge(inner_radius)).flat_map(|(outer, inner)| [outer, inner]).collect();
+		let indices = (0..segments)
+			.flat_map(|i| {
+				let (a, b) = (i * 2, i * 2 + 2);
+				[a, b, a + 1, b, a + 1, b + 1]
+			})
+			.collect();
+		Self::planar(pos, &points, bounds, indices, color)
+	}
+
+	/// Makes a regular polygon mesh, with its first corner pointing right
+	fn polygon(pos: Vec3, sides: u32, radius: f32, color: Rgba) -> Self {
+		Self::ellipse(pos, Vec2::splat(radius), sides.max(3), color)
+	}
+
+	/// Makes a plane of `size` split into `cells`, with the texture spanning the whole plane
+	fn grid(pos: Vec3, size: Vec2, cells: UVec2, color: Rgba) -> Self {
+		let cells = cells.max(UVec2::ONE);
+		let half = size / 2.0;
+		// Rows of points from the top down, in the order of the quad vertices
+		let points: Vec<Vec2> = (0..=cells.y)
+			.flat_map(|row| (0..=cells.x).map(move |column| vec2(column as f32 / cells.x as f32, 1.0 - row as f32 / cells.y as f32)))
+			.map(|fraction| fraction * size - half)
+			.collect();
+
+		let stride = cells.x + 1;
+		let indices = (0..cells.y)
+			.flat_map(|row| (0..cells.x).map(move |column| row * stride + column))
+			.flat_map(|top_left| {
+				let (top_right, bottom_left) = (top_left + 1, top_left + stride);
+				[top_left, top_right, bottom_left, top_right, bottom_left, bottom_left + 1]
+			})
+			.collect();
+		Self::planar(pos, &points, (-half, half), indices, color)
+	}
+
+	/// Makes a triangle mesh, with the texture spanning its bounds
+	fn triangle(pos: Vec3, corners: [Vec2; 3], color: Rgba) -> Self {
+		let bounds = (corners[0].min(corners[1]).min(corners[2]), corners[0].max(corners[1]).max(corners[2]));
+		Self::planar(pos, &corners, bounds, vec![0, 1, 2], color)
+	}
+
 	/// Colors the verticies of the mesh by the given [`Rgba`].
 	pub fn colored_as(mut self, color: Rgba) -> Self {
 		self.color_as(color);
@@ -125,12 +267,146 @@ impl Mesh {
 			vert.position = scale * vert.position;
 		}
     }
+
+	/// Concatenates the meshes into one, rebasing the indices of each mesh onto its vertices
+	pub fn merge<'a>(meshes: impl IntoIterator<Item = &'a Mesh>) -> Self {
+		let mut merged = Self { vertices: Vec::new(), indices: Vec::new() };
+		for mesh in meshes {
+			merged.append(mesh);
+		}
+		merged
+	}
+
+	/// Adds the vertices and indices of the other mesh to this one, drawn after it
+	pub fn append(&mut self, other: &Mesh) {
+		let start = self.vertices.len() as u32;
+		self.vertices.extend_from_slice(&other.vertices);
+		self.indices.extend(other.indices.iter().map(|index| start + index));
+	}
+
+	/// The smallest box containing every vertex, or `None` for a mesh without vertices
+	pub fn aabb(&self) -> Option<Aabb> {
+		Aabb::from_points(self.vertices.iter().map(|vert| vert.position))
+	}
+
+	/// Checks that every index points at a vertex, the same way [`RenderingBackend::geometry`](super::RenderingBackend::geometry) does before drawing
+	pub fn validate(&self) -> Result<(), GeometryError> {
+		match self.indices.iter().find(|index| **index as usize >= self.vertices.len()) {
+			Some(index) => Err(GeometryError::IndexOutOfRange(*index as usize)),
+			None => Ok(()),
+		}
+	}
+
+	/// Indices of the edges of the triangles, to draw the same vertices as lines with [`DrawMode::Lines`](super::pipeline::DrawMode::Lines).
+	/// Edges shared by neighbouring triangles are only included once
+	pub fn to_wireframe(&self) -> Vec<u32> {
+		let mut edges = HashSet::with_capacity(self.indices.len());
+		let mut lines = Vec::with_capacity(self.indices.len() * 2);
+		for triangle in self.indices.chunks_exact(3) {
+			for (a, b) in [(triangle[0], triangle[1]), (triangle[1], triangle[2]), (triangle[2], triangle[0])] {
+				if a != b && edges.insert((a.min(b), a.max(b))) {
+					lines.extend([a, b]);
+				}
+			}
+		}
+		lines
+	}
+
+	/// Transforms the texture coordinates of the mesh by the given [`Affine2`].
+	pub fn uvs_transformed_by(mut self, transform: Affine2) -> Self {
+		self.transform_uvs(transform);
+		self
+	}
+
+	/// Transforms the texture coordinates of the mesh in place by the given [`Affine2`].
+	pub fn transform_uvs(&mut self, transform: Affine2) {
+		for vert in self.vertices.iter_mut() {
+			vert.uv = transform.transform_point2(vert.uv);
+		}
+	}
+
+	/// Mirrors the texture coordinates of the mesh in place, horizontally and/or vertically.
+	pub fn flip_uvs(&mut self, x: bool, y: bool) {
+		let scale = vec2(if x { -1.0 } else { 1.0 }, if y { -1.0 } else { 1.0 });
+		self.transform_uvs(Affine2::from_scale_angle_translation(scale, 0.0, (Vec2::ONE - scale) / 2.0));
+	}
+
+	/// Maps the `0..1` texture coordinates of the mesh in place onto a region of the texture, given as `(x, y, width, height)` in pixels
+	/// like the rects of a [`TextureAtlas`](crate::asset::TextureAtlas).
+	pub fn map_uvs_to_region(&mut self, rect: Vec4, texture_size: Vec2) {
+		let (offset, size) = (vec2(rect.x, rect.y) / texture_size, vec2(rect.z, rect.w) / texture_size);
+		self.transform_uvs(Affine2::from_scale_angle_translation(size, 0.0, offset));
+	}
+}
+
+/// Axis-aligned bounding box, from the `min` to the `max` corner
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct Aabb {
+	pub min: Vec3,
+	pub max: Vec3,
+}
+
+impl Aabb {
+	pub fn new(min: Vec3, max: Vec3) -> Self {
+		Self { min, max }
+	}
+
+	/// The smallest box containing the points, or `None` without any
+	pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+		points.into_iter().fold(None, |aabb, point| match aabb {
+			Some(Aabb { min, max }) => Some(Self::new(min.min(point), max.max(point))),
+			None => Some(Self::new(point, point)),
+		})
+	}
+
+	pub fn center(&self) -> Vec3 {
+		(self.min + self.max) / 2.0
+	}
+
+	pub fn size(&self) -> Vec3 {
+		self.max - self.min
+	}
+
+	/// Whether the point is inside of the box or on its surface
+	pub fn contains(&self, point: Vec3) -> bool {
+		point.cmpge(self.min).all() && point.cmple(self.max).all()
+	}
+
+	/// Whether the boxes overlap or touch
+	pub fn intersects(&self, other: &Aabb) -> bool {
+		self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+	}
+
+	/// The smallest box containing both boxes
+	pub fn union(&self, other: &Aabb) -> Self {
+		Self::new(self.min.min(other.min), self.max.max(other.max))
+	}
+
+	/// The box around this one after the transformation, like the world space bounds of a mesh with the
+	/// [`GlobalTransform::compute_matrix`](crate::transform::GlobalTransform::compute_matrix) of its entity
+	pub fn transformed_by(&self, matrix: &Mat4) -> Self {
+		let corners = (0..8).map(|corner| {
+			let pick = |bit: u32, min: f32, max: f32| if corner & bit == 0 { min } else { max };
+			matrix.transform_point3(vec3(pick(1, self.min.x, self.max.x), pick(2, self.min.y, self.max.y), pick(4, self.min.z, self.max.z)))
+		});
+		// A box always has corners
+		Self::from_points(corners).unwrap_or(*self)
+	}
 }
 
 /// A private struct that only stores meshes size.
 enum MeshShape {
 	Quad(Vec2),
 	Circle(f32),
+	RoundedRect { size: Vec2, radius: f32 },
+	Ellipse(Vec2),
+	Ring { inner_radius: f32, outer_radius: f32 },
+	Arc { inner_radius: f32, outer_radius: f32, start: f32, sweep: f32 },
+	Polygon { sides: u32, radius: f32 },
+	Capsule(Vec2),
+	Grid { size: Vec2, cells: UVec2 },
+	Triangle([Vec2; 3]),
+	Polyline { points: Vec<Vec2>, closed: bool, style: StrokeStyle },
 }
 
 /// A Mesh constructor for generating/loading meshes. Meshes in `quadify` also contain color information
@@ -168,14 +444,75 @@ impl MeshBuilder {
 		self
 	}
 
-	/// Sets circle's point amount. The default value is `20`, but you can increase/reduce this number to a desired result.
+	/// Generates a [stroked](super::stroke::tessellate_stroke) polyline mesh through the points, relative to the mesh position.
+	/// A `closed` polyline connects the last point back to the first
+	pub fn as_polyline(&mut self, points: &[Vec2], closed: bool, style: StrokeStyle) -> &mut Self {
+		self.shape = Some(MeshShape::Polyline { points: points.to_vec(), closed, style });
+		self
+	}
+
+	/// Generates a rectangle mesh with corners rounded by `radius`, which is at most half of the shorter side
+	pub fn as_rounded_rect(&mut self, size: Vec2, radius: f32) -> &mut Self {
+		self.shape = Some(MeshShape::RoundedRect { size, radius });
+		self
+	}
+
+	/// Generates an ellipse mesh with the specified radii along the x and y axes
+	pub fn as_ellipse(&mut self, radii: Vec2) -> &mut Self {
+		self.shape = Some(MeshShape::Ellipse(radii));
+		self
+	}
+
+	/// Generates a ring (annulus) mesh between the two radii
+	pub fn as_ring(&mut self, inner_radius: f32, outer_radius: f32) -> &mut Self {
+		self.shape = Some(MeshShape::Ring { inner_radius, outer_radius });
+		self
+	}
+
+	/// Generates a part of a ring going counter-clockwise from `start` for `sweep` radians (clockwise when negative).
+	/// An `inner_radius` of `0` makes a circle sector
+	pub fn as_arc(&mut self, inner_radius: f32, outer_radius: f32, start: f32, sweep: f32) -> &mut Self {
+		self.shape = Some(MeshShape::Arc {
+			inner_radius,
+			outer_radius,
+			start,
+			sweep,
+		});
+		self
+	}
+
+	/// Generates a regular polygon mesh of at least 3 sides, with its first corner pointing right
+	pub fn as_polygon(&mut self, sides: u32, radius: f32) -> &mut Self {
+		self.shape = Some(MeshShape::Polygon { sides, radius });
+		self
+	}
+
+	/// Generates a capsule mesh of the specified size, rounded along its shorter side
+	pub fn as_capsule(&mut self, size: Vec2) -> &mut Self {
+		self.shape = Some(MeshShape::Capsule(size));
+		self
+	}
+
+	/// Generates a plane mesh of the specified size, subdivided into `cells` columns and rows
+	pub fn as_grid(&mut self, size: Vec2, cells: UVec2) -> &mut Self {
+		self.shape = Some(MeshShape::Grid { size, cells });
+		self
+	}
+
+	/// Generates a triangle mesh out of corners relative to the position
+	pub fn as_triangle(&mut self, a: Vec2, b: Vec2, c: Vec2) -> &mut Self {
+		self.shape = Some(MeshShape::Triangle([a, b, c]));
+		self
+	}
+
+	/// Sets the amount of points of round shapes: circles, ellipses, rings and capsules, with arcs and rounded corners getting their share of it.
+	/// The default value is `20`, but you can increase/reduce this number to a desired result. Round shapes need at least `3`.
 	pub fn circle_points(&mut self, n_points: u32) -> &mut Self {
-		debug_assert!(n_points >= 3, "Not enough points to represent a circle mesh. Minimum is 3");
 		self.circle_points = n_points;
 		self
 	}
 
-	/// Sets the center position of the mesh (for both circles and quads). This method cannot be ignored.
+	/// Sets the center position of the mesh, or the origin of triangle and polyline points. This method cannot be ignored.
 	pub fn at_position(&mut self, position: Vec3) -> &mut Self {
 		self.position = Some(position);
 		self
@@ -187,18 +524,63 @@ impl MeshBuilder {
 		self
 	}
 
-	/// Constructs and returns the desired mesh back.
+	/// Constructs and returns the desired mesh back. Texture coordinates span `0..1` over the bounds of the shape,
+	/// or of the whole circle for arcs.
 	///
-	/// *Note: panics if the shape wasn't provided*
-	pub fn build(&mut self) -> Mesh {
-		debug_assert!(self.shape.is_some(), "Can't build a Mesh without shape parameter provided.");
-		debug_assert!(self.position.is_some(), "Can't build a Mesh without position parameter provided.");
-
+	/// Fails if the shape or the position wasn't provided, or if a round shape has less than 3 [`circle_points`](MeshBuilder::circle_points)
+	pub fn build(&mut self) -> Result<Mesh, MeshError> {
+		let pos = self.position.ok_or(MeshError::MissingPosition)?;
+		let round = matches!(
+			self.shape,
+			Some(MeshShape::Circle(_) | MeshShape::RoundedRect { .. } | MeshShape::Ellipse(_) | MeshShape::Ring { .. } | MeshShape::Arc { .. } | MeshShape::Capsule(_))
+		);
+		if round && self.circle_points < 3 {
+			return Err(MeshError::TooFewPoints(self.circle_points));
+		}
+		let shape = self.shape.take().ok_or(MeshError::MissingShape)?;
 		let color = self.color.unwrap_or_default();
-		let pos = self.position.unwrap();
-		match self.shape.take().unwrap() {
+		let points = self.circle_points;
+
+		Ok(match shape {
 			MeshShape::Quad(size) => Mesh::quad(pos, size, color),
-			MeshShape::Circle(r) => Mesh::circle(pos, r, self.circle_points, color),
+			MeshShape::Circle(r) => Mesh::circle(pos, r, points, color),
+			MeshShape::RoundedRect { size, radius } => Mesh::rounded_rect(pos, size, radius, points, color),
+			MeshShape::Ellipse(radii) => Mesh::ellipse(pos, radii, points, color),
+			MeshShape::Ring { inner_radius, outer_radius } => Mesh::ring(pos, inner_radius, outer_radius, points, color),
+			MeshShape::Arc {
+				inner_radius,
+				outer_radius,
+				start,
+				sweep,
+			} => Mesh::arc(pos, inner_radius, outer_radius, start, sweep, points, color),
+			MeshShape::Polygon { sides, radius } => Mesh::polygon(pos, sides, radius, color),
+			MeshShape::Capsule(size) => Mesh::rounded_rect(pos, size, size.x.min(size.y) / 2.0, points, color),
+			MeshShape::Grid { size, cells } => Mesh::grid(pos, size, cells, color),
+			MeshShape::Triangle(corners) => Mesh::triangle(pos, corners, color),
+			MeshShape::Polyline { points, closed, style } => Mesh::polyline(pos, &points, closed, &style, color),
+		})
+	}
+}
+
+/// Reasons a [`MeshBuilder`] can't build a mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshError {
+	/// None of the `as_*` shape methods was called
+	MissingShape,
+	/// [`at_position`](MeshBuilder::at_position) wasn't called
+	MissingPosition,
+	/// A round shape was given less than 3 [`circle_points`](MeshBuilder::circle_points)
+	TooFewPoints(u32),
+}
+
+impl std::fmt::Display for MeshError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::MissingShape => write!(f, "Can't build a Mesh without shape parameter provided"),
+			Self::MissingPosition => write!(f, "Can't build a Mesh without position parameter provided"),
+			Self::TooFewPoints(points) => write!(f, "Can't build a round Mesh out of {} points, the minimum is 3", points),
 		}
 	}
 }
+
+impl std::error::Error for MeshError {}
@@ -0,0 +1,71 @@
+use glam::{Mat4, Vec4};
+use miniquad::{BufferId, VertexAttribute, VertexFormat};
+
+use super::geometry::VertexLayout;
+use super::pipeline::VertexLayoutId;
+use super::rgba::Rgba;
+
+/// Mesh geometry uploaded to the GPU once, to be drawn many times with [`RenderingBackend::draw_instanced`](super::RenderingBackend::draw_instanced).
+///
+/// Created with [`RenderingBackend::upload_mesh`](super::RenderingBackend::upload_mesh), and should be released with
+/// [`RenderingBackend::delete_mesh`](super::RenderingBackend::delete_mesh) once it's not needed anymore.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpuMesh {
+	pub(crate) vertex_buffer: BufferId,
+	pub(crate) index_buffer: BufferId,
+	pub(crate) indices_count: usize,
+	pub(crate) vertex_layout: VertexLayoutId,
+}
+
+impl GpuMesh {
+	pub fn indices_count(&self) -> usize {
+		self.indices_count
+	}
+
+	pub fn vertex_layout(&self) -> VertexLayoutId {
+		self.vertex_layout
+	}
+}
+
+/// Per-instance data of the default instanced pipelines
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstanceData {
+	/// Column-major transform, applied before the model matrix
+	pub transform: [f32; 16],
+	/// Texture region as `(x, y, width, height)` in UV coordinates, the mesh's UVs are mapped into it
+	pub uv_rect: [f32; 4],
+	/// Multiplied with the vertex color
+	pub color: Rgba,
+}
+
+impl InstanceData {
+	pub fn new(transform: Mat4, color: Rgba) -> Self {
+		Self {
+			transform: transform.to_cols_array(),
+			uv_rect: [0.0, 0.0, 1.0, 1.0],
+			color,
+		}
+	}
+
+	/// Sets the texture region of the instance, as `(x, y, width, height)` in UV coordinates
+	pub fn with_uv_rect(mut self, uv_rect: Vec4) -> Self {
+		self.uv_rect = uv_rect.to_array();
+		self
+	}
+}
+
+impl Default for InstanceData {
+	fn default() -> Self {
+		Self::new(Mat4::IDENTITY, Rgba::new(255, 255, 255, 255))
+	}
+}
+
+// SAFETY: `InstanceData` is `#[repr(C)]` of 84 bytes (20 floats and 4 bytes of color), so it doesn't have any padding
+unsafe impl VertexLayout for InstanceData {
+	const ATTRIBUTES: &'static [VertexAttribute] = &[
+		VertexAttribute::new("inst_transform", VertexFormat::Mat4),
+		VertexAttribute::new("inst_uv_rect", VertexFormat::Float4),
+		VertexAttribute::new("inst_color", VertexFormat::Byte4),
+	];
+}
@@ -0,0 +1,470 @@
+use std::ops::Range;
+
+use bevy_asset::Assets;
+use glam::{vec2, Vec2};
+use miniquad::TextureId;
+
+use super::geometry::Vertex;
+use super::rgba::{Rgba, WHITE};
+use super::text::GlyphCache;
+use super::{GeometryError, RenderingBackend};
+use crate::asset::{Font, GlyphQuad, Texture, TtfFont};
+
+/// Font of a [`TextSpan`], either a bitmap or a TrueType one
+#[derive(Debug, Clone, Copy)]
+pub enum FontRef<'a> {
+	Bitmap(&'a Font),
+	Ttf(&'a TtfFont),
+}
+
+impl<'a> From<&'a Font> for FontRef<'a> {
+	fn from(font: &'a Font) -> Self {
+		Self::Bitmap(font)
+	}
+}
+
+impl<'a> From<&'a TtfFont> for FontRef<'a> {
+	fn from(font: &'a TtfFont) -> Self {
+		Self::Ttf(font)
+	}
+}
+
+impl FontRef<'_> {
+	fn ascent(&self, size: f32) -> f32 {
+		match self {
+			Self::Bitmap(font) => font.base * bitmap_scale(font, size),
+			Self::Ttf(font) => font.ascent(size),
+		}
+	}
+
+	fn line_height(&self, size: f32) -> f32 {
+		match self {
+			Self::Bitmap(font) => font.line_height * bitmap_scale(font, size),
+			Self::Ttf(font) => font.line_height(size),
+		}
+	}
+
+	fn advance(&self, c: char, size: f32) -> f32 {
+		match self {
+			Self::Bitmap(font) => font.glyph(c).map(|glyph| glyph.advance * bitmap_scale(font, size)).unwrap_or(0.0),
+			Self::Ttf(font) => font.advance(c, size),
+		}
+	}
+
+	fn kerning(&self, first: char, second: char, size: f32) -> f32 {
+		match self {
+			Self::Bitmap(font) => font.kerning(first, second) * bitmap_scale(font, size),
+			Self::Ttf(font) => font.kerning(first, second, size),
+		}
+	}
+
+	fn same_font(&self, other: &FontRef) -> bool {
+		match (self, other) {
+			(Self::Bitmap(a), FontRef::Bitmap(b)) => std::ptr::eq(*a, *b),
+			(Self::Ttf(a), FontRef::Ttf(b)) => a.id() == b.id(),
+			_ => false,
+		}
+	}
+}
+
+/// Bitmap fonts are scaled from the size they were rendered at
+fn bitmap_scale(font: &Font, size: f32) -> f32 {
+	match font.size > 0.0 {
+		true => size / font.size,
+		false => 1.0,
+	}
+}
+
+/// A run of text sharing a font, size and color
+#[derive(Debug, Clone, Copy)]
+pub struct TextSpan<'a> {
+	pub text: &'a str,
+	pub font: FontRef<'a>,
+	/// Font size in pixels
+	pub size: f32,
+	pub color: Rgba,
+}
+
+impl<'a> TextSpan<'a> {
+	/// A white span
+	pub fn new(text: &'a str, font: impl Into<FontRef<'a>>, size: f32) -> Self {
+		Self {
+			text,
+			font: font.into(),
+			size,
+			color: WHITE,
+		}
+	}
+
+	pub fn with_color(mut self, color: Rgba) -> Self {
+		self.color = color;
+		self
+	}
+}
+
+/// Horizontal alignment of the lines of a [`TextLayout`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+	#[default]
+	Left,
+	Center,
+	Right,
+	/// Stretches the spaces of wrapped lines to fill the max width. The last line of every paragraph stays left aligned
+	Justify,
+}
+
+/// Vertical alignment of the lines of a [`TextLayout`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+	#[default]
+	Top,
+	Middle,
+	Bottom,
+}
+
+/// Lays out [`TextSpan`]s into lines inside a box, whose top left corner is the text position
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TextLayout {
+	/// Width the lines wrap at and are aligned within. Without it, lines only break at newlines and are aligned within the widest one
+	pub max_width: Option<f32>,
+	/// Height of the box the lines are vertically aligned within. Without it, the box is as high as the text
+	pub height: Option<f32>,
+	pub align: TextAlign,
+	pub vertical_align: VerticalAlign,
+}
+
+/// A glyph placed by [`TextLayout::layout`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+	pub character: char,
+	/// Index of the span the glyph comes from
+	pub span: usize,
+	/// Pen position on the baseline
+	pub position: Vec2,
+	/// How far the pen moves after the glyph, including the stretching of justified spaces
+	pub advance: f32,
+}
+
+/// A line of [`LaidOutText`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+	/// Range of the line in [`LaidOutText::glyphs`]
+	pub glyphs: Range<usize>,
+	/// Height of the baseline
+	pub baseline: f32,
+	/// Width without the trailing whitespace
+	pub width: f32,
+	pub height: f32,
+}
+
+/// Result of [`TextLayout::layout`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LaidOutText {
+	/// Every glyph except newlines and the spaces lines were wrapped at
+	pub glyphs: Vec<PositionedGlyph>,
+	pub lines: Vec<TextLine>,
+	/// Width of the widest line and height of all the lines
+	pub size: Vec2,
+}
+
+/// Where the texture of a [`TextQuad`] comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextPage {
+	/// A page of the bitmap font of a span
+	Font { span: usize, page: usize },
+	/// A page of the [`GlyphCache`]
+	Cache(usize),
+}
+
+/// A textured quad of a single glyph, as produced by [`LaidOutText::glyph_quads`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextQuad {
+	pub page: TextPage,
+	/// Top-left, top-right, bottom-left and bottom-right corners, drawn with [`Sprite::INDICES`](crate::prelude::Sprite::INDICES)
+	pub vertices: [Vertex; 4],
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LineGlyph {
+	span: usize,
+	character: char,
+	/// Pen position relative to the start of the line
+	x: f32,
+	advance: f32,
+}
+
+#[derive(Debug, Default)]
+struct Line {
+	glyphs: Vec<LineGlyph>,
+	pen: f32,
+	/// Ended by wrapping rather than by a newline or the end of the text
+	wrapped: bool,
+	/// Span of the last newline touching the line, which sets the height of empty lines
+	newline: Option<usize>,
+}
+
+impl Line {
+	fn push(&mut self, spans: &[TextSpan], span: usize, character: char) {
+		let font = &spans[span].font;
+		let size = spans[span].size;
+		if let Some(previous) = self.glyphs.last() {
+			let previous_span = &spans[previous.span];
+			if previous_span.font.same_font(font) && previous_span.size == size {
+				self.pen += font.kerning(previous.character, character, size);
+			}
+		}
+
+		let advance = font.advance(character, size);
+		self.glyphs.push(LineGlyph { span, character, x: self.pen, advance });
+		self.pen += advance;
+	}
+
+	/// Number of glyphs without the trailing whitespace
+	fn visible_len(&self) -> usize {
+		self.glyphs.iter().rposition(|glyph| !glyph.character.is_whitespace()).map_or(0, |index| index + 1)
+	}
+
+	fn width(&self) -> f32 {
+		match self.visible_len() {
+			0 => 0.0,
+			len => self.glyphs[len - 1].x + self.glyphs[len - 1].advance,
+		}
+	}
+
+	/// Ascent and descent of the tallest span on the line
+	fn metrics(&self, spans: &[TextSpan]) -> (f32, f32) {
+		let mut line_spans: Vec<usize> = self.glyphs.iter().map(|glyph| glyph.span).collect();
+		if line_spans.is_empty() {
+			line_spans.extend(self.newline.or((!spans.is_empty()).then_some(0)));
+		}
+
+		line_spans.into_iter().fold((0.0f32, 0.0f32), |(ascent, descent), span| {
+			let TextSpan { font, size, .. } = spans[span];
+			let span_ascent = font.ascent(size);
+			(ascent.max(span_ascent), descent.max(font.line_height(size) - span_ascent))
+		})
+	}
+}
+
+impl TextLayout {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_max_width(mut self, max_width: f32) -> Self {
+		self.max_width = Some(max_width);
+		self
+	}
+
+	pub fn with_height(mut self, height: f32) -> Self {
+		self.height = Some(height);
+		self
+	}
+
+	pub fn with_align(mut self, align: TextAlign) -> Self {
+		self.align = align;
+		self
+	}
+
+	pub fn with_vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+		self.vertical_align = vertical_align;
+		self
+	}
+
+	/// Size the spans take up, without laying out the glyphs
+	pub fn measure_text(&self, spans: &[TextSpan]) -> Vec2 {
+		let lines = self.break_lines(spans);
+		let width = lines.iter().map(|line| self.line_width(line)).fold(0.0, f32::max);
+		let height = lines.iter().map(|line| line.metrics(spans)).map(|(ascent, descent)| ascent + descent).sum();
+		vec2(width, height)
+	}
+
+	/// Breaks the spans into lines and positions their glyphs, with `position` as the top left corner of the layout box
+	pub fn layout(&self, spans: &[TextSpan], position: Vec2) -> LaidOutText {
+		let lines = self.break_lines(spans);
+		let metrics: Vec<_> = lines.iter().map(|line| line.metrics(spans)).collect();
+		let widest = lines.iter().map(|line| line.width()).fold(0.0, f32::max);
+		let total_height: f32 = metrics.iter().map(|(ascent, descent)| ascent + descent).sum();
+
+		let box_width = self.max_width.unwrap_or(widest);
+		let box_height = self.height.unwrap_or(total_height);
+		let mut top = position.y
+			- match self.vertical_align {
+				VerticalAlign::Top => 0.0,
+				VerticalAlign::Middle => (box_height - total_height) / 2.0,
+				VerticalAlign::Bottom => box_height - total_height,
+			};
+
+		let mut text = LaidOutText {
+			glyphs: Vec::with_capacity(lines.iter().map(|line| line.glyphs.len()).sum()),
+			lines: Vec::with_capacity(lines.len()),
+			size: Vec2::ZERO,
+		};
+
+		for (line, (ascent, descent)) in lines.iter().zip(metrics) {
+			let width = line.width();
+			let visible = line.visible_len();
+			let spaces = line.glyphs[..visible].iter().filter(|glyph| glyph.character.is_whitespace()).count();
+
+			let (offset, stretch) = match self.align {
+				TextAlign::Left => (0.0, 0.0),
+				TextAlign::Center => ((box_width - width) / 2.0, 0.0),
+				TextAlign::Right => (box_width - width, 0.0),
+				TextAlign::Justify if line.wrapped && spaces > 0 => (0.0, (box_width - width).max(0.0) / spaces as f32),
+				TextAlign::Justify => (0.0, 0.0),
+			};
+
+			let baseline = top - ascent;
+			let start = text.glyphs.len();
+			let mut shift = 0.0;
+			for (index, glyph) in line.glyphs.iter().enumerate() {
+				let extra = match index < visible && glyph.character.is_whitespace() {
+					true => stretch,
+					false => 0.0,
+				};
+				text.glyphs.push(PositionedGlyph {
+					character: glyph.character,
+					span: glyph.span,
+					position: vec2(position.x + offset + glyph.x + shift, baseline),
+					advance: glyph.advance + extra,
+				});
+				shift += extra;
+			}
+
+			let width = width + stretch * spaces as f32;
+			text.lines.push(TextLine {
+				glyphs: start..text.glyphs.len(),
+				baseline,
+				width,
+				height: ascent + descent,
+			});
+			text.size = vec2(text.size.x.max(width), text.size.y + ascent + descent);
+			top -= ascent + descent;
+		}
+
+		text
+	}
+
+	/// Width of the line after justification
+	fn line_width(&self, line: &Line) -> f32 {
+		let spaces = line.glyphs[..line.visible_len()].iter().any(|glyph| glyph.character.is_whitespace());
+		match (self.align, self.max_width) {
+			(TextAlign::Justify, Some(max_width)) if line.wrapped && spaces => line.width().max(max_width),
+			_ => line.width(),
+		}
+	}
+
+	/// Greedy line breaking at whitespace. Words wider than the max width are broken between characters
+	fn break_lines(&self, spans: &[TextSpan]) -> Vec<Line> {
+		let mut lines = Vec::new();
+		let mut line = Line::default();
+
+		for (span_index, span) in spans.iter().enumerate() {
+			for c in span.text.chars() {
+				if c == '\n' {
+					line.newline = Some(span_index);
+					lines.push(std::mem::take(&mut line));
+					line.newline = Some(span_index);
+					continue;
+				}
+
+				line.push(spans, span_index, c);
+				let max_width = match self.max_width {
+					Some(max_width) => max_width,
+					None => continue,
+				};
+				let previous = &line.glyphs[..line.glyphs.len() - 1];
+				if c.is_whitespace() || line.pen <= max_width || previous.iter().all(|glyph| glyph.character.is_whitespace()) {
+					continue;
+				}
+
+				// Break after the last whitespace following a word, or before the overflowing character
+				let character = line.glyphs.pop().unwrap();
+				let word_start = line
+					.glyphs
+					.iter()
+					.rposition(|glyph| glyph.character.is_whitespace())
+					.filter(|index| line.glyphs[..*index].iter().any(|glyph| !glyph.character.is_whitespace()))
+					.map_or(line.glyphs.len(), |index| index + 1);
+
+				let rest = line.glyphs.split_off(word_start);
+				line.glyphs.truncate(line.visible_len());
+				line.wrapped = true;
+				lines.push(std::mem::take(&mut line));
+
+				for glyph in rest.into_iter().chain([character]) {
+					line.push(spans, glyph.span, glyph.character);
+				}
+			}
+		}
+
+		lines.push(line);
+		lines
+	}
+}
+
+impl LaidOutText {
+	/// Quads of the laid out glyphs. `spans` have to be the ones the text was laid out from.
+	/// TrueType glyphs are rasterized into the cache, which has to be [flushed](GlyphCache::flush) before drawing the quads
+	pub fn glyph_quads(&self, spans: &[TextSpan], cache: &mut GlyphCache) -> Vec<TextQuad> {
+		let mut quads = Vec::with_capacity(self.glyphs.len());
+		let mut ttf_glyphs = Vec::new();
+
+		for glyph in &self.glyphs {
+			let span = &spans[glyph.span];
+			match span.font {
+				FontRef::Bitmap(font) => {
+					let scale = bitmap_scale(font, span.size);
+					let top = glyph.position + vec2(0.0, font.base * scale);
+					if let Some(quad) = font.glyph(glyph.character).and_then(|bitmap_glyph| font.glyph_quad(bitmap_glyph, top, scale, span.color)) {
+						quads.push(TextQuad {
+							page: TextPage::Font { span: glyph.span, page: quad.page },
+							vertices: quad.vertices,
+						});
+					}
+				}
+				FontRef::Ttf(font) => ttf_glyphs.push((font, glyph.character, span.size, glyph.position, span.color)),
+			}
+		}
+
+		quads.extend(cache.rasterize(ttf_glyphs).into_iter().map(|quad| TextQuad {
+			page: TextPage::Cache(quad.page),
+			vertices: quad.vertices,
+		}));
+		quads
+	}
+}
+
+impl RenderingBackend {
+	/// Draws laid out text using the current pipeline, with one [`geometry`](RenderingBackend::geometry) call per texture. `spans` have to be the ones the text was laid out from.
+	///
+	/// Bitmap font pages that aren't uploaded yet are skipped.
+	pub fn draw_text_layout(&mut self, text: &LaidOutText, spans: &[TextSpan], cache: &mut GlyphCache, textures: &Assets<Texture>) -> Result<(), GeometryError> {
+		let quads = text.glyph_quads(spans, cache);
+		cache.flush(self);
+
+		let mut pages: Vec<Option<TextureId>> = Vec::new();
+		let quads: Vec<GlyphQuad> = quads
+			.into_iter()
+			.map(|quad| {
+				let texture = match quad.page {
+					TextPage::Font { span, page } => match spans[span].font {
+						FontRef::Bitmap(font) => font.pages.get(page).and_then(|page| textures.get(page)).map(Texture::id),
+						FontRef::Ttf(_) => None,
+					},
+					TextPage::Cache(page) => cache.page_texture(page),
+				};
+				let page = match pages.iter().position(|page| *page == texture) {
+					Some(page) => page,
+					None => {
+						pages.push(texture);
+						pages.len() - 1
+					}
+				};
+				GlyphQuad { page, vertices: quad.vertices }
+			})
+			.collect();
+
+		self.draw_glyph_quads(&quads, &pages)
+	}
+}
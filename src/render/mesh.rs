@@ -0,0 +1,83 @@
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::{
+	schedule::SystemSet,
+	system::{Local, NonSendMut, Query, Res},
+};
+
+use super::camera::{Camera2D, CurrentCameraTag, RenderTarget};
+use super::geometry::{Mesh, Vertex};
+use super::material::Material;
+use super::RenderingBackend;
+use crate::asset::Texture;
+use crate::transform::GlobalTransform;
+
+/// System set of the default mesh rendering, in the [`MiniquadDraw`](crate::window::state::MiniquadDraw) schedule.
+/// Order your own drawing systems against it to draw below or on top of meshes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct MeshRenderSet;
+
+type Renderables<'w, 's> = Query<'w, 's, (&'static Handle<Mesh>, Option<&'static Handle<Material>>, Option<&'static Handle<Texture>>, &'static GlobalTransform)>;
+
+/// Draws all entities with a [`Handle<Mesh>`] and a [`GlobalTransform`] from the view of the current camera, batched by the [`RenderingBackend`].
+/// Vertices are transformed on the CPU, so meshes sharing the material and the texture end up in the same draw call.
+///
+/// The optional [`Handle<Material>`] and [`Handle<Texture>`] components set the pipeline and the texture of the mesh.
+/// Meshes are drawn from the lowest to the highest global `z` translation.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_meshes(
+	mut render_ctx: NonSendMut<RenderingBackend>,
+	current_camera: Res<CurrentCameraTag>,
+	cameras: Query<(&Camera2D, &RenderTarget)>,
+	meshes: Res<Assets<Mesh>>,
+	materials: Res<Assets<Material>>,
+	textures: Res<Assets<Texture>>,
+	renderables: Renderables,
+	mut vertices: Local<Vec<Vertex>>,
+) {
+	if renderables.is_empty() {
+		return;
+	}
+
+	let (camera, render_target) = match cameras.get(current_camera.0) {
+		Ok(camera) => camera,
+		Err(_e) => {
+			#[cfg(feature = "log")]
+			bevy_log::error!("Failed to get the current Camera: {:?}, {:?}", current_camera.0, _e);
+			return;
+		}
+	};
+
+	let mut renderables: Vec<_> = renderables.iter().collect();
+	renderables.sort_by(|(.., a), (.., b)| a.translation().z.total_cmp(&b.translation().z));
+
+	render_ctx.begin_camera(camera, render_target);
+
+	for (mesh, material, texture, transform) in renderables {
+		// The mesh could be still loading
+		let mesh = match meshes.get(mesh) {
+			Some(mesh) => mesh,
+			None => continue,
+		};
+
+		match material.and_then(|material| materials.get(material)) {
+			Some(material) => render_ctx.set_material(material),
+			None => render_ctx.pipeline(None),
+		}
+		let texture = texture.and_then(|texture| textures.get(texture)).map(Texture::id);
+		render_ctx.texture(texture.as_ref());
+
+		let matrix = transform.compute_matrix();
+		vertices.clear();
+		vertices.extend(mesh.vertices.iter().map(|vertex| Vertex {
+			position: matrix.transform_point3(vertex.position),
+			..*vertex
+		}));
+
+		if let Err(_err) = render_ctx.geometry(&vertices, &mesh.indices) {
+			#[cfg(feature = "log")]
+			bevy_log::error!("Failed to draw a mesh: {}", _err);
+		}
+	}
+
+	render_ctx.end_camera(camera);
+}
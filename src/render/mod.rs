@@ -1,9 +1,10 @@
+use bevy_ecs::schedule::IntoSystemConfigs;
 use bevy_ecs::system::{NonSendMut, Query, Res, Resource};
-use glam::{vec2, vec3};
 use miniquad::*;
 use miniquad::{window, PassAction, RenderingBackend as MqdRenderingBackend};
 
-use self::geometry::Vertex;
+use self::geometry::{vertex_bytes, Mesh, Vertex, VertexLayout};
+use self::instancing::GpuMesh;
 use self::material::Material;
 use self::rgba::Rgba;
 use crate::window::state;
@@ -12,14 +13,26 @@ use super::render::{material::*, pipeline::*};
 
 pub mod camera;
 pub mod geometry;
+pub mod instancing;
+pub mod layout;
 pub mod material;
+pub mod mesh;
+pub mod path;
 pub mod pipeline;
+#[cfg(feature = "testing")]
+pub mod recording;
 pub mod rgba;
+pub mod sdf;
+pub mod shapes;
+pub mod sprite;
+pub mod stroke;
+pub mod text;
 
 /// Miniquad rendering backend object.
 pub struct RenderingBackend {
 	backend: Box<dyn MqdRenderingBackend>,
 	start_time: f64,
+	screen_size: Option<(f32, f32)>,
 
 	white_texture: miniquad::TextureId,
 
@@ -31,6 +44,10 @@ pub struct RenderingBackend {
 	draw_calls: Vec<DrawCall>,
 	draw_calls_count: usize,
 	draw_call_bindings: Vec<miniquad::Bindings>,
+	/// Per-instance data buffers of instanced draw calls, created on demand
+	draw_call_instance_buffers: Vec<Option<BufferId>>,
+	/// Indices converted to 16 bits before being uploaded, see [`RenderingBackend::update_drawcall_capacity`]
+	short_indices: Vec<u16>,
 }
 
 // For ease of use
@@ -56,14 +73,19 @@ impl Default for RenderingBackend {
 
 impl RenderingBackend {
 	pub fn new() -> Self {
-		let mut backend = window::new_rendering_backend();
+		Self::from_backend(window::new_rendering_backend())
+	}
 
+	/// Creates the rendering backend on top of any miniquad backend, for example a `RecordingBackend` (with the `testing` feature)
+	/// when there's no GL context to render to.
+	pub fn from_backend(mut backend: Box<dyn MqdRenderingBackend>) -> Self {
 		let white_texture = backend.new_texture_from_rgba8(1, 1, &[255, 255, 255, 255]);
 		let pipelines = pipeline::PipelineStorage::new(&mut *backend);
 
 		Self {
 			backend,
 			start_time: miniquad::date::now(),
+			screen_size: None,
 
 			white_texture,
 
@@ -74,38 +96,79 @@ impl RenderingBackend {
 			state: GlState::default(),
 			draw_calls: Vec::with_capacity(200),
 			draw_call_bindings: Vec::with_capacity(64),
+			draw_call_instance_buffers: Vec::new(),
 			draw_calls_count: 0,
+			short_indices: Vec::new(),
 		}
 	}
 
 	pub fn make_pipeline(&mut self, shader: miniquad::ShaderSource, params: PipelineParams, uniforms: Vec<(String, UniformType)>, textures: Vec<String>) -> Result<GlPipeline, ShaderError> {
+		self.make_pipeline_with_layout::<Vertex>(shader, params, uniforms, textures)
+	}
+
+	/// The same as [`RenderingBackend::make_pipeline`], but the pipeline takes vertices of a custom [`VertexLayout`]
+	pub fn make_pipeline_with_layout<V: VertexLayout>(&mut self, shader: miniquad::ShaderSource, params: PipelineParams, uniforms: Vec<(String, UniformType)>, textures: Vec<String>) -> Result<GlPipeline, ShaderError> {
+		let shader = self.new_pipeline_shader(shader, &uniforms, &textures)?;
+
+		Ok(self.pipelines.make_pipeline::<V>(&mut *self.backend, shader, params, uniforms, textures))
+	}
+
+	/// Makes a pipeline for [`RenderingBackend::draw_instanced`], taking mesh vertices of `V` and per-instance data of `I`.
+	/// The attributes of `I` come from the second vertex buffer.
+	pub fn make_instanced_pipeline<V: VertexLayout, I: VertexLayout>(
+		&mut self,
+		shader: miniquad::ShaderSource,
+		params: PipelineParams,
+		uniforms: Vec<(String, UniformType)>,
+		textures: Vec<String>,
+	) -> Result<GlPipeline, ShaderError> {
+		let shader = self.new_pipeline_shader(shader, &uniforms, &textures)?;
+
+		Ok(self.pipelines.make_instanced_pipeline::<V, I>(&mut *self.backend, shader, params, uniforms, textures))
+	}
+
+	fn new_pipeline_shader(&mut self, shader: miniquad::ShaderSource, uniforms: &[(String, UniformType)], textures: &[String]) -> Result<ShaderId, ShaderError> {
 		let mut shader_meta: ShaderMeta = pipeline::shader::meta();
 
-		for uniform in &uniforms {
+		for uniform in uniforms {
 			shader_meta.uniforms.uniforms.push(UniformDesc::new(&uniform.0, uniform.1));
 		}
 
-		for texture in &textures {
+		for texture in textures {
 			if texture == "Texture" {
 				panic!("you can't use name `Texture` for your texture. This name is reserved for the texture that will be drawn with that material");
 			}
 			shader_meta.images.push(texture.clone());
 		}
-		let shader = self.backend.new_shader(shader, shader_meta)?;
-
-		Ok(self.pipelines.make_pipeline(&mut *self.backend, shader, params, uniforms, textures))
+		self.backend.new_shader(shader, shader_meta)
 	}
 
 	/// Tries to compile shaders and create a pipeline, and on success will return a new [`Material`]
 	pub fn request_material(&mut self, shader: ShaderSource, params: MaterialParams) -> Result<Material, ShaderError> {
-		match self.make_pipeline(shader, params.pipeline_params, params.uniforms, params.textures) {
+		self.request_material_with_layout::<Vertex>(shader, params)
+	}
+
+	/// The same as [`RenderingBackend::request_material`], but the material's shader takes vertices of a custom [`VertexLayout`].
+	/// Geometry drawn with this material has to be of the same vertex type.
+	pub fn request_material_with_layout<V: VertexLayout>(&mut self, shader: ShaderSource, params: MaterialParams) -> Result<Material, ShaderError> {
+		match self.make_pipeline_with_layout::<V>(shader, params.pipeline_params, params.uniforms, params.textures) {
+			Ok(pipeline) => Ok(Material { pipeline }),
+			Err(err) => Err(err),
+		}
+	}
+
+	/// The same as [`RenderingBackend::request_material`], but the material draws instanced meshes with [`RenderingBackend::draw_instanced`],
+	/// taking mesh vertices of `V` and per-instance data of `I`
+	pub fn request_instanced_material<V: VertexLayout, I: VertexLayout>(&mut self, shader: ShaderSource, params: MaterialParams) -> Result<Material, ShaderError> {
+		match self.make_instanced_pipeline::<V, I>(shader, params.pipeline_params, params.uniforms, params.textures) {
 			Ok(pipeline) => Ok(Material { pipeline }),
 			Err(err) => Err(err),
 		}
 	}
 
-	pub fn remove_material(&mut self, material: Material) {
-		self.delete_pipeline(material.pipeline);
+	/// Deletes the material's pipeline. Using the material afterwards returns [`PipelineError::InvalidHandle`]
+	pub fn remove_material(&mut self, material: Material) -> Result<(), PipelineError> {
+		self.delete_pipeline(material.pipeline)
 	}
 
 	/// Clears all draw calls and clears the screen with specified color
@@ -142,24 +205,29 @@ impl RenderingBackend {
 		let white_texture = self.white_texture;
 
 		for _ in 0..self.draw_calls.len() - self.draw_call_bindings.len() {
-			let vertex_buffer = self.backend.new_buffer(BufferType::VertexBuffer, BufferUsage::Stream, BufferSource::empty::<Vertex>(self.max_vertices));
-			let index_buffer = self.backend.new_buffer(BufferType::IndexBuffer, BufferUsage::Stream, BufferSource::empty::<u16>(self.max_indices));
-			let bindings = Bindings {
-				vertex_buffers: vec![vertex_buffer],
-				index_buffer,
-				images: vec![white_texture],
-			};
-
+			let bindings = self.new_draw_call_bindings();
 			self.draw_call_bindings.push(bindings);
 		}
 		assert_eq!(self.draw_call_bindings.len(), self.draw_calls.len());
+		self.draw_call_instance_buffers.resize(self.draw_calls.len(), None);
 
-		let (screen_width, screen_height) = miniquad::window::screen_size();
+		let wide_indices = self.wide_indices();
+		let (screen_width, screen_height) = self.screen_size();
 		let time = (miniquad::date::now() - self.start_time) as f32;
 		let time = glam::vec4(time, time.sin(), time.cos(), 0.);
 
-		for (dc, bindings) in self.draw_calls[0..self.draw_calls_count].iter_mut().zip(self.draw_call_bindings.iter_mut()) {
-			let pipeline = self.pipelines.get_pipeline_mut(dc.pipeline);
+		for ((dc, bindings), instance_buffer) in self.draw_calls[0..self.draw_calls_count].iter_mut().zip(self.draw_call_bindings.iter_mut()).zip(self.draw_call_instance_buffers.iter_mut()) {
+			let pipeline = match self.pipelines.get_pipeline_mut(dc.pipeline) {
+				Ok(pipeline) => pipeline,
+				Err(_err) => {
+					// The pipeline was deleted after the geometry was queued
+					#[cfg(feature = "log")]
+					bevy_log::error!("Skipping draw call: {}", _err);
+					dc.vertices_count = 0;
+					dc.indices_count = 0;
+					continue;
+				}
+			};
 
 			let (width, height) = if let Some(render_pass) = dc.render_pass {
 				let render_texture = self.backend.render_pass_texture(render_pass);
@@ -175,8 +243,35 @@ impl RenderingBackend {
 				self.backend.begin_default_pass(PassAction::Nothing);
 			}
 
-			self.backend.buffer_update(bindings.vertex_buffers[0], BufferSource::slice(dc.vertices()));
-			self.backend.buffer_update(bindings.index_buffer, BufferSource::slice(dc.indices()));
+			if let Some(instanced) = &dc.instanced {
+				let instance_bytes = &instanced.instances[0..instanced.instances_count * instanced.instance_layout.size()];
+				let buffer = match *instance_buffer {
+					Some(buffer) if self.backend.buffer_size(buffer) >= instance_bytes.len() => buffer,
+					_ => {
+						if let Some(buffer) = instance_buffer.take() {
+							self.backend.delete_buffer(buffer);
+						}
+						let buffer = self.backend.new_buffer(BufferType::VertexBuffer, BufferUsage::Stream, BufferSource::empty::<u8>(instance_bytes.len().next_power_of_two()));
+						*instance_buffer = Some(buffer);
+						buffer
+					}
+				};
+				self.backend.buffer_update(buffer, BufferSource::slice(instance_bytes));
+			} else {
+				let vertex_buffer_size = self.max_vertices * dc.vertex_layout.size();
+				if self.backend.buffer_size(bindings.vertex_buffers[0]) < vertex_buffer_size {
+					self.backend.delete_buffer(bindings.vertex_buffers[0]);
+					bindings.vertex_buffers[0] = self.backend.new_buffer(BufferType::VertexBuffer, BufferUsage::Stream, BufferSource::empty::<u8>(vertex_buffer_size));
+				}
+				self.backend.buffer_update(bindings.vertex_buffers[0], BufferSource::slice(dc.vertex_bytes()));
+				if wide_indices {
+					self.backend.buffer_update(bindings.index_buffer, BufferSource::slice(dc.indices()));
+				} else {
+					self.short_indices.clear();
+					self.short_indices.extend(dc.indices().iter().map(|index| *index as u16));
+					self.backend.buffer_update(bindings.index_buffer, BufferSource::slice(&self.short_indices));
+				}
+			}
 
 			bindings.images[0] = dc.texture.unwrap_or(white_texture);
 			bindings.images.resize(1 + pipeline.textures.len(), white_texture);
@@ -198,7 +293,10 @@ impl RenderingBackend {
 			} else {
 				self.backend.apply_scissor_rect(0, 0, width as i32, height as i32);
 			}
-			self.backend.apply_bindings(bindings);
+			match (&dc.instanced, *instance_buffer) {
+				(Some(instanced), Some(buffer)) => self.backend.apply_bindings_from_slice(&[instanced.mesh.vertex_buffer, buffer], instanced.mesh.index_buffer, &bindings.images),
+				_ => self.backend.apply_bindings(bindings),
+			}
 
 			if let Some(ref uniforms) = dc.uniforms {
 				for i in 0..uniforms.len() {
@@ -209,7 +307,10 @@ impl RenderingBackend {
 			pipeline.set_uniform("Model", dc.model);
 			pipeline.set_uniform("_Time", time);
 			self.backend.apply_uniforms_from_bytes(pipeline.uniforms_data.as_ptr(), pipeline.uniforms_data.len());
-			self.backend.draw(0, dc.indices_count as i32, 1);
+			match &dc.instanced {
+				Some(instanced) => self.backend.draw(0, instanced.mesh.indices_count as i32, instanced.instances_count as i32),
+				None => self.backend.draw(0, dc.indices_count as i32, 1),
+			}
 			self.backend.end_render_pass();
 
 			dc.vertices_count = 0;
@@ -219,6 +320,55 @@ impl RenderingBackend {
 		self.draw_calls_count = 0;
 	}
 
+	/// Targets the render target and the viewport of the camera, used by the default renderers
+	pub(crate) fn begin_camera(&mut self, camera: &camera::Camera2D, render_target: &camera::RenderTarget) {
+		let render_pass = match render_target {
+			camera::RenderTarget::Window => None,
+			camera::RenderTarget::Texture { render_pass, .. } => Some(*render_pass),
+		};
+		self.render_pass(render_pass);
+		self.depth_test(render_target.depth_test_enabled());
+		self.viewport(camera.viewport);
+		self.draw_mode(DrawMode::Triangles);
+	}
+
+	/// Draws everything queued from the view of the camera, and resets the state changed since [`RenderingBackend::begin_camera`]
+	pub(crate) fn end_camera(&mut self, camera: &camera::Camera2D) {
+		self.draw(camera.matrix());
+
+		self.pipeline(None);
+		self.texture(None);
+		self.render_pass(None);
+		self.depth_test(false);
+		self.viewport(None);
+	}
+
+	/// Draw calls collected since the last [`RenderingBackend::draw`]
+	pub fn draw_calls(&self) -> &[DrawCall] {
+		&self.draw_calls[0..self.draw_calls_count]
+	}
+
+	/// Draw calls address at most `max_vertices` vertices, so 16-bit index buffers are enough unless the capacity is raised above that
+	fn wide_indices(&self) -> bool {
+		self.max_vertices > u16::MAX as usize + 1
+	}
+
+	fn new_draw_call_bindings(&mut self) -> Bindings {
+		let index_source = if self.wide_indices() {
+			BufferSource::empty::<u32>(self.max_indices)
+		} else {
+			BufferSource::empty::<u16>(self.max_indices)
+		};
+
+		let vertex_buffer = self.backend.new_buffer(BufferType::VertexBuffer, BufferUsage::Stream, BufferSource::empty::<Vertex>(self.max_vertices));
+		let index_buffer = self.backend.new_buffer(BufferType::IndexBuffer, BufferUsage::Stream, index_source);
+		Bindings {
+			vertex_buffers: vec![vertex_buffer],
+			index_buffer,
+			images: vec![self.white_texture],
+		}
+	}
+
 	pub fn get_active_render_pass(&self) -> Option<RenderPass> {
 		self.state.render_pass
 	}
@@ -251,10 +401,21 @@ impl RenderingBackend {
 	}
 
 	pub fn get_viewport(&self) -> (i32, i32, i32, i32) {
-		let (w, h) = miniquad::window::screen_size();
+		let (w, h) = self.screen_size();
 		self.state.viewport.unwrap_or((0, 0, w as _, h as _))
 	}
 
+	/// Overrides the size of the default framebuffer. When `None` (default), the size is queried from the window.
+	///
+	/// *Note: required when rendering without a window, like with a `RecordingBackend`*
+	pub fn set_screen_size(&mut self, size: Option<(f32, f32)>) {
+		self.screen_size = size;
+	}
+
+	fn screen_size(&self) -> (f32, f32) {
+		self.screen_size.unwrap_or_else(miniquad::window::screen_size)
+	}
+
 	pub fn push_model_matrix(&mut self, matrix: glam::Mat4) {
 		self.state.model_stack.push(self.state.model() * matrix);
 	}
@@ -292,17 +453,97 @@ impl RenderingBackend {
 	/// 
 	/// The new draw call will be allocated, if previous + new geometry exceeds the vertex or indices limit (`10000` and `5000`) 
 	/// 
+	/// Geometry that doesn't fit into a single draw call is split on primitive (triangle or line) boundaries across as many draw calls as needed.
+	/// Fails if a single primitive can't fit into a draw call, if an index is out of range of the vertices, or if the current pipeline was deleted.
+	/// 
+	/// Indices can be either `u16` or `u32`. Vertices have to be of the [`VertexLayout`] of the current pipeline ([`Vertex`] for the default ones).
+	/// 
 	/// You can manually allocate a new draw call by calling [`RenderingBackend::break_batching`]
-	pub fn geometry(&mut self, vertices: &[Vertex], indices: &[u16]) {
-		if vertices.len() >= self.max_vertices || indices.len() >= self.max_indices {
-			#[cfg(feature = "log")]
-			bevy_log::warn!("geometry() exceeded max drawcall size, clamping");
+	pub fn geometry<V: VertexLayout, I: Copy + Into<u32>>(&mut self, vertices: &[V], indices: &[I]) -> Result<(), GeometryError> {
+		let pipeline = self.state.pipeline.unwrap_or(self.pipelines.get_default_by(self.state.draw_mode, self.state.depth_test_enable));
+		let pipeline = self.pipelines.get_pipeline(pipeline)?;
+		if pipeline.vertex_layout != VertexLayoutId::of::<V>() {
+			return Err(GeometryError::VertexLayoutMismatch {
+				expected: pipeline.vertex_layout.name(),
+				found: std::any::type_name::<V>(),
+			});
+		}
+		if let Some(instance_layout) = pipeline.instance_layout {
+			return Err(GeometryError::InstanceLayoutMismatch {
+				expected: Some(instance_layout.name()),
+				found: None,
+			});
+		}
+		if let Some(index) = indices.iter().map(|index| (*index).into() as usize).find(|index| *index >= vertices.len()) {
+			return Err(GeometryError::IndexOutOfRange(index));
 		}
 
-		let vertices = &vertices[0..self.max_vertices.min(vertices.len())];
-		let indices = &indices[0..self.max_indices.min(indices.len())];
+		if vertices.len() <= self.max_vertices && indices.len() <= self.max_indices {
+			self.push_geometry(vertices, indices);
+			return Ok(());
+		}
 
+		let primitive_size = match self.state.draw_mode {
+			DrawMode::Triangles => 3,
+			DrawMode::Lines => 2,
+		};
+		if primitive_size > self.max_vertices || primitive_size > self.max_indices {
+			return Err(GeometryError::PrimitiveTooLarge {
+				max_vertices: self.max_vertices,
+				max_indices: self.max_indices,
+			});
+		}
+
+		// Maps the vertex indices of the whole geometry to the vertices of the current chunk
+		let mut remap: Vec<Option<u32>> = vec![None; vertices.len()];
+		let mut chunk_sources = Vec::with_capacity(self.max_vertices);
+		let mut chunk_vertices = Vec::with_capacity(self.max_vertices);
+		let mut chunk_indices = Vec::with_capacity(self.max_indices);
+
+		let mut primitive_buffer = [0; 3];
+		for chunk in indices.chunks(primitive_size) {
+			for (i, index) in chunk.iter().enumerate() {
+				primitive_buffer[i] = (*index).into() as usize;
+			}
+
+			let primitive = &primitive_buffer[..chunk.len()];
+			let new_vertices = primitive
+				.iter()
+				.enumerate()
+				.filter(|(i, index)| remap[**index].is_none() && !primitive[..*i].contains(index))
+				.count();
+
+			if chunk_vertices.len() + new_vertices > self.max_vertices || chunk_indices.len() + primitive.len() > self.max_indices {
+				self.push_geometry(&chunk_vertices, &chunk_indices);
+
+				for source in chunk_sources.drain(..) {
+					remap[source] = None;
+				}
+				chunk_vertices.clear();
+				chunk_indices.clear();
+			}
+
+			for &index in primitive {
+				let local = *remap[index].get_or_insert_with(|| {
+					chunk_sources.push(index);
+					chunk_vertices.push(vertices[index]);
+					(chunk_vertices.len() - 1) as u32
+				});
+				chunk_indices.push(local);
+			}
+		}
+
+		if !chunk_indices.is_empty() {
+			self.push_geometry(&chunk_vertices, &chunk_indices);
+		}
+
+		Ok(())
+	}
+
+	/// Append geometry that is known to fit into a single draw call
+	fn push_geometry<V: VertexLayout, I: Copy + Into<u32>>(&mut self, vertices: &[V], indices: &[I]) {
 		let pip = self.state.pipeline.unwrap_or(self.pipelines.get_default_by(self.state.draw_mode, self.state.depth_test_enable));
+		let layout = VertexLayoutId::of::<V>();
 
 		let previous_dc_ix = if self.draw_calls_count == 0 { None } else { Some(self.draw_calls_count - 1) };
 		let previous_dc = previous_dc_ix.and_then(|ix| self.draw_calls.get(ix));
@@ -315,65 +556,148 @@ impl RenderingBackend {
 				|| draw_call.pipeline != pip
 				|| draw_call.render_pass != self.state.render_pass
 				|| draw_call.draw_mode != self.state.draw_mode
-				|| draw_call.vertices_count >= self.max_vertices - vertices.len()
-				|| draw_call.indices_count >= self.max_indices - indices.len()
+				|| draw_call.vertex_layout != layout
+				|| draw_call.vertices_count + vertices.len() > self.max_vertices
+				|| draw_call.indices_count + indices.len() > self.max_indices
+				|| draw_call.instanced.is_some()
 				|| self.state.break_batching
 		}) {
-			let uniforms = self.state.pipeline.map(|pipeline| self.pipelines.get_pipeline_mut(pipeline).uniforms_data.clone());
-
-			if self.draw_calls_count >= self.draw_calls.len() {
-				self.draw_calls.push(DrawCall::new(
-					self.state.texture,
-					self.state.model(),
-					self.state.draw_mode,
-					pip,
-					uniforms.clone(),
-					self.state.render_pass,
-					self.max_vertices,
-					self.max_indices,
-				));
-			}
-			self.draw_calls[self.draw_calls_count].texture = self.state.texture;
-			self.draw_calls[self.draw_calls_count].uniforms = uniforms;
-			self.draw_calls[self.draw_calls_count].vertices_count = 0;
-			self.draw_calls[self.draw_calls_count].indices_count = 0;
-			self.draw_calls[self.draw_calls_count].clip = self.state.clip;
-			self.draw_calls[self.draw_calls_count].viewport = self.state.viewport;
-			self.draw_calls[self.draw_calls_count].model = self.state.model();
-			self.draw_calls[self.draw_calls_count].pipeline = pip;
-			self.draw_calls[self.draw_calls_count].render_pass = self.state.render_pass;
-
-			self.draw_calls_count += 1;
-			self.state.break_batching = false;
+			self.new_draw_call(pip, layout);
 		}
 
 		let dc = &mut self.draw_calls[self.draw_calls_count - 1];
 
-		for i in 0..vertices.len() {
-			dc.vertices[dc.vertices_count + i] = vertices[i];
-		}
-
 		for i in 0..indices.len() {
-			dc.indices[dc.indices_count + i] = indices[i] + dc.vertices_count as u16;
+			dc.indices[dc.indices_count + i] = indices[i].into() + dc.vertices_count as u32;
 		}
 
-		dc.vertices_count += vertices.len();
+		dc.push_vertices(vertices);
 		dc.indices_count += indices.len();
 		dc.texture = self.state.texture;
 	}
 
-	/// Deletes the pipeline from the inner pipeline storage. 
+	/// Allocates a new draw call with the current state
+	fn new_draw_call(&mut self, pip: GlPipeline, layout: VertexLayoutId) -> &mut DrawCall {
+		let uniforms = self.state.pipeline.and_then(|pipeline| self.pipelines.get_pipeline(pipeline).ok()).map(|pipeline| pipeline.uniforms_data.clone());
+
+		if self.draw_calls_count >= self.draw_calls.len() {
+			self.draw_calls.push(DrawCall::new(
+				self.state.texture,
+				self.state.model(),
+				self.state.draw_mode,
+				pip,
+				uniforms.clone(),
+				self.state.render_pass,
+				self.max_vertices,
+				self.max_indices,
+			));
+		}
+		let dc = &mut self.draw_calls[self.draw_calls_count];
+		dc.texture = self.state.texture;
+		dc.uniforms = uniforms;
+		dc.vertices_count = 0;
+		dc.indices_count = 0;
+		dc.clip = self.state.clip;
+		dc.viewport = self.state.viewport;
+		dc.model = self.state.model();
+		dc.pipeline = pip;
+		dc.draw_mode = self.state.draw_mode;
+		dc.render_pass = self.state.render_pass;
+		dc.instanced = None;
+		dc.set_vertex_layout(layout, self.max_vertices);
+
+		self.draw_calls_count += 1;
+		self.state.break_batching = false;
+
+		dc
+	}
+
+	/// Uploads the mesh to the GPU, to be drawn with [`RenderingBackend::draw_instanced`].
+	/// Fails if an index is out of range of the vertices.
+	pub fn upload_mesh(&mut self, mesh: &Mesh) -> Result<GpuMesh, GeometryError> {
+		self.upload_geometry(&mesh.vertices, &mesh.indices)
+	}
+
+	/// Uploads vertices of any [`VertexLayout`] to the GPU, to be drawn with [`RenderingBackend::draw_instanced`].
+	/// Fails if an index is out of range of the vertices.
+	pub fn upload_geometry<V: VertexLayout, I: Copy + Into<u32>>(&mut self, vertices: &[V], indices: &[I]) -> Result<GpuMesh, GeometryError> {
+		if let Some(index) = indices.iter().map(|index| (*index).into() as usize).find(|index| *index >= vertices.len()) {
+			return Err(GeometryError::IndexOutOfRange(index));
+		}
+
+		let vertex_buffer = self.backend.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(vertex_bytes(vertices)));
+		let index_buffer = if vertices.len() > u16::MAX as usize + 1 {
+			let indices: Vec<u32> = indices.iter().map(|index| (*index).into()).collect();
+			self.backend.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&indices))
+		} else {
+			let indices: Vec<u16> = indices.iter().map(|index| (*index).into() as u16).collect();
+			self.backend.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&indices))
+		};
+
+		Ok(GpuMesh {
+			vertex_buffer,
+			index_buffer,
+			indices_count: indices.len(),
+			vertex_layout: VertexLayoutId::of::<V>(),
+		})
+	}
+
+	/// Releases the GPU buffers of the mesh
+	pub fn delete_mesh(&mut self, mesh: GpuMesh) {
+		self.backend.delete_buffer(mesh.vertex_buffer);
+		self.backend.delete_buffer(mesh.index_buffer);
+	}
+
+	/// Draws the uploaded mesh once per instance, in a single draw call. The current state (texture, model matrix, clip etc.) applies
+	/// to all instances, the same way it does for [`RenderingBackend::geometry`].
+	///
+	/// Without a pipeline set, the default instanced pipeline is used, which takes [`Vertex`] meshes and [`InstanceData`](instancing::InstanceData) instances.
+	/// Custom pipelines have to be created with [`RenderingBackend::make_instanced_pipeline`] or [`RenderingBackend::request_instanced_material`].
+	pub fn draw_instanced<I: VertexLayout>(&mut self, mesh: &GpuMesh, instances: &[I]) -> Result<(), GeometryError> {
+		let pip = self.state.pipeline.unwrap_or(self.pipelines.get_default_instanced_by(self.state.draw_mode, self.state.depth_test_enable));
+		let pipeline = self.pipelines.get_pipeline(pip)?;
+		if pipeline.vertex_layout != mesh.vertex_layout {
+			return Err(GeometryError::VertexLayoutMismatch {
+				expected: pipeline.vertex_layout.name(),
+				found: mesh.vertex_layout.name(),
+			});
+		}
+		if pipeline.instance_layout != Some(VertexLayoutId::of::<I>()) {
+			return Err(GeometryError::InstanceLayoutMismatch {
+				expected: pipeline.instance_layout.map(|layout| layout.name()),
+				found: Some(std::any::type_name::<I>()),
+			});
+		}
+		if instances.is_empty() {
+			return Ok(());
+		}
+
+		let dc = self.new_draw_call(pip, mesh.vertex_layout);
+		dc.instanced = Some(InstancedDraw {
+			mesh: *mesh,
+			instances: vertex_bytes(instances).to_vec(),
+			instances_count: instances.len(),
+			instance_layout: VertexLayoutId::of::<I>(),
+		});
+		// Nothing can be appended to an instanced draw call
+		self.state.break_batching = true;
+
+		Ok(())
+	}
+
+	/// Deletes the pipeline from the inner pipeline storage, releasing its miniquad pipeline and shader.
 	/// 
-	/// *Attention: using the same pipeline again will panic, or give unexpected results*
-	pub fn delete_pipeline(&mut self, pipeline: GlPipeline) {
-		self.pipelines.delete_pipeline(pipeline);
+	/// *Note: using the same pipeline again will return [`PipelineError::InvalidHandle`]*
+	pub fn delete_pipeline(&mut self, pipeline: GlPipeline) -> Result<(), PipelineError> {
+		self.pipelines.delete_pipeline(&mut *self.backend, pipeline)
 	}
 
 	/// Update the uniform of a loaded pipeline
-	pub fn set_uniform<T>(&mut self, pipeline: GlPipeline, name: &str, uniform: T) {
+	pub fn set_uniform<T>(&mut self, pipeline: GlPipeline, name: &str, uniform: T) -> Result<(), PipelineError> {
+		self.pipelines.get_pipeline_mut(pipeline)?.set_uniform(name, uniform);
 		self.state.break_batching = true;
 
-		self.pipelines.get_pipeline_mut(pipeline).set_uniform(name, uniform);
+		Ok(())
 	}
 
 	/// Prepare material for a draw call. Basically the same as [`RenderingBackend::pipeline`] 
@@ -382,49 +706,82 @@ impl RenderingBackend {
 	}
 
 	/// Update the uniform of an already loaded material. The same as [`RenderingBackend::set_uniform`], but for materials
-	pub fn material_set_uniform<T>(&mut self, material: &Material, name: &str, uniform: T) {
-		self.set_uniform(material.pipeline, name, uniform);
+	pub fn material_set_uniform<T>(&mut self, material: &Material, name: &str, uniform: T) -> Result<(), PipelineError> {
+		self.set_uniform(material.pipeline, name, uniform)
 	}
 
 	/// Update the texture under specific name, in a specific pipeline. Useful in materials
-	pub fn set_texture(&mut self, pipeline: GlPipeline, name: &str, texture: TextureId) {
-		let pipeline = self.pipelines.get_pipeline_mut(pipeline);
+	pub fn set_texture(&mut self, pipeline: GlPipeline, name: &str, texture: TextureId) -> Result<(), PipelineError> {
+		let pipeline = self.pipelines.get_pipeline_mut(pipeline)?;
 		pipeline
 			.textures
 			.iter()
 			.find(|x| *x == name)
 			.unwrap_or_else(|| panic!("can't find texture with name '{}', there is only this names: {:?}", name, pipeline.textures));
 		*pipeline.textures_data.entry(name.to_owned()).or_insert(texture) = texture;
+
+		Ok(())
 	}
 
 	/// Update the texture under specific name, in a specific material. The same as [`RenderingBackend::set_texture`]
-	pub fn material_set_texture(&mut self, material: &Material, name: &str, texture: TextureId) {
-		self.set_texture(material.pipeline, name, texture);
+	pub fn material_set_texture(&mut self, material: &Material, name: &str, texture: TextureId) -> Result<(), PipelineError> {
+		self.set_texture(material.pipeline, name, texture)
 	}
 
 	/// Update the vertex/index limits of draw calls
 	/// 
+	/// Draw calls of more than `65536` vertices use 32-bit index buffers, which require the `OES_element_index_uint` extension on WebGL 1.
+	/// 
 	/// *Note: It will resize all existing draw calls as well*
 	pub fn update_drawcall_capacity(&mut self, max_vertices: usize, max_indices: usize) {
 		self.max_vertices = max_vertices;
 		self.max_indices = max_indices;
 
 		for draw_call in &mut self.draw_calls {
-			draw_call.vertices = vec![Vertex::new(vec3(0.0, 0.0, 0.0), vec2(0.0, 0.0), Rgba::default()); max_vertices];
+			draw_call.vertices = vec![0; max_vertices * draw_call.vertex_layout.size()];
 			draw_call.indices = vec![0; max_indices];
 		}
-		for binding in &mut self.draw_call_bindings {
-			let vertex_buffer = self.backend.new_buffer(BufferType::VertexBuffer, BufferUsage::Stream, BufferSource::empty::<Vertex>(self.max_vertices));
-			let index_buffer = self.backend.new_buffer(BufferType::IndexBuffer, BufferUsage::Stream, BufferSource::empty::<u16>(self.max_indices));
-			*binding = Bindings {
-				vertex_buffers: vec![vertex_buffer],
-				index_buffer,
-				images: vec![self.white_texture],
-			};
+		for i in 0..self.draw_call_bindings.len() {
+			self.draw_call_bindings[i] = self.new_draw_call_bindings();
+		}
+	}
+}
+
+/// Error returned by [`RenderingBackend::geometry`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryError {
+	/// A single primitive needs more vertices or indices than a draw call can hold
+	PrimitiveTooLarge { max_vertices: usize, max_indices: usize },
+	/// The index points past the end of the vertices
+	IndexOutOfRange(usize),
+	/// The current pipeline can't be used
+	Pipeline(PipelineError),
+	/// The vertices are of another [`VertexLayout`] than the one of the current pipeline
+	VertexLayoutMismatch { expected: &'static str, found: &'static str },
+	/// The instances are of another layout than the one of the current pipeline. `None` stands for non-instanced geometry or pipelines
+	InstanceLayoutMismatch { expected: Option<&'static str>, found: Option<&'static str> },
+}
+
+impl From<PipelineError> for GeometryError {
+	fn from(err: PipelineError) -> Self {
+		Self::Pipeline(err)
+	}
+}
+
+impl std::fmt::Display for GeometryError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::PrimitiveTooLarge { max_vertices, max_indices } => write!(f, "A primitive doesn't fit into a draw call of {} vertices and {} indices", max_vertices, max_indices),
+			Self::IndexOutOfRange(index) => write!(f, "Index {} is out of range of the vertices", index),
+			Self::Pipeline(err) => err.fmt(f),
+			Self::VertexLayoutMismatch { expected, found } => write!(f, "The pipeline takes vertices of type {}, got {}", expected, found),
+			Self::InstanceLayoutMismatch { expected, found } => write!(f, "The pipeline takes instances of type {}, got {}", expected.unwrap_or("none"), found.unwrap_or("none")),
 		}
 	}
 }
 
+impl std::error::Error for GeometryError {}
+
 /// Sets the Clear Color of the window
 #[repr(transparent)]
 #[derive(Resource, Default)]
@@ -453,7 +810,11 @@ impl bevy_app::Plugin for RenderBackendPlugin {
 			// Setup the rendering backend
 			app.insert_resource(camera::CurrentCameraTag(id))
 				.init_resource::<ClearColor>()
+				.init_resource::<text::GlyphCache>()
 				.add_systems(state::MiniquadPrepareDraw, apply_clear_color)
+				.add_systems(state::MiniquadDraw, mesh::render_meshes.in_set(mesh::MeshRenderSet))
+				.add_systems(state::MiniquadDraw, sprite::render_sprites.in_set(sprite::SpriteRenderSet).after(mesh::MeshRenderSet))
+				.add_systems(bevy_app::PostUpdate, sprite::sync_atlas_sprites.after(crate::animation::AnimationUpdate))
 				.add_systems(state::MiniquadEndDraw, commit_frame);
 		}
 	}
@@ -464,7 +825,6 @@ fn apply_clear_color(
 	clear_color: Res<ClearColor>,
 	current_camera: Res<camera::CurrentCameraTag>,
 	render_target: Query<&camera::RenderTarget>,
-	// renderables: Query<&Handle<Mesh>>
 ) {
 	let color = clear_color.as_ref().0.to_float();
 	let clear = PassAction::clear_color(color.x, color.y, color.z, color.w);
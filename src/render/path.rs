@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use glam::{vec2, Vec2};
+
+use super::geometry::{Mesh, Vertex};
+use super::rgba::Rgba;
+use super::stroke::{tessellate_stroke, StrokeStyle};
+
+/// Default largest distance between curves and the line segments they're flattened to
+const DEFAULT_TOLERANCE: f32 = 0.1;
+
+/// Most line segments a single curve is flattened to
+const MAX_CURVE_SEGMENTS: f32 = 1024.0;
+
+/// Decides which regions of overlapping or nested contours are [filled](Path::fill)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+	/// Fills regions surrounded by contours going around them more times in one direction than the other.
+	/// Holes have to go around in the opposite direction of the contours around them
+	#[default]
+	NonZero,
+	/// Fills regions inside an odd number of contours, regardless of their direction
+	EvenOdd,
+}
+
+impl FillRule {
+	fn is_inside(&self, winding: i32) -> bool {
+		match self {
+			Self::NonZero => winding != 0,
+			Self::EvenOdd => winding % 2 != 0,
+		}
+	}
+}
+
+/// A run of connected line segments of a [`Path`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Contour {
+	pub points: Vec<Vec2>,
+	/// Whether the last point connects back to the first one. Contours are always closed when [filled](Path::fill)
+	pub closed: bool,
+}
+
+/// Contours made of line segments, with the curves already flattened. Built with a [`PathBuilder`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path {
+	pub contours: Vec<Contour>,
+}
+
+/// Builds [`Path`]s out of lines, Bézier curves and arcs, the way a canvas does. Commands without a current point start a new contour at their first point.
+#[derive(Debug, Clone)]
+pub struct PathBuilder {
+	contours: Vec<Contour>,
+	current: Option<Contour>,
+	tolerance: f32,
+}
+
+impl Default for PathBuilder {
+	fn default() -> Self {
+		Self {
+			contours: Vec::new(),
+			current: None,
+			tolerance: DEFAULT_TOLERANCE,
+		}
+	}
+}
+
+impl PathBuilder {
+	/// Sets the largest distance between curves and the line segments they're flattened to, `0.1` by default.
+	/// Lower tolerances look smoother when zoomed in, at the cost of more triangles
+	pub fn tolerance(&mut self, tolerance: f32) -> &mut Self {
+		debug_assert!(tolerance > 0.0, "The path tolerance has to be positive");
+		self.tolerance = tolerance;
+		self
+	}
+
+	/// The last point of the current contour
+	pub fn current_point(&self) -> Option<Vec2> {
+		self.current.as_ref().and_then(|contour| contour.points.last().copied())
+	}
+
+	/// Starts a new contour at the point
+	pub fn move_to(&mut self, point: Vec2) -> &mut Self {
+		self.finish_contour();
+		self.current = Some(Contour { points: vec![point], closed: false });
+		self
+	}
+
+	pub fn line_to(&mut self, point: Vec2) -> &mut Self {
+		self.push(point);
+		self
+	}
+
+	/// Adds a quadratic Bézier curve from the current point to `to`
+	pub fn quadratic_to(&mut self, control: Vec2, to: Vec2) -> &mut Self {
+		let from = self.start_at(control);
+		// The second derivative bounds the distance between the curve and its chords
+		let segments = self.segments(((from - control * 2.0 + to).length() / 4.0 / self.tolerance).sqrt());
+		for i in 1..=segments {
+			let t = i as f32 / segments as f32;
+			self.push(from.lerp(control, t).lerp(control.lerp(to, t), t));
+		}
+		self
+	}
+
+	/// Adds a cubic Bézier curve from the current point to `to`
+	pub fn cubic_to(&mut self, control1: Vec2, control2: Vec2, to: Vec2) -> &mut Self {
+		let from = self.start_at(control1);
+		let curvature = (from - control1 * 2.0 + control2).length().max((control1 - control2 * 2.0 + to).length());
+		let segments = self.segments((curvature * 3.0 / 4.0 / self.tolerance).sqrt());
+		for i in 1..=segments {
+			let t = i as f32 / segments as f32;
+			let (a, b, c) = (from.lerp(control1, t), control1.lerp(control2, t), control2.lerp(to, t));
+			self.push(a.lerp(b, t).lerp(b.lerp(c, t), t));
+		}
+		self
+	}
+
+	/// Adds a circular arc around `center`, starting at `start_angle` and going counter-clockwise for `sweep` radians (clockwise when negative).
+	/// The current point is connected to the start of the arc with a line
+	pub fn arc(&mut self, center: Vec2, radius: f32, start_angle: f32, sweep: f32) -> &mut Self {
+		self.elliptical_arc(center, Vec2::splat(radius), 0.0, start_angle, sweep)
+	}
+
+	/// Adds an arc of the ellipse around `center`, rotated by `rotation` radians. The angles are measured before the rotation,
+	/// and the current point is connected to the start of the arc with a line
+	pub fn elliptical_arc(&mut self, center: Vec2, radii: Vec2, rotation: f32, start_angle: f32, sweep: f32) -> &mut Self {
+		let rotation = Vec2::from_angle(rotation);
+		let point = |angle: f32| center + rotation.rotate(Vec2::from_angle(angle) * radii);
+
+		// Chords of an arc of `step` radians are `radius * (1 - cos(step / 2))` away from it
+		let radius = radii.x.abs().max(radii.y.abs());
+		let step = match radius > self.tolerance {
+			true => 2.0 * (1.0 - self.tolerance / radius).acos(),
+			false => TAU,
+		};
+		let segments = self.segments(sweep.abs() / step);
+
+		self.push(point(start_angle));
+		for i in 1..=segments {
+			self.push(point(start_angle + sweep * i as f32 / segments as f32));
+		}
+		self
+	}
+
+	/// Connects the current contour back to its first point. The next command starts a new contour at that point
+	pub fn close(&mut self) -> &mut Self {
+		if let Some(mut contour) = self.current.take() {
+			let start = contour.points[0];
+			if contour.points.len() > 1 && contour.points.last() == Some(&start) {
+				contour.points.pop();
+			}
+			if contour.points.len() > 1 {
+				contour.closed = true;
+				self.contours.push(contour);
+			}
+			self.current = Some(Contour { points: vec![start], closed: false });
+		}
+		self
+	}
+
+	/// Adds a closed polygon through the points
+	pub fn polygon(&mut self, points: &[Vec2]) -> &mut Self {
+		if let Some((first, rest)) = points.split_first() {
+			self.move_to(*first);
+			for point in rest {
+				self.push(*point);
+			}
+			self.close();
+		}
+		self
+	}
+
+	/// Returns the built path, leaving the builder empty
+	pub fn build(&mut self) -> Path {
+		self.finish_contour();
+		Path { contours: std::mem::take(&mut self.contours) }
+	}
+
+	/// Adds the current contour to the path, unless it's just a starting point
+	fn finish_contour(&mut self) {
+		if let Some(contour) = self.current.take() {
+			if contour.points.len() > 1 {
+				self.contours.push(contour);
+			}
+		}
+	}
+
+	fn push(&mut self, point: Vec2) {
+		match &mut self.current {
+			Some(contour) => {
+				if contour.points.last() != Some(&point) {
+					contour.points.push(point);
+				}
+			}
+			None => self.current = Some(Contour { points: vec![point], closed: false }),
+		}
+	}
+
+	/// Returns the current point, starting a contour at `point` without one
+	fn start_at(&mut self, point: Vec2) -> Vec2 {
+		match self.current_point() {
+			Some(current) => current,
+			None => {
+				self.move_to(point);
+				point
+			}
+		}
+	}
+
+	fn segments(&self, count: f32) -> u32 {
+		match count.is_finite() {
+			true => count.ceil().clamp(1.0, MAX_CURVE_SEGMENTS) as u32,
+			false => 1,
+		}
+	}
+}
+
+/// An edge of a filled path, going up from `low` to `high`
+#[derive(Clone, Copy)]
+struct Edge {
+	low: Vec2,
+	high: Vec2,
+	/// `1` for edges going up in the path, `-1` for edges going down
+	winding: i32,
+}
+
+impl Edge {
+	fn x_at(&self, y: f32) -> f32 {
+		self.low.x + (y - self.low.y) * (self.high.x - self.low.x) / (self.high.y - self.low.y)
+	}
+
+	/// Height where the edges cross, if they do between their ends
+	fn crossing(&self, other: &Edge) -> Option<f32> {
+		let (d1, d2) = (self.high - self.low, other.high - other.low);
+		let denominator = d1.perp_dot(d2);
+		if denominator == 0.0 {
+			return None;
+		}
+		let offset = other.low - self.low;
+		let (t, u) = (offset.perp_dot(d2) / denominator, offset.perp_dot(d1) / denominator);
+		match t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0 {
+			true => Some(self.low.y + d1.y * t),
+			false => None,
+		}
+	}
+}
+
+/// Triangles being built from a fill, sharing the vertices between neighbouring trapezoids
+#[derive(Default)]
+struct Triangles {
+	positions: Vec<Vec2>,
+	indices: Vec<u32>,
+	lookup: HashMap<(u32, u32), u32>,
+}
+
+impl Triangles {
+	fn index(&mut self, point: Vec2) -> u32 {
+		let positions = &mut self.positions;
+		*self.lookup.entry((point.x.to_bits(), point.y.to_bits())).or_insert_with(|| {
+			positions.push(point);
+			positions.len() as u32 - 1
+		})
+	}
+
+	fn trapezoid(&mut self, top_left: Vec2, top_right: Vec2, bottom_left: Vec2, bottom_right: Vec2) {
+		if top_right.x > top_left.x {
+			let triangle = [top_left, top_right, bottom_left].map(|point| self.index(point));
+			self.indices.extend(triangle);
+		}
+		if bottom_right.x > bottom_left.x {
+			let triangle = [top_right, bottom_left, bottom_right].map(|point| self.index(point));
+			self.indices.extend(triangle);
+		}
+	}
+}
+
+impl Path {
+	/// Tessellates the inside of the path into triangles, closing every contour. Contours may be concave, cross themselves or each other, and cut holes
+	/// into each other according to the fill rule.
+	///
+	/// The path is cut into horizontal slabs at every point and crossing, which are filled with trapezoids. Finding the crossings compares
+	/// every pair of edges, so it's meant for paths of up to a few thousand points
+	pub fn tessellate_fill(&self, rule: FillRule) -> (Vec<Vec2>, Vec<u32>) {
+		let mut edges = Vec::new();
+		for contour in self.contours.iter().filter(|contour| contour.points.len() > 2) {
+			let points = &contour.points;
+			for (i, start) in points.iter().copied().enumerate() {
+				let end = points[(i + 1) % points.len()];
+				if start.y < end.y {
+					edges.push(Edge { low: start, high: end, winding: 1 });
+				} else if start.y > end.y {
+					edges.push(Edge { low: end, high: start, winding: -1 });
+				}
+			}
+		}
+
+		let mut heights: Vec<f32> = edges.iter().flat_map(|edge| [edge.low.y, edge.high.y]).collect();
+		for (i, edge) in edges.iter().enumerate() {
+			heights.extend(edges[i + 1..].iter().filter_map(|other| edge.crossing(other)));
+		}
+		heights.sort_by(f32::total_cmp);
+		heights.dedup();
+
+		let mut triangles = Triangles::default();
+		let mut slab = Vec::new();
+		for window in heights.windows(2) {
+			let (bottom, top) = (window[0], window[1]);
+			let middle = (bottom + top) / 2.0;
+
+			// Edges don't cross inside of a slab, so their order in the middle holds for the whole slab
+			slab.clear();
+			slab.extend(edges.iter().filter(|edge| edge.low.y <= bottom && edge.high.y >= top).map(|edge| (edge.x_at(middle), edge)));
+			slab.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+			let mut winding = 0;
+			let mut left = None;
+			for (_, edge) in slab.iter() {
+				let was_inside = rule.is_inside(winding);
+				winding += edge.winding;
+				match (was_inside, rule.is_inside(winding), left) {
+					(false, true, _) => left = Some(*edge),
+					(true, false, Some(left_edge)) => {
+						let (left_x, right_x) = ((left_edge.x_at(top), left_edge.x_at(bottom)), (edge.x_at(top), edge.x_at(bottom)));
+						triangles.trapezoid(vec2(left_x.0, top), vec2(right_x.0, top), vec2(left_x.1, bottom), vec2(right_x.1, bottom));
+						left = None;
+					}
+					_ => {}
+				}
+			}
+		}
+
+		(triangles.positions, triangles.indices)
+	}
+
+	/// Fills the path with the color. The texture coordinates span the bounds of the path, from `(0, 0)` at the top left to `(1, 1)` at the bottom right
+	pub fn fill(&self, rule: FillRule, color: Rgba) -> Mesh {
+		let (positions, indices) = self.tessellate_fill(rule);
+		let (min, max) = positions.iter().fold((Vec2::MAX, Vec2::MIN), |(min, max), point| (min.min(*point), max.max(*point)));
+		let size = (max - min).max(Vec2::splat(f32::EPSILON));
+
+		let vertices = positions
+			.into_iter()
+			.map(|point| Vertex::new(point.extend(0.0), vec2(point.x - min.x, max.y - point.y) / size, color))
+			.collect();
+		Mesh { vertices, indices }
+	}
+
+	/// Strokes every contour of the path with the style, see [`tessellate_stroke`]
+	pub fn stroke(&self, style: &StrokeStyle, color: Rgba) -> Mesh {
+		let mut mesh = Mesh { vertices: Vec::new(), indices: Vec::new() };
+		for contour in self.contours.iter() {
+			let (positions, indices) = tessellate_stroke(&contour.points, contour.closed, style);
+			let start = mesh.vertices.len() as u32;
+			mesh.vertices.extend(positions.into_iter().map(|point| Vertex::new(point.extend(0.0), Vec2::ZERO, color)));
+			mesh.indices.extend(indices.into_iter().map(|index| start + index));
+		}
+		mesh
+	}
+}
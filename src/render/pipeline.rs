@@ -1,11 +1,68 @@
-use super::{geometry::Vertex, rgba::Rgba};
+use super::geometry::{vertex_bytes, Vertex, VertexLayout};
+use super::instancing::{GpuMesh, InstanceData};
 use bevy_reflect::Reflect;
-use glam::{vec2, vec3};
 use miniquad::*;
-use std::collections::BTreeMap;
+use std::{any::TypeId, collections::BTreeMap};
 
+/// Handle to a pipeline in the [`PipelineStorage`]. Handles of deleted pipelines are never reused, using them returns [`PipelineError::InvalidHandle`]
 #[derive(Reflect, Debug, Clone, Copy, PartialEq)]
-pub struct GlPipeline(usize);
+pub struct GlPipeline {
+	index: usize,
+	generation: u32,
+}
+
+impl GlPipeline {
+	const fn new(index: usize) -> Self {
+		Self { index, generation: 0 }
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipelineError {
+	/// The pipeline was deleted
+	InvalidHandle(GlPipeline),
+	/// Default pipelines are used internally and can't be deleted
+	DefaultPipeline(GlPipeline),
+}
+
+impl std::fmt::Display for PipelineError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::InvalidHandle(pipeline) => write!(f, "Pipeline {:?} doesn't exist anymore", pipeline),
+			Self::DefaultPipeline(pipeline) => write!(f, "Pipeline {:?} is a default pipeline and can't be deleted", pipeline),
+		}
+	}
+}
+
+impl std::error::Error for PipelineError {}
+
+/// Identifies the [`VertexLayout`] used by a pipeline or a draw call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexLayoutId {
+	type_id: TypeId,
+	name: &'static str,
+	size: usize,
+}
+
+impl VertexLayoutId {
+	pub fn of<V: VertexLayout>() -> Self {
+		Self {
+			type_id: TypeId::of::<V>(),
+			name: std::any::type_name::<V>(),
+			size: std::mem::size_of::<V>(),
+		}
+	}
+
+	/// Type name of the vertex
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	/// Size of a single vertex in bytes
+	pub fn size(&self) -> usize {
+		self.size
+	}
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DrawMode {
@@ -21,8 +78,10 @@ pub struct Uniform {
 }
 
 pub struct DrawCall {
-	pub vertices: Vec<Vertex>,
-	pub indices: Vec<u16>,
+	/// Raw bytes of the vertices, in the draw call's [`VertexLayout`]
+	pub vertices: Vec<u8>,
+	pub indices: Vec<u32>,
+	pub vertex_layout: VertexLayoutId,
 
 	pub vertices_count: usize,
 	pub indices_count: usize,
@@ -38,6 +97,17 @@ pub struct DrawCall {
 	pub uniforms: Option<Vec<u8>>,
 	pub render_pass: Option<RenderPass>,
 	pub capture: bool,
+	/// Set for draw calls of [`RenderingBackend::draw_instanced`](super::RenderingBackend::draw_instanced), which draw an uploaded mesh instead of the vertices and indices
+	pub instanced: Option<InstancedDraw>,
+}
+
+/// Instances of an uploaded mesh, drawn by a single draw call
+pub struct InstancedDraw {
+	pub mesh: GpuMesh,
+	/// Raw bytes of the per-instance data
+	pub instances: Vec<u8>,
+	pub instances_count: usize,
+	pub instance_layout: VertexLayoutId,
 }
 
 impl DrawCall {
@@ -52,8 +122,9 @@ impl DrawCall {
 		max_indices: usize,
 	) -> DrawCall {
 		DrawCall {
-			vertices: vec![Vertex::new(vec3(0.0, 0.0, 0.0), vec2(0.0, 0.0), Rgba::default()); max_vertices],
+			vertices: vec![0; max_vertices * std::mem::size_of::<Vertex>()],
 			indices: vec![0; max_indices],
+			vertex_layout: VertexLayoutId::of::<Vertex>(),
 			vertices_count: 0,
 			indices_count: 0,
 			viewport: None,
@@ -65,14 +136,43 @@ impl DrawCall {
 			uniforms,
 			render_pass,
 			capture: false,
+			instanced: None,
 		}
 	}
 
-	pub fn vertices(&self) -> &[Vertex] {
-		&self.vertices[0..self.vertices_count]
+	/// Vertices of the draw call. Returns `None` if they're of another [`VertexLayout`]
+	pub fn vertices<V: VertexLayout>(&self) -> Option<Vec<V>> {
+		if self.vertex_layout != VertexLayoutId::of::<V>() {
+			return None;
+		}
+
+		// SAFETY: the bytes were written from vertices of the same type
+		let vertices = self.vertex_bytes().chunks_exact(self.vertex_layout.size()).map(|bytes| unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const V) }).collect();
+		Some(vertices)
 	}
 
-	pub fn indices(&self) -> &[u16] {
+	/// Raw bytes of the vertices, as they're uploaded to the vertex buffer
+	pub fn vertex_bytes(&self) -> &[u8] {
+		&self.vertices[0..self.vertices_count * self.vertex_layout.size()]
+	}
+
+	/// Switches the draw call to another vertex layout, resizing the vertex storage for `max_vertices` of it
+	pub(crate) fn set_vertex_layout(&mut self, layout: VertexLayoutId, max_vertices: usize) {
+		self.vertex_layout = layout;
+		self.vertices.resize(max_vertices * layout.size(), 0);
+	}
+
+	/// Appends vertices to the draw call. They must fit into the draw call and be of its layout
+	pub(crate) fn push_vertices<V: VertexLayout>(&mut self, vertices: &[V]) {
+		debug_assert_eq!(self.vertex_layout, VertexLayoutId::of::<V>());
+
+		let start = self.vertices_count * self.vertex_layout.size();
+		let bytes = vertex_bytes(vertices);
+		self.vertices[start..start + bytes.len()].copy_from_slice(bytes);
+		self.vertices_count += vertices.len();
+	}
+
+	pub fn indices(&self) -> &[u32] {
 		&self.indices[0..self.indices_count]
 	}
 }
@@ -116,6 +216,10 @@ impl GlState {
 #[derive(Clone)]
 pub struct PipelineExt {
 	pub pipeline: miniquad::Pipeline,
+	pub shader: ShaderId,
+	pub vertex_layout: VertexLayoutId,
+	/// Layout of the per-instance data, for pipelines drawing instanced meshes
+	pub instance_layout: Option<VertexLayoutId>,
 	pub uniforms: Vec<Uniform>,
 	pub uniforms_data: Vec<u8>,
 	pub textures: Vec<String>,
@@ -161,25 +265,41 @@ impl PipelineExt {
 	}
 }
 
-#[repr(transparent)]
+struct PipelineSlot {
+	generation: u32,
+	pipeline: Option<PipelineExt>,
+}
+
+/// Growable storage of all pipelines, with slots of deleted pipelines being reused
 pub struct PipelineStorage {
-	pub pipelines: [Option<PipelineExt>; Self::MAX_PIPELINES],
+	pipelines: Vec<PipelineSlot>,
+	free_slots: Vec<usize>,
 }
 
 impl PipelineStorage {
-	const MAX_PIPELINES: usize = 32;
-	const TRIANGLES_PIPELINE: GlPipeline = GlPipeline(0);
-	const LINES_PIPELINE: GlPipeline = GlPipeline(1);
-	const TRIANGLES_DEPTH_PIPELINE: GlPipeline = GlPipeline(2);
-	const LINES_DEPTH_PIPELINE: GlPipeline = GlPipeline(3);
+	const TRIANGLES_PIPELINE: GlPipeline = GlPipeline::new(0);
+	const LINES_PIPELINE: GlPipeline = GlPipeline::new(1);
+	const TRIANGLES_DEPTH_PIPELINE: GlPipeline = GlPipeline::new(2);
+	const LINES_DEPTH_PIPELINE: GlPipeline = GlPipeline::new(3);
+	const INSTANCED_TRIANGLES_PIPELINE: GlPipeline = GlPipeline::new(4);
+	const INSTANCED_LINES_PIPELINE: GlPipeline = GlPipeline::new(5);
+	const INSTANCED_TRIANGLES_DEPTH_PIPELINE: GlPipeline = GlPipeline::new(6);
+	const INSTANCED_LINES_DEPTH_PIPELINE: GlPipeline = GlPipeline::new(7);
+	const DEFAULT_PIPELINES: usize = 8;
 
 	pub(crate) fn new(ctx: &mut dyn RenderingBackend) -> PipelineStorage {
-		let source = match ctx.info().backend {
-			Backend::OpenGl => ShaderSource::Glsl {
-				vertex: shader::VERTEX,
-				fragment: shader::FRAGMENT,
-			},
-			Backend::Metal => ShaderSource::Msl { program: shader::METAL },
+		let (source, instanced_source) = match ctx.info().backend {
+			Backend::OpenGl => (
+				ShaderSource::Glsl {
+					vertex: shader::VERTEX,
+					fragment: shader::FRAGMENT,
+				},
+				ShaderSource::Glsl {
+					vertex: shader::INSTANCED_VERTEX,
+					fragment: shader::FRAGMENT,
+				},
+			),
+			Backend::Metal => (ShaderSource::Msl { program: shader::METAL }, ShaderSource::Msl { program: shader::INSTANCED_METAL }),
 		};
 
 		let shader = ctx.new_shader(source, shader::meta()).unwrap();
@@ -192,9 +312,12 @@ impl PipelineStorage {
 			..Default::default()
 		};
 
-		let mut storage = PipelineStorage { pipelines: Default::default() };
+		let mut storage = PipelineStorage {
+			pipelines: Vec::new(),
+			free_slots: Vec::new(),
+		};
 
-		let triangles_pipeline = storage.make_pipeline(
+		let triangles_pipeline = storage.make_pipeline::<Vertex>(
 			ctx,
 			shader,
 			PipelineParams {
@@ -206,7 +329,7 @@ impl PipelineStorage {
 		);
 		assert_eq!(triangles_pipeline, Self::TRIANGLES_PIPELINE);
 
-		let lines_pipeline = storage.make_pipeline(
+		let lines_pipeline = storage.make_pipeline::<Vertex>(
 			ctx,
 			shader,
 			PipelineParams {
@@ -218,7 +341,7 @@ impl PipelineStorage {
 		);
 		assert_eq!(lines_pipeline, Self::LINES_PIPELINE);
 
-		let triangles_depth_pipeline = storage.make_pipeline(
+		let triangles_depth_pipeline = storage.make_pipeline::<Vertex>(
 			ctx,
 			shader,
 			PipelineParams {
@@ -232,7 +355,7 @@ impl PipelineStorage {
 		);
 		assert_eq!(triangles_depth_pipeline, Self::TRIANGLES_DEPTH_PIPELINE);
 
-		let lines_depth_pipeline = storage.make_pipeline(
+		let lines_depth_pipeline = storage.make_pipeline::<Vertex>(
 			ctx,
 			shader,
 			PipelineParams {
@@ -246,14 +369,72 @@ impl PipelineStorage {
 		);
 		assert_eq!(lines_depth_pipeline, Self::LINES_DEPTH_PIPELINE);
 
+		// Instanced pipelines, in the same order as the ones above
+		let instanced_shader = ctx.new_shader(instanced_source, shader::meta()).unwrap();
+		let instanced_pipelines: Vec<_> = [(PrimitiveType::Triangles, false), (PrimitiveType::Lines, false), (PrimitiveType::Triangles, true), (PrimitiveType::Lines, true)]
+			.into_iter()
+			.map(|(primitive_type, depth)| {
+				let params = match depth {
+					true => PipelineParams {
+						depth_write: true,
+						depth_test: Comparison::LessOrEqual,
+						primitive_type,
+						..params
+					},
+					false => PipelineParams { primitive_type, ..params },
+				};
+				storage.make_instanced_pipeline::<Vertex, InstanceData>(ctx, instanced_shader, params, vec![], vec![])
+			})
+			.collect();
+		assert_eq!(
+			instanced_pipelines,
+			[Self::INSTANCED_TRIANGLES_PIPELINE, Self::INSTANCED_LINES_PIPELINE, Self::INSTANCED_TRIANGLES_DEPTH_PIPELINE, Self::INSTANCED_LINES_DEPTH_PIPELINE]
+		);
+
 		storage
 	}
 
-	pub fn make_pipeline(&mut self, ctx: &mut dyn RenderingBackend, shader: ShaderId, params: PipelineParams, mut uniforms: Vec<(String, UniformType)>, textures: Vec<String>) -> GlPipeline {
-		// TODO: Is it possible to create custom pipelines, with custom vertex attributes? Or is it batch-bound?
-		let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &Vertex::attributes(), shader, params);
+	pub fn make_pipeline<V: VertexLayout>(&mut self, ctx: &mut dyn RenderingBackend, shader: ShaderId, params: PipelineParams, uniforms: Vec<(String, UniformType)>, textures: Vec<String>) -> GlPipeline {
+		let pipeline = ctx.new_pipeline(&[BufferLayout::default()], V::ATTRIBUTES, shader, params);
+
+		self.insert_pipeline(pipeline, shader, VertexLayoutId::of::<V>(), None, uniforms, textures)
+	}
+
+	/// Makes a pipeline taking per-vertex data of `V` from the first vertex buffer, and per-instance data of `I` from the second one
+	pub fn make_instanced_pipeline<V: VertexLayout, I: VertexLayout>(
+		&mut self,
+		ctx: &mut dyn RenderingBackend,
+		shader: ShaderId,
+		params: PipelineParams,
+		uniforms: Vec<(String, UniformType)>,
+		textures: Vec<String>,
+	) -> GlPipeline {
+		let buffer_layouts = [
+			BufferLayout::default(),
+			BufferLayout {
+				step_func: VertexStep::PerInstance,
+				..Default::default()
+			},
+		];
+		let attributes: Vec<_> = V::ATTRIBUTES
+			.iter()
+			.map(|attribute| VertexAttribute { buffer_index: 0, ..attribute.clone() })
+			.chain(I::ATTRIBUTES.iter().map(|attribute| VertexAttribute { buffer_index: 1, ..attribute.clone() }))
+			.collect();
+		let pipeline = ctx.new_pipeline(&buffer_layouts, &attributes, shader, params);
+
+		self.insert_pipeline(pipeline, shader, VertexLayoutId::of::<V>(), Some(VertexLayoutId::of::<I>()), uniforms, textures)
+	}
 
-		let id = self.pipelines.iter().position(|p| p.is_none()).expect("Pipelines amount exceeded");
+	fn insert_pipeline(
+		&mut self,
+		pipeline: Pipeline,
+		shader: ShaderId,
+		vertex_layout: VertexLayoutId,
+		instance_layout: Option<VertexLayoutId>,
+		mut uniforms: Vec<(String, UniformType)>,
+		textures: Vec<String>,
+	) -> GlPipeline {
 		let mut max_offset = 0;
 
 		for (name, kind) in shader::uniforms().into_iter().rev() {
@@ -276,15 +457,28 @@ impl PipelineStorage {
 			})
 			.collect();
 
-		self.pipelines[id] = Some(PipelineExt {
+		let pipeline = Some(PipelineExt {
 			pipeline,
+			shader,
+			vertex_layout,
+			instance_layout,
 			uniforms,
 			uniforms_data: vec![0; max_offset],
 			textures,
 			textures_data: BTreeMap::new(),
 		});
 
-		GlPipeline(id)
+		match self.free_slots.pop() {
+			Some(index) => {
+				let slot = &mut self.pipelines[index];
+				slot.pipeline = pipeline;
+				GlPipeline { index, generation: slot.generation }
+			}
+			None => {
+				self.pipelines.push(PipelineSlot { generation: 0, pipeline });
+				GlPipeline::new(self.pipelines.len() - 1)
+			}
+		}
 	}
 
 	pub fn get_default_by(&self, draw_mode: DrawMode, depth_enabled: bool) -> GlPipeline {
@@ -296,12 +490,50 @@ impl PipelineStorage {
 		}
 	}
 
-	pub fn get_pipeline_mut(&mut self, pip: GlPipeline) -> &mut PipelineExt {
-		self.pipelines[pip.0].as_mut().unwrap()
+	/// Default pipeline drawing [`InstanceData`] instances of [`Vertex`] meshes
+	pub fn get_default_instanced_by(&self, draw_mode: DrawMode, depth_enabled: bool) -> GlPipeline {
+		match (draw_mode, depth_enabled) {
+			(DrawMode::Triangles, false) => Self::INSTANCED_TRIANGLES_PIPELINE,
+			(DrawMode::Triangles, true) => Self::INSTANCED_TRIANGLES_DEPTH_PIPELINE,
+			(DrawMode::Lines, false) => Self::INSTANCED_LINES_PIPELINE,
+			(DrawMode::Lines, true) => Self::INSTANCED_LINES_DEPTH_PIPELINE,
+		}
 	}
 
-	pub fn delete_pipeline(&mut self, pip: GlPipeline) {
-		self.pipelines[pip.0] = None;
+	pub fn get_pipeline(&self, pip: GlPipeline) -> Result<&PipelineExt, PipelineError> {
+		self.pipelines
+			.get(pip.index)
+			.filter(|slot| slot.generation == pip.generation)
+			.and_then(|slot| slot.pipeline.as_ref())
+			.ok_or(PipelineError::InvalidHandle(pip))
+	}
+
+	pub fn get_pipeline_mut(&mut self, pip: GlPipeline) -> Result<&mut PipelineExt, PipelineError> {
+		self.pipelines
+			.get_mut(pip.index)
+			.filter(|slot| slot.generation == pip.generation)
+			.and_then(|slot| slot.pipeline.as_mut())
+			.ok_or(PipelineError::InvalidHandle(pip))
+	}
+
+	/// Deletes the pipeline, releasing the miniquad pipeline and its shader (unless it's used by other pipelines)
+	pub fn delete_pipeline(&mut self, ctx: &mut dyn RenderingBackend, pip: GlPipeline) -> Result<(), PipelineError> {
+		if pip.index < Self::DEFAULT_PIPELINES {
+			return Err(PipelineError::DefaultPipeline(pip));
+		}
+		self.get_pipeline(pip)?;
+
+		let slot = &mut self.pipelines[pip.index];
+		let pipeline = slot.pipeline.take().unwrap();
+		slot.generation = slot.generation.wrapping_add(1);
+		self.free_slots.push(pip.index);
+
+		ctx.delete_pipeline(pipeline.pipeline);
+		if !self.pipelines.iter().filter_map(|slot| slot.pipeline.as_ref()).any(|other| other.shader == pipeline.shader) {
+			ctx.delete_shader(pipeline.shader);
+		}
+
+		Ok(())
 	}
 }
 
@@ -326,6 +558,27 @@ pub(crate) mod shader {
 		uv = texcoord;
 	}"#;
 
+	pub const INSTANCED_VERTEX: &str = r#"#version 100
+	attribute vec3 position;
+	attribute vec2 texcoord;
+	attribute vec4 color0;
+
+	attribute mat4 inst_transform;
+	attribute vec4 inst_uv_rect;
+	attribute vec4 inst_color;
+
+	varying lowp vec2 uv;
+	varying lowp vec4 color;
+
+	uniform mat4 Model;
+	uniform mat4 Projection;
+
+	void main() {
+		gl_Position = Projection * Model * inst_transform * vec4(position, 1);
+		color = color0 / 255.0 * inst_color / 255.0;
+		uv = inst_uv_rect.xy + texcoord * inst_uv_rect.zw;
+	}"#;
+
 	pub const FRAGMENT: &str = r#"#version 100
 	varying lowp vec4 color;
 	varying lowp vec2 uv;
@@ -376,6 +629,54 @@ pub(crate) mod shader {
 	}
 	"#;
 
+	pub const INSTANCED_METAL: &str = r#"#include <metal_stdlib>
+	using namespace metal;
+
+	struct Uniforms
+	{
+		float4x4 Projection;
+		float4x4 Model;
+		float4 _Time;
+	};
+
+	struct Vertex
+	{
+		float3 position    [[attribute(0)]];
+		float2 texcoord    [[attribute(1)]];
+		float4 color0      [[attribute(2)]];
+		float4 transform0  [[attribute(3)]];
+		float4 transform1  [[attribute(4)]];
+		float4 transform2  [[attribute(5)]];
+		float4 transform3  [[attribute(6)]];
+		float4 uv_rect     [[attribute(7)]];
+		float4 inst_color  [[attribute(8)]];
+	};
+
+	struct RasterizerData
+	{
+		float4 position [[position]];
+		float4 color [[user(locn0)]];
+		float2 uv [[user(locn1)]];
+	};
+
+	vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+	{
+		RasterizerData out;
+
+		float4x4 transform = float4x4(v.transform0, v.transform1, v.transform2, v.transform3);
+		out.position = uniforms.Projection * uniforms.Model * transform * float4(v.position, 1);
+		out.color = v.color0 / 255.0 * v.inst_color / 255.0;
+		out.uv = v.uv_rect.xy + v.texcoord * v.uv_rect.zw;
+
+		return out;
+	}
+
+	fragment float4 fragmentShader(RasterizerData in [[stage_in]], texture2d<float> tex [[texture(0)]], sampler texSmplr [[sampler(0)]])
+	{
+		return in.color * tex.sample(texSmplr, in.uv);
+	}
+	"#;
+
 	pub fn uniforms() -> Vec<(&'static str, UniformType)> {
 		vec![("Projection", UniformType::Mat4), ("Model", UniformType::Mat4), ("_Time", UniformType::Float4)]
 	}
@@ -0,0 +1,309 @@
+use std::{cell::RefCell, rc::Rc};
+
+use miniquad::*;
+
+/// A single call received by the [`RecordingBackend`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedCall {
+	NewBuffer {
+		buffer: BufferId,
+		buffer_type: BufferType,
+		usage: BufferUsage,
+		/// Size in bytes. Only known for buffers created from [`BufferSource::empty`]
+		size: Option<usize>,
+	},
+	BufferUpdate {
+		buffer: BufferId,
+	},
+	ApplyPipeline(Pipeline),
+	DeletePipeline(Pipeline),
+	DeleteShader(ShaderId),
+	/// A region of a texture was updated, or the whole of it with [`texture_update`](miniquad::RenderingBackend::texture_update)
+	TextureUpdate {
+		texture: TextureId,
+		x: i32,
+		y: i32,
+		width: i32,
+		height: i32,
+	},
+	ApplyViewport {
+		x: i32,
+		y: i32,
+		w: i32,
+		h: i32,
+	},
+	ApplyScissorRect {
+		x: i32,
+		y: i32,
+		w: i32,
+		h: i32,
+	},
+	ApplyBindings {
+		vertex_buffers: Vec<BufferId>,
+		index_buffer: BufferId,
+		images: Vec<TextureId>,
+	},
+	/// `None` is the default (window) pass
+	BeginPass(Option<RenderPass>),
+	EndPass,
+	CommitFrame,
+	Draw {
+		base_element: i32,
+		num_elements: i32,
+		num_instances: i32,
+	},
+}
+
+/// Shared handle to the calls logged by a [`RecordingBackend`]. It stays valid after the backend
+/// was moved into a [`RenderingBackend`](super::RenderingBackend).
+#[derive(Clone, Default)]
+pub struct RecordedCalls(Rc<RefCell<Vec<RecordedCall>>>);
+
+impl RecordedCalls {
+	/// Returns a copy of all recorded calls
+	pub fn get(&self) -> Vec<RecordedCall> {
+		self.0.borrow().clone()
+	}
+
+	/// Returns all recorded calls, clearing the log
+	pub fn take(&self) -> Vec<RecordedCall> {
+		std::mem::take(&mut *self.0.borrow_mut())
+	}
+
+	pub fn clear(&self) {
+		self.0.borrow_mut().clear();
+	}
+
+	fn push(&self, call: RecordedCall) {
+		self.0.borrow_mut().push(call);
+	}
+}
+
+/// A stand-in miniquad rendering backend that doesn't need a GL context. It creates fake resource handles and logs
+/// every buffer, pipeline, pass and draw call, so the batching done by [`RenderingBackend`](super::RenderingBackend) can be
+/// inspected on machines without a GPU.
+///
+/// Only available with the `testing` feature. Use [`RecordingBackend::calls`] before boxing it into [`RenderingBackend::from_backend`](super::RenderingBackend::from_backend) to keep access to the log.
+#[derive(Default)]
+pub struct RecordingBackend {
+	calls: RecordedCalls,
+
+	shaders: usize,
+	pipelines: usize,
+	buffers: Vec<(BufferId, usize)>,
+	textures: Vec<(TextureId, TextureParams)>,
+	render_passes: Vec<(RenderPass, Vec<TextureId>)>,
+}
+
+/// A handle minted by the [`RecordingBackend`]. Textures go through [`TextureId::from_raw_id`], but miniquad has no public
+/// constructors for its other handles, so each of them is made up from the backend's own `usize` ids here. That relies on their
+/// private layout (only their size and alignment are checked, at compile time), which is why the recorder is limited to the `testing` feature.
+trait RecordedHandle: Copy {
+	fn from_id(id: usize) -> Self;
+}
+
+macro_rules! recorded_handle {
+	($($ty:ident),*) => {
+		$(
+			const _: () = assert!(
+				std::mem::size_of::<$ty>() == std::mem::size_of::<usize>() && std::mem::align_of::<$ty>() == std::mem::align_of::<usize>()
+			);
+
+			impl RecordedHandle for $ty {
+				fn from_id(id: usize) -> Self {
+					// SAFETY: the handles are `usize` newtypes in miniquad, of the same size and alignment (asserted above). Ids are
+					// never zero, so a `NonZeroUsize` inner field would stay valid too
+					unsafe { std::mem::transmute::<usize, $ty>(id) }
+				}
+			}
+		)*
+	};
+}
+
+recorded_handle!(BufferId, ShaderId, Pipeline, RenderPass);
+
+/// Map the backend's 0-based resource index to a miniquad handle
+fn handle<T: RecordedHandle>(index: usize) -> T {
+	T::from_id(index + 1)
+}
+
+impl RecordingBackend {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Get a handle to the call log of this backend
+	pub fn calls(&self) -> RecordedCalls {
+		self.calls.clone()
+	}
+
+	fn texture_index(&self, texture: TextureId) -> usize {
+		self.textures.iter().position(|(id, _)| *id == texture).expect("Unknown texture")
+	}
+}
+
+impl RenderingBackend for RecordingBackend {
+	fn info(&self) -> ContextInfo {
+		ContextInfo {
+			backend: Backend::OpenGl,
+			gl_version_string: "Recording".to_owned(),
+			glsl_support: GlslSupport {
+				v130: false,
+				v150: false,
+				v330: false,
+				v300es: false,
+				v100_ext: false,
+				v100: true,
+			},
+			features: Features {
+				instancing: true,
+				resolve_attachments: false,
+			},
+		}
+	}
+
+	fn new_shader(&mut self, _shader: ShaderSource, _meta: ShaderMeta) -> Result<ShaderId, ShaderError> {
+		self.shaders += 1;
+		Ok(handle(self.shaders - 1))
+	}
+
+	fn new_texture(&mut self, _access: TextureAccess, _data: TextureSource, params: TextureParams) -> TextureId {
+		let texture = TextureId::from_raw_id(RawId::OpenGl(self.textures.len() as _));
+		self.textures.push((texture, params));
+		texture
+	}
+
+	fn texture_params(&self, texture: TextureId) -> TextureParams {
+		self.textures[self.texture_index(texture)].1
+	}
+
+	unsafe fn texture_raw_id(&self, texture: TextureId) -> RawId {
+		RawId::OpenGl(self.texture_index(texture) as _)
+	}
+
+	fn texture_set_min_filter(&mut self, _texture: TextureId, _filter: FilterMode, _mipmap_filter: MipmapFilterMode) {}
+
+	fn texture_set_mag_filter(&mut self, _texture: TextureId, _filter: FilterMode) {}
+
+	fn texture_set_wrap(&mut self, _texture: TextureId, _wrap_x: TextureWrap, _wrap_y: TextureWrap) {}
+
+	fn texture_generate_mipmaps(&mut self, _texture: TextureId) {}
+
+	fn texture_resize(&mut self, texture: TextureId, width: u32, height: u32, _bytes: Option<&[u8]>) {
+		let index = self.texture_index(texture);
+		self.textures[index].1.width = width;
+		self.textures[index].1.height = height;
+	}
+
+	fn texture_read_pixels(&mut self, _texture: TextureId, bytes: &mut [u8]) {
+		bytes.fill(0);
+	}
+
+	fn texture_update_part(&mut self, texture: TextureId, x_offset: i32, y_offset: i32, width: i32, height: i32, _bytes: &[u8]) {
+		self.calls.push(RecordedCall::TextureUpdate {
+			texture,
+			x: x_offset,
+			y: y_offset,
+			width,
+			height,
+		});
+	}
+
+	fn new_render_pass_mrt(&mut self, color_img: &[TextureId], _resolve_img: Option<&[TextureId]>, _depth_img: Option<TextureId>) -> RenderPass {
+		let render_pass = handle(self.render_passes.len());
+		self.render_passes.push((render_pass, color_img.to_vec()));
+		render_pass
+	}
+
+	fn render_pass_color_attachments(&self, render_pass: RenderPass) -> &[TextureId] {
+		let (_, textures) = self.render_passes.iter().find(|(id, _)| *id == render_pass).expect("Unknown render pass");
+		textures
+	}
+
+	fn delete_render_pass(&mut self, _render_pass: RenderPass) {}
+
+	fn new_pipeline(&mut self, _buffer_layout: &[BufferLayout], _attributes: &[VertexAttribute], _shader: ShaderId, _params: PipelineParams) -> Pipeline {
+		self.pipelines += 1;
+		handle(self.pipelines - 1)
+	}
+
+	fn apply_pipeline(&mut self, pipeline: &Pipeline) {
+		self.calls.push(RecordedCall::ApplyPipeline(*pipeline));
+	}
+
+	fn delete_pipeline(&mut self, pipeline: Pipeline) {
+		self.calls.push(RecordedCall::DeletePipeline(pipeline));
+	}
+
+	fn new_buffer(&mut self, buffer_type: BufferType, usage: BufferUsage, data: BufferSource) -> BufferId {
+		let buffer = handle(self.buffers.len());
+		let size = match data {
+			BufferSource::Empty { size, .. } => Some(size),
+			BufferSource::Slice(_) => None,
+		};
+
+		self.buffers.push((buffer, size.unwrap_or(0)));
+		self.calls.push(RecordedCall::NewBuffer { buffer, buffer_type, usage, size });
+		buffer
+	}
+
+	fn buffer_update(&mut self, buffer: BufferId, _data: BufferSource) {
+		self.calls.push(RecordedCall::BufferUpdate { buffer });
+	}
+
+	fn buffer_size(&mut self, buffer: BufferId) -> usize {
+		self.buffers.iter().find(|(id, _)| *id == buffer).map_or(0, |(_, size)| *size)
+	}
+
+	fn delete_buffer(&mut self, _buffer: BufferId) {}
+
+	fn delete_texture(&mut self, _texture: TextureId) {}
+
+	fn delete_shader(&mut self, program: ShaderId) {
+		self.calls.push(RecordedCall::DeleteShader(program));
+	}
+
+	fn apply_viewport(&mut self, x: i32, y: i32, w: i32, h: i32) {
+		self.calls.push(RecordedCall::ApplyViewport { x, y, w, h });
+	}
+
+	fn apply_scissor_rect(&mut self, x: i32, y: i32, w: i32, h: i32) {
+		self.calls.push(RecordedCall::ApplyScissorRect { x, y, w, h });
+	}
+
+	fn apply_bindings_from_slice(&mut self, vertex_buffers: &[BufferId], index_buffer: BufferId, textures: &[TextureId]) {
+		self.calls.push(RecordedCall::ApplyBindings {
+			vertex_buffers: vertex_buffers.to_vec(),
+			index_buffer,
+			images: textures.to_vec(),
+		});
+	}
+
+	fn apply_uniforms_from_bytes(&mut self, _uniform_ptr: *const u8, _size: usize) {}
+
+	fn clear(&mut self, _color: Option<(f32, f32, f32, f32)>, _depth: Option<f32>, _stencil: Option<i32>) {}
+
+	fn begin_default_pass(&mut self, _action: PassAction) {
+		self.calls.push(RecordedCall::BeginPass(None));
+	}
+
+	fn begin_pass(&mut self, pass: Option<RenderPass>, _action: PassAction) {
+		self.calls.push(RecordedCall::BeginPass(pass));
+	}
+
+	fn end_render_pass(&mut self) {
+		self.calls.push(RecordedCall::EndPass);
+	}
+
+	fn commit_frame(&mut self) {
+		self.calls.push(RecordedCall::CommitFrame);
+	}
+
+	fn draw(&self, base_element: i32, num_elements: i32, num_instances: i32) {
+		self.calls.push(RecordedCall::Draw {
+			base_element,
+			num_elements,
+			num_instances,
+		});
+	}
+}
@@ -0,0 +1,212 @@
+use glam::{vec2, Vec2};
+use miniquad::*;
+
+use super::material::{Material, MaterialParams};
+use super::rgba::{Rgba, BLANK};
+use super::{PipelineError, RenderingBackend};
+use crate::asset::Font;
+
+/// Outline and drop shadow of text drawn with the [SDF material](RenderingBackend::request_sdf_material).
+/// The default draws plain text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfStyle {
+	pub outline_color: Rgba,
+	/// Width of the outline as a fraction of the font's distance range, up to `0.5`
+	pub outline_width: f32,
+	pub shadow_color: Rgba,
+	/// Offset of the shadow in pixels of the font pages, `y` pointing up. The shadow is cut off outside the glyph quads,
+	/// so it can't be offset further than the distance range
+	pub shadow_offset: Vec2,
+	/// Blur of the shadow edge as a fraction of the distance range
+	pub shadow_softness: f32,
+}
+
+impl Default for SdfStyle {
+	fn default() -> Self {
+		Self {
+			outline_color: BLANK,
+			outline_width: 0.0,
+			shadow_color: BLANK,
+			shadow_offset: Vec2::ZERO,
+			shadow_softness: 0.0,
+		}
+	}
+}
+
+impl RenderingBackend {
+	/// Creates the built-in material for [distance field fonts](crate::asset::DistanceField), which stay sharp at any scale or camera zoom.
+	/// Text is drawn with its vertex color, over an optional outline and drop shadow controlled by the `OutlineColor`, `OutlineWidth`,
+	/// `ShadowColor`, `ShadowOffset` and `ShadowSoftness` uniforms. They can be set with [`material_set_uniform`](RenderingBackend::material_set_uniform),
+	/// or all at once with [`set_sdf_style`](RenderingBackend::set_sdf_style).
+	///
+	/// Single channel fields have to be stored in the color channels, as msdfgen tools write them
+	pub fn request_sdf_material(&mut self) -> Result<Material, ShaderError> {
+		let source = match self.info().backend {
+			Backend::OpenGl => ShaderSource::Glsl {
+				vertex: shader::VERTEX,
+				fragment: shader::FRAGMENT,
+			},
+			Backend::Metal => ShaderSource::Msl { program: shader::METAL },
+		};
+		let params = MaterialParams {
+			pipeline_params: PipelineParams {
+				color_blend: Some(BlendState::new(
+					Equation::Add,
+					BlendFactor::Value(BlendValue::SourceAlpha),
+					BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+				)),
+				..Default::default()
+			},
+			uniforms: shader::uniforms().into_iter().map(|(name, kind)| (name.to_string(), kind)).collect(),
+			textures: Vec::new(),
+		};
+
+		self.request_material(source, params)
+	}
+
+	/// Sets all the uniforms of the [SDF material](RenderingBackend::request_sdf_material) for text of the font
+	pub fn set_sdf_style(&mut self, material: &Material, font: &Font, style: &SdfStyle) -> Result<(), PipelineError> {
+		// Texture coordinates point down
+		let shadow_offset = match font.page_size.x > 0.0 && font.page_size.y > 0.0 {
+			true => style.shadow_offset * vec2(1.0, -1.0) / font.page_size,
+			false => Vec2::ZERO,
+		};
+
+		self.material_set_uniform(material, "OutlineColor", style.outline_color.to_float().to_array())?;
+		self.material_set_uniform(material, "ShadowColor", style.shadow_color.to_float().to_array())?;
+		self.material_set_uniform(material, "ShadowOffset", shadow_offset.to_array())?;
+		self.material_set_uniform(material, "OutlineWidth", style.outline_width)?;
+		self.material_set_uniform(material, "ShadowSoftness", style.shadow_softness)
+	}
+}
+
+mod shader {
+	use miniquad::UniformType;
+
+	pub const VERTEX: &str = r#"#version 100
+	attribute vec3 position;
+	attribute vec2 texcoord;
+	attribute vec4 color0;
+
+	varying mediump vec2 uv;
+	varying lowp vec4 color;
+
+	uniform mat4 Model;
+	uniform mat4 Projection;
+
+	void main() {
+		gl_Position = Projection * Model * vec4(position, 1);
+		color = color0 / 255.0;
+		uv = texcoord;
+	}"#;
+
+	pub const FRAGMENT: &str = r#"#version 100
+	#extension GL_OES_standard_derivatives : enable
+	precision mediump float;
+
+	varying lowp vec4 color;
+	varying mediump vec2 uv;
+
+	uniform sampler2D Texture;
+	uniform vec4 OutlineColor;
+	uniform vec4 ShadowColor;
+	uniform vec2 ShadowOffset;
+	uniform float OutlineWidth;
+	uniform float ShadowSoftness;
+
+	float field(vec2 at) {
+		vec3 texel = texture2D(Texture, at).rgb;
+		return max(min(texel.r, texel.g), min(max(texel.r, texel.g), texel.b));
+	}
+
+	void main() {
+		float dist = field(uv);
+		float edge = max(fwidth(dist), 0.0001);
+
+		float fill = smoothstep(0.5 - edge, 0.5 + edge, dist);
+		float outline = smoothstep(0.5 - OutlineWidth - edge, 0.5 - OutlineWidth + edge, dist);
+		vec4 glyph = vec4(mix(OutlineColor.rgb, color.rgb, fill), mix(OutlineColor.a, color.a, fill) * outline);
+
+		float shadow_edge = 0.5 - OutlineWidth;
+		float shadow = smoothstep(shadow_edge - edge - ShadowSoftness, shadow_edge + edge, field(uv - ShadowOffset)) * ShadowColor.a;
+
+		float alpha = glyph.a + shadow * (1.0 - glyph.a);
+		vec3 rgb = (glyph.rgb * glyph.a + ShadowColor.rgb * shadow * (1.0 - glyph.a)) / max(alpha, 0.0001);
+		gl_FragColor = vec4(rgb, alpha);
+	}"#;
+
+	pub const METAL: &str = r#"#include <metal_stdlib>
+	using namespace metal;
+
+	struct Uniforms
+	{
+		float4x4 Projection;
+		float4x4 Model;
+		float4 _Time;
+		float4 OutlineColor;
+		float4 ShadowColor;
+		float2 ShadowOffset;
+		float OutlineWidth;
+		float ShadowSoftness;
+	};
+
+	struct Vertex
+	{
+		float3 position    [[attribute(0)]];
+		float2 texcoord    [[attribute(1)]];
+		float4 color0      [[attribute(2)]];
+	};
+
+	struct RasterizerData
+	{
+		float4 position [[position]];
+		float4 color [[user(locn0)]];
+		float2 uv [[user(locn1)]];
+	};
+
+	vertex RasterizerData vertexShader(Vertex v [[stage_in]], constant Uniforms& uniforms [[buffer(0)]])
+	{
+		RasterizerData out;
+
+		out.position = uniforms.Projection * uniforms.Model * float4(v.position, 1);
+		out.color = v.color0 / 255.0;
+		out.uv = v.texcoord;
+
+		return out;
+	}
+
+	float field(texture2d<float> tex, sampler texSmplr, float2 at)
+	{
+		float3 texel = tex.sample(texSmplr, at).rgb;
+		return max(min(texel.r, texel.g), min(max(texel.r, texel.g), texel.b));
+	}
+
+	fragment float4 fragmentShader(RasterizerData in [[stage_in]], constant Uniforms& uniforms [[buffer(0)]], texture2d<float> tex [[texture(0)]], sampler texSmplr [[sampler(0)]])
+	{
+		float dist = field(tex, texSmplr, in.uv);
+		float edge = max(fwidth(dist), 0.0001);
+
+		float fill = smoothstep(0.5 - edge, 0.5 + edge, dist);
+		float outline = smoothstep(0.5 - uniforms.OutlineWidth - edge, 0.5 - uniforms.OutlineWidth + edge, dist);
+		float4 glyph = float4(mix(uniforms.OutlineColor.rgb, in.color.rgb, fill), mix(uniforms.OutlineColor.a, in.color.a, fill) * outline);
+
+		float shadow_edge = 0.5 - uniforms.OutlineWidth;
+		float shadow = smoothstep(shadow_edge - edge - uniforms.ShadowSoftness, shadow_edge + edge, field(tex, texSmplr, in.uv - uniforms.ShadowOffset)) * uniforms.ShadowColor.a;
+
+		float alpha = glyph.a + shadow * (1.0 - glyph.a);
+		float3 rgb = (glyph.rgb * glyph.a + uniforms.ShadowColor.rgb * shadow * (1.0 - glyph.a)) / max(alpha, 0.0001);
+		return float4(rgb, alpha);
+	}
+	"#;
+
+	/// Uniforms after the built-in ones, in the order of the Metal uniform struct
+	pub fn uniforms() -> Vec<(&'static str, UniformType)> {
+		vec![
+			("OutlineColor", UniformType::Float4),
+			("ShadowColor", UniformType::Float4),
+			("ShadowOffset", UniformType::Float2),
+			("OutlineWidth", UniformType::Float1),
+			("ShadowSoftness", UniformType::Float1),
+		]
+	}
+}
@@ -0,0 +1,245 @@
+use std::f32::consts::TAU;
+use std::ops::{Deref, DerefMut};
+
+use bevy_ecs::system::{NonSendMut, SystemParam};
+use glam::{vec2, vec3, Vec2, Vec4};
+use miniquad::TextureId;
+
+use super::geometry::Vertex;
+use super::path::{FillRule, Path};
+use super::pipeline::DrawMode;
+use super::rgba::{Rgba, WHITE};
+use super::sprite::Sprite;
+use super::stroke::{tessellate_stroke, StrokeStyle};
+use super::{GeometryError, RenderingBackend};
+
+/// Number of sides of the polygons [circles](RenderingBackend::draw_circle) are drawn with
+const CIRCLE_SIDES: u8 = 32;
+
+/// Miters of sharp outline corners are cut to this many times half the line thickness
+const MITER_LIMIT: f32 = 4.0;
+
+/// Rotation and color of [`RenderingBackend::draw_rectangle_ex`] and [`RenderingBackend::draw_rectangle_lines_ex`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawRectangleParams {
+	/// Counter-clockwise rotation in radians around the pivot
+	pub rotation: f32,
+	/// Point the rectangle rotates around, relative to its position. The center of the rectangle when `None`
+	pub pivot: Option<Vec2>,
+	pub color: Rgba,
+}
+
+impl Default for DrawRectangleParams {
+	fn default() -> Self {
+		Self {
+			rotation: 0.0,
+			pivot: None,
+			color: WHITE,
+		}
+	}
+}
+
+/// Region, size and transformation of [`RenderingBackend::draw_texture_ex`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DrawTextureParams {
+	/// Size of the drawn quad in world units. The size of [`source`](DrawTextureParams::source) (or of the whole texture) in pixels when `None`
+	pub dest_size: Option<Vec2>,
+	/// Region of the texture to draw as `(x, y, width, height)` in pixels. The whole texture when `None`
+	pub source: Option<Vec4>,
+	/// Counter-clockwise rotation in radians around the pivot
+	pub rotation: f32,
+	/// Point the quad rotates around, relative to its position. The center of the quad when `None`
+	pub pivot: Option<Vec2>,
+	/// Mirrors the texture horizontally
+	pub flip_x: bool,
+	/// Mirrors the texture vertically
+	pub flip_y: bool,
+}
+
+/// Corners of a rotated rectangle (top-left, top-right, bottom-left, bottom-right), with `position` being its bottom left corner before the rotation
+fn rectangle_corners(position: Vec2, size: Vec2, rotation: f32, pivot: Option<Vec2>) -> [Vec2; 4] {
+	let pivot = position + pivot.unwrap_or(size / 2.0);
+	let rotation = Vec2::from_angle(rotation);
+	let corner = |x: f32, y: f32| pivot + rotation.rotate(position + vec2(x, y) - pivot);
+
+	[corner(0.0, size.y), corner(size.x, size.y), corner(0.0, 0.0), corner(size.x, 0.0)]
+}
+
+/// Corners of a regular polygon, the first one pointing at `rotation`
+fn polygon_points(center: Vec2, sides: u8, radius: f32, rotation: f32) -> Vec<Vec2> {
+	let sides = sides.max(3);
+	(0..sides).map(|i| center + Vec2::from_angle(rotation + TAU * i as f32 / sides as f32) * radius).collect()
+}
+
+fn vertex(position: Vec2, color: Rgba) -> Vertex {
+	Vertex::new(vec3(position.x, position.y, 0.0), Vec2::ZERO, color)
+}
+
+impl RenderingBackend {
+	/// Draws untextured triangles with the current pipeline, batched with the surrounding geometry
+	fn shape(&mut self, vertices: &[Vertex], indices: &[u32]) -> Result<(), GeometryError> {
+		self.texture(None);
+		self.draw_mode(DrawMode::Triangles);
+		self.geometry(vertices, indices)
+	}
+
+	/// Draws a line as a quad `thickness` units wide
+	pub fn draw_line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Rgba) -> Result<(), GeometryError> {
+		let normal = (end - start).perp().normalize_or_zero() * thickness / 2.0;
+		let vertices = [start + normal, end + normal, start - normal, end - normal].map(|position| vertex(position, color));
+		self.shape(&vertices, &Sprite::INDICES)
+	}
+
+	/// Draws a polyline as triangles with the width, joins and caps of the style. A `closed` polyline connects the last point back to the first
+	pub fn draw_polyline(&mut self, points: &[Vec2], closed: bool, style: &StrokeStyle, color: Rgba) -> Result<(), GeometryError> {
+		let (positions, indices) = tessellate_stroke(points, closed, style);
+		let vertices: Vec<Vertex> = positions.into_iter().map(|position| vertex(position, color)).collect();
+		self.shape(&vertices, &indices)
+	}
+
+	/// Fills the inside of the path according to the fill rule, see [`Path::tessellate_fill`]
+	pub fn fill_path(&mut self, path: &Path, rule: FillRule, color: Rgba) -> Result<(), GeometryError> {
+		let (positions, indices) = path.tessellate_fill(rule);
+		let vertices: Vec<Vertex> = positions.into_iter().map(|position| vertex(position, color)).collect();
+		self.shape(&vertices, &indices)
+	}
+
+	/// Strokes every contour of the path, closing the closed ones with joins instead of caps
+	pub fn stroke_path(&mut self, path: &Path, style: &StrokeStyle, color: Rgba) -> Result<(), GeometryError> {
+		let mesh = path.stroke(style, color);
+		self.shape(&mesh.vertices, &mesh.indices)
+	}
+
+	/// Draws a filled rectangle with its bottom left corner at `position`
+	pub fn draw_rectangle(&mut self, position: Vec2, size: Vec2, color: Rgba) -> Result<(), GeometryError> {
+		self.draw_rectangle_ex(position, size, &DrawRectangleParams { color, ..Default::default() })
+	}
+
+	/// Draws a filled rectangle with its bottom left corner at `position`, rotated around the pivot
+	pub fn draw_rectangle_ex(&mut self, position: Vec2, size: Vec2, params: &DrawRectangleParams) -> Result<(), GeometryError> {
+		let vertices = rectangle_corners(position, size, params.rotation, params.pivot).map(|position| vertex(position, params.color));
+		self.shape(&vertices, &Sprite::INDICES)
+	}
+
+	/// Draws the outline of a rectangle with its bottom left corner at `position`. The lines are centered on the edges
+	pub fn draw_rectangle_lines(&mut self, position: Vec2, size: Vec2, thickness: f32, color: Rgba) -> Result<(), GeometryError> {
+		self.draw_rectangle_lines_ex(position, size, thickness, &DrawRectangleParams { color, ..Default::default() })
+	}
+
+	/// Draws the outline of a rectangle with its bottom left corner at `position`, rotated around the pivot. The lines are centered on the edges
+	pub fn draw_rectangle_lines_ex(&mut self, position: Vec2, size: Vec2, thickness: f32, params: &DrawRectangleParams) -> Result<(), GeometryError> {
+		let [top_left, top_right, bottom_left, bottom_right] = rectangle_corners(position, size, params.rotation, params.pivot);
+		self.draw_outline(&[bottom_left, bottom_right, top_right, top_left], thickness, params.color)
+	}
+
+	pub fn draw_triangle(&mut self, a: Vec2, b: Vec2, c: Vec2, color: Rgba) -> Result<(), GeometryError> {
+		self.shape(&[vertex(a, color), vertex(b, color), vertex(c, color)], &[0, 1, 2])
+	}
+
+	/// Draws the outline of a triangle, with the lines centered on the edges
+	pub fn draw_triangle_lines(&mut self, a: Vec2, b: Vec2, c: Vec2, thickness: f32, color: Rgba) -> Result<(), GeometryError> {
+		self.draw_outline(&[a, b, c], thickness, color)
+	}
+
+	pub fn draw_circle(&mut self, center: Vec2, radius: f32, color: Rgba) -> Result<(), GeometryError> {
+		self.draw_poly(center, CIRCLE_SIDES, radius, 0.0, color)
+	}
+
+	/// Draws the outline of a circle, with the line centered on its edge
+	pub fn draw_circle_lines(&mut self, center: Vec2, radius: f32, thickness: f32, color: Rgba) -> Result<(), GeometryError> {
+		self.draw_poly_lines(center, CIRCLE_SIDES, radius, 0.0, thickness, color)
+	}
+
+	/// Draws a filled regular polygon of at least 3 sides, with the first corner pointing at `rotation` radians
+	pub fn draw_poly(&mut self, center: Vec2, sides: u8, radius: f32, rotation: f32, color: Rgba) -> Result<(), GeometryError> {
+		let points = polygon_points(center, sides, radius, rotation);
+		let vertices: Vec<Vertex> = std::iter::once(center).chain(points.iter().copied()).map(|position| vertex(position, color)).collect();
+		let sides = points.len() as u32;
+		let indices: Vec<u32> = (0..sides).flat_map(|i| [0, i + 1, (i + 1) % sides + 1]).collect();
+		self.shape(&vertices, &indices)
+	}
+
+	/// Draws the outline of a regular polygon of at least 3 sides, with the first corner pointing at `rotation` radians. The lines are centered on the edges
+	pub fn draw_poly_lines(&mut self, center: Vec2, sides: u8, radius: f32, rotation: f32, thickness: f32, color: Rgba) -> Result<(), GeometryError> {
+		self.draw_outline(&polygon_points(center, sides, radius, rotation), thickness, color)
+	}
+
+	/// Draws a closed outline through the points, with mitered corners
+	fn draw_outline(&mut self, points: &[Vec2], thickness: f32, color: Rgba) -> Result<(), GeometryError> {
+		let count = points.len();
+		let mut vertices = Vec::with_capacity(count * 2);
+		for (i, point) in points.iter().enumerate() {
+			let previous = points[(i + count - 1) % count];
+			let next = points[(i + 1) % count];
+			let normals = ((*point - previous).perp().normalize_or_zero(), (next - *point).perp().normalize_or_zero());
+
+			// The miter is along the average normal, stretched to keep the line width
+			let miter = (normals.0 + normals.1).try_normalize().unwrap_or(normals.0);
+			let length = thickness / 2.0 / miter.dot(normals.0).max(1.0 / MITER_LIMIT);
+			vertices.push(vertex(*point + miter * length, color));
+			vertices.push(vertex(*point - miter * length, color));
+		}
+
+		let indices: Vec<u32> = (0..count as u32)
+			.flat_map(|i| {
+				let (a, b) = (i * 2, (i + 1) % count as u32 * 2);
+				[a, b, a + 1, b, a + 1, b + 1]
+			})
+			.collect();
+		self.shape(&vertices, &indices)
+	}
+
+	/// Draws the whole texture at its size in pixels, with its bottom left corner at `position`
+	pub fn draw_texture(&mut self, texture: TextureId, position: Vec2, color: Rgba) -> Result<(), GeometryError> {
+		self.draw_texture_ex(texture, position, color, &DrawTextureParams::default())
+	}
+
+	/// Draws a region of the texture with its bottom left corner at `position`, scaled to the destination size and rotated around the pivot
+	pub fn draw_texture_ex(&mut self, texture: TextureId, position: Vec2, color: Rgba, params: &DrawTextureParams) -> Result<(), GeometryError> {
+		let texture_size = Vec2::from(self.texture_size(texture));
+		let source = params.source.unwrap_or(Vec4::new(0.0, 0.0, texture_size.x, texture_size.y));
+		let size = params.dest_size.unwrap_or(vec2(source.z, source.w));
+
+		let (mut u0, mut v0) = (source.x / texture_size.x, source.y / texture_size.y);
+		let (mut u1, mut v1) = ((source.x + source.z) / texture_size.x, (source.y + source.w) / texture_size.y);
+		if params.flip_x {
+			std::mem::swap(&mut u0, &mut u1);
+		}
+		if params.flip_y {
+			std::mem::swap(&mut v0, &mut v1);
+		}
+
+		let [top_left, top_right, bottom_left, bottom_right] = rectangle_corners(position, size, params.rotation, params.pivot);
+		let vertices = [
+			Vertex::new(top_left.extend(0.0), vec2(u0, v0), color),
+			Vertex::new(top_right.extend(0.0), vec2(u1, v0), color),
+			Vertex::new(bottom_left.extend(0.0), vec2(u0, v1), color),
+			Vertex::new(bottom_right.extend(0.0), vec2(u1, v1), color),
+		];
+
+		self.texture(Some(&texture));
+		self.draw_mode(DrawMode::Triangles);
+		self.geometry(&vertices, &Sprite::INDICES)
+	}
+}
+
+/// Immediate mode shape drawing for systems, like [`draw_rectangle`](RenderingBackend::draw_rectangle) or [`draw_texture_ex`](RenderingBackend::draw_texture_ex).
+/// Shapes go through the [`RenderingBackend`] it dereferences to, so they're batched with the rest of the geometry
+#[derive(SystemParam)]
+pub struct Shapes<'w> {
+	backend: NonSendMut<'w, RenderingBackend>,
+}
+
+impl Deref for Shapes<'_> {
+	type Target = RenderingBackend;
+
+	fn deref(&self) -> &Self::Target {
+		&self.backend
+	}
+}
+
+impl DerefMut for Shapes<'_> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.backend
+	}
+}
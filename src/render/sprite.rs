@@ -0,0 +1,223 @@
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::{
+	component::Component,
+	entity::Entity,
+	schedule::SystemSet,
+	system::{Commands, Local, NonSendMut, Query, Res},
+};
+use glam::{vec2, vec3, Vec2, Vec4};
+
+use super::camera::{Camera2D, CurrentCameraTag, RenderTarget};
+use super::geometry::Vertex;
+use super::rgba::{Rgba, WHITE};
+use super::RenderingBackend;
+use crate::asset::{Texture, TextureAtlas};
+use crate::transform::GlobalTransform;
+
+/// A textured quad, drawn at the [`GlobalTransform`] of the entity
+#[derive(Debug, Clone, Component)]
+pub struct Sprite {
+	pub texture: Handle<Texture>,
+	/// Multiplied with the texture color
+	pub color: Rgba,
+	/// Mirrors the texture horizontally
+	pub flip_x: bool,
+	/// Mirrors the texture vertically
+	pub flip_y: bool,
+	/// Size of the sprite in world units. When `None`, the size of [`rect`](Sprite::rect) (or of the whole texture) in pixels is used
+	pub custom_size: Option<Vec2>,
+	/// Region of the texture to draw as `(x, y, width, height)` in pixels. When `None`, the whole texture is drawn
+	pub rect: Option<Vec4>,
+	/// Point of the sprite placed at its transform's position
+	pub anchor: Anchor,
+}
+
+impl Sprite {
+	pub fn new(texture: Handle<Texture>) -> Self {
+		Self {
+			texture,
+			color: WHITE,
+			flip_x: false,
+			flip_y: false,
+			custom_size: None,
+			rect: None,
+			anchor: Anchor::Center,
+		}
+	}
+
+	/// Indices of the [`vertices`](Sprite::vertices) of the sprite
+	pub const INDICES: [u32; 6] = [0, 1, 2, 1, 2, 3];
+
+	/// Vertices of the sprite quad in world space (top-left, top-right, bottom-left, bottom-right), for a texture of the given size in pixels
+	pub fn vertices(&self, transform: &GlobalTransform, texture_size: Vec2) -> [Vertex; 4] {
+		let rect = self.rect.unwrap_or(Vec4::new(0.0, 0.0, texture_size.x, texture_size.y));
+		let size = self.custom_size.unwrap_or(vec2(rect.z, rect.w));
+
+		let (mut u0, mut v0) = (rect.x / texture_size.x, rect.y / texture_size.y);
+		let (mut u1, mut v1) = ((rect.x + rect.z) / texture_size.x, (rect.y + rect.w) / texture_size.y);
+		if self.flip_x {
+			std::mem::swap(&mut u0, &mut u1);
+		}
+		if self.flip_y {
+			std::mem::swap(&mut v0, &mut v1);
+		}
+
+		let anchor = self.anchor.as_vec();
+		let matrix = transform.compute_matrix();
+		let corner = |x: f32, y: f32, u: f32, v: f32| {
+			let position = vec3((x - anchor.x) * size.x, (y - anchor.y) * size.y, 0.0);
+			Vertex::new(matrix.transform_point3(position), vec2(u, v), self.color)
+		};
+
+		[corner(-0.5, 0.5, u0, v0), corner(0.5, 0.5, u1, v0), corner(-0.5, -0.5, u0, v1), corner(0.5, -0.5, u1, v1)]
+	}
+}
+
+/// Point of a [`Sprite`] that's placed at the entity's position
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Anchor {
+	#[default]
+	Center,
+	BottomLeft,
+	BottomCenter,
+	BottomRight,
+	CenterLeft,
+	CenterRight,
+	TopLeft,
+	TopCenter,
+	TopRight,
+	/// Custom anchor point, where `(-0.5, -0.5)` is the bottom left corner and `(0.5, 0.5)` is the top right one
+	Custom(Vec2),
+}
+
+impl Anchor {
+	pub fn as_vec(&self) -> Vec2 {
+		match self {
+			Self::Center => vec2(0.0, 0.0),
+			Self::BottomLeft => vec2(-0.5, -0.5),
+			Self::BottomCenter => vec2(0.0, -0.5),
+			Self::BottomRight => vec2(0.5, -0.5),
+			Self::CenterLeft => vec2(-0.5, 0.0),
+			Self::CenterRight => vec2(0.5, 0.0),
+			Self::TopLeft => vec2(-0.5, 0.5),
+			Self::TopCenter => vec2(0.0, 0.5),
+			Self::TopRight => vec2(0.5, 0.5),
+			Self::Custom(point) => *point,
+		}
+	}
+}
+
+/// Draws a region of a [`TextureAtlas`]. Keeps the [`Sprite`] of the entity (inserting it if missing) pointing at the atlas texture and the region at `index`
+#[derive(Debug, Clone, Component)]
+pub struct TextureAtlasSprite {
+	pub atlas: Handle<TextureAtlas>,
+	/// Index of the region in the atlas
+	pub index: usize,
+}
+
+impl TextureAtlasSprite {
+	pub fn new(atlas: Handle<TextureAtlas>, index: usize) -> Self {
+		Self { atlas, index }
+	}
+}
+
+/// Copies the texture and the region of [`TextureAtlasSprite`]s into their [`Sprite`]s
+pub(crate) fn sync_atlas_sprites(mut commands: Commands, atlases: Res<Assets<TextureAtlas>>, mut sprites: Query<(Entity, &TextureAtlasSprite, Option<&mut Sprite>)>) {
+	for (entity, atlas_sprite, sprite) in sprites.iter_mut() {
+		// The atlas could be still loading
+		let atlas = match atlases.get(&atlas_sprite.atlas) {
+			Some(atlas) => atlas,
+			None => continue,
+		};
+
+		let rect = atlas.rect(atlas_sprite.index);
+		if rect.is_none() {
+			#[cfg(feature = "log")]
+			bevy_log::warn!("Atlas region {} of {:?} doesn't exist", atlas_sprite.index, entity);
+		}
+
+		match sprite {
+			Some(mut sprite) => {
+				if sprite.texture != atlas.texture {
+					sprite.texture = atlas.texture.clone();
+				}
+				if sprite.rect != rect {
+					sprite.rect = rect;
+				}
+			}
+			None => {
+				commands.entity(entity).insert(Sprite {
+					rect,
+					..Sprite::new(atlas.texture.clone())
+				});
+			}
+		}
+	}
+}
+
+/// System set of the default sprite rendering, in the [`MiniquadDraw`](crate::window::state::MiniquadDraw) schedule. Runs after the [`MeshRenderSet`](super::mesh::MeshRenderSet)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct SpriteRenderSet;
+
+/// Draws all [`Sprite`]s from the view of the current camera, from the lowest to the highest global `z` translation.
+/// Sprites of the same `z` are grouped by texture, and every run of sprites sharing a texture is drawn with a single [`RenderingBackend::geometry`] call.
+pub(crate) fn render_sprites(
+	mut render_ctx: NonSendMut<RenderingBackend>,
+	current_camera: Res<CurrentCameraTag>,
+	cameras: Query<(&Camera2D, &RenderTarget)>,
+	textures: Res<Assets<Texture>>,
+	sprites: Query<(&Sprite, &GlobalTransform)>,
+	mut batch: Local<(Vec<Vertex>, Vec<u32>)>,
+) {
+	if sprites.is_empty() {
+		return;
+	}
+
+	let (camera, render_target) = match cameras.get(current_camera.0) {
+		Ok(camera) => camera,
+		Err(_e) => {
+			#[cfg(feature = "log")]
+			bevy_log::error!("Failed to get the current Camera: {:?}, {:?}", current_camera.0, _e);
+			return;
+		}
+	};
+
+	// The texture could be still loading
+	let mut sprites: Vec<_> = sprites.iter().filter_map(|(sprite, transform)| textures.get(&sprite.texture).map(|texture| (sprite, transform, texture.id()))).collect();
+	sprites.sort_by(|(a, a_transform, _), (b, b_transform, _)| a_transform.translation().z.total_cmp(&b_transform.translation().z).then_with(|| a.texture.id().cmp(&b.texture.id())));
+
+	render_ctx.begin_camera(camera, render_target);
+
+	let (vertices, indices) = &mut *batch;
+	let mut batch_texture = None;
+	for (sprite, transform, texture) in sprites {
+		if batch_texture != Some(texture) {
+			flush(&mut render_ctx, batch_texture, vertices, indices);
+			batch_texture = Some(texture);
+		}
+
+		let texture_size = render_ctx.texture_size(texture);
+		let start = vertices.len() as u32;
+		vertices.extend(sprite.vertices(transform, vec2(texture_size.0, texture_size.1)));
+		indices.extend(Sprite::INDICES.map(|index| start + index));
+	}
+	flush(&mut render_ctx, batch_texture, vertices, indices);
+
+	render_ctx.end_camera(camera);
+}
+
+fn flush(render_ctx: &mut RenderingBackend, texture: Option<miniquad::TextureId>, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>) {
+	if indices.is_empty() {
+		return;
+	}
+
+	render_ctx.pipeline(None);
+	render_ctx.texture(texture.as_ref());
+	if let Err(_err) = render_ctx.geometry(vertices, indices) {
+		#[cfg(feature = "log")]
+		bevy_log::error!("Failed to draw sprites: {}", _err);
+	}
+
+	vertices.clear();
+	indices.clear();
+}
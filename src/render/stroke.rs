@@ -0,0 +1,229 @@
+use std::f32::consts::PI;
+
+use glam::{vec3, Vec2, Vec3};
+
+use super::geometry::{Mesh, Vertex};
+use super::rgba::Rgba;
+
+/// Number of triangles of a half circle in round joins and caps
+const ROUND_SEGMENTS: f32 = 16.0;
+
+/// Shape of the outer corner where two segments of a stroke meet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+	/// Extends the outer edges until they meet, falling back to [`Bevel`](LineJoin::Bevel) past the [miter limit](StrokeStyle::miter_limit)
+	#[default]
+	Miter,
+	/// Cuts the corner straight between the outer edges
+	Bevel,
+	/// Rounds the corner with an arc around the point
+	Round,
+}
+
+/// Shape of the ends of an open stroke
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineCap {
+	/// Ends the stroke exactly at the end points
+	#[default]
+	Butt,
+	/// Extends the stroke by half its width past the end points
+	Square,
+	/// Ends the stroke with a half circle around the end points
+	Round,
+}
+
+/// Width, joins and caps of [stroked](tessellate_stroke) polylines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrokeStyle {
+	pub width: f32,
+	pub join: LineJoin,
+	pub cap: LineCap,
+	/// Longest miter, in multiples of half the width, before a [`LineJoin::Miter`] is beveled. Same as SVG's `stroke-miterlimit`
+	pub miter_limit: f32,
+}
+
+impl Default for StrokeStyle {
+	fn default() -> Self {
+		Self {
+			width: 1.0,
+			join: LineJoin::Miter,
+			cap: LineCap::Butt,
+			miter_limit: 4.0,
+		}
+	}
+}
+
+impl StrokeStyle {
+	pub fn new(width: f32) -> Self {
+		Self { width, ..Default::default() }
+	}
+
+	pub fn with_join(mut self, join: LineJoin) -> Self {
+		self.join = join;
+		self
+	}
+
+	pub fn with_cap(mut self, cap: LineCap) -> Self {
+		self.cap = cap;
+		self
+	}
+
+	pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+		self.miter_limit = miter_limit;
+		self
+	}
+}
+
+/// Triangles being built from a stroke
+#[derive(Default)]
+struct Triangles {
+	positions: Vec<Vec2>,
+	indices: Vec<u32>,
+}
+
+impl Triangles {
+	fn quad(&mut self, top_left: Vec2, top_right: Vec2, bottom_left: Vec2, bottom_right: Vec2) {
+		let start = self.positions.len() as u32;
+		self.positions.extend([top_left, top_right, bottom_left, bottom_right]);
+		self.indices.extend([0, 1, 2, 1, 2, 3].map(|i| start + i));
+	}
+
+	/// Triangle fan around `center` through the points
+	fn fan(&mut self, center: Vec2, points: impl IntoIterator<Item = Vec2>) {
+		let start = self.positions.len() as u32;
+		self.positions.push(center);
+		self.positions.extend(points);
+		let end = self.positions.len() as u32;
+		self.indices.extend((start + 1..end.saturating_sub(1)).flat_map(|i| [start, i, i + 1]));
+	}
+
+	/// Fan around `pivot` through an arc of `radius` around `center`, going counter-clockwise from `from` by `angle` radians
+	fn arc(&mut self, pivot: Vec2, center: Vec2, from: Vec2, angle: f32, radius: f32) {
+		let steps = (angle.abs() / PI * ROUND_SEGMENTS).ceil().max(1.0) as u32;
+		let start = from.to_angle();
+		self.fan(pivot, (0..=steps).map(|i| center + Vec2::from_angle(start + angle * i as f32 / steps as f32) * radius));
+	}
+}
+
+/// Tessellates a polyline into triangles `style.width` units wide, centered on the lines. A `closed` polyline also connects the last point back to the first,
+/// and has joins instead of caps there.
+///
+/// Returns the triangle corners and their indices, in the order of [`Sprite::INDICES`](super::sprite::Sprite::INDICES) for each segment.
+/// Segments overlap at very sharp joins between short segments, where translucent strokes blend twice
+pub fn tessellate_stroke(points: &[Vec2], closed: bool, style: &StrokeStyle) -> (Vec<Vec2>, Vec<u32>) {
+	let mut points: Vec<Vec2> = points.to_vec();
+	points.dedup();
+	if closed && points.len() > 1 && points.first() == points.last() {
+		points.pop();
+	}
+
+	let mut triangles = Triangles::default();
+	let half_width = style.width / 2.0;
+	if points.len() < 2 || half_width <= 0.0 {
+		return (triangles.positions, triangles.indices);
+	}
+
+	let count = points.len();
+	let segment_count = if closed { count } else { count - 1 };
+	let segment = |i: usize| (points[i % count], points[(i + 1) % count]);
+	let direction = |i: usize| {
+		let (start, end) = segment(i);
+		(end - start).normalize()
+	};
+
+	// Inner corners where the segments meet, replacing the end points on the inner side of the turns
+	let mut inner: Vec<Option<Vec2>> = vec![None; count];
+	for (i, inner) in inner.iter_mut().enumerate() {
+		if !closed && (i == 0 || i == count - 1) {
+			continue;
+		}
+		let (previous, next) = (direction(i + count - 1), direction(i));
+		let turn = previous.perp_dot(next);
+		if turn.abs() < 1e-6 {
+			continue;
+		}
+
+		// The inner offset points move back along both segments, and can't cross the middle of either
+		let side = -turn.signum();
+		let miter = match (previous.perp() + next.perp()).try_normalize() {
+			Some(miter) => miter,
+			None => continue,
+		};
+		let length = half_width / miter.dot(previous.perp());
+		let shortest = segment(i + count - 1).0.distance(points[i]).min(points[i].distance(segment(i).1));
+		if (length * length - half_width * half_width).sqrt() <= shortest / 2.0 {
+			*inner = Some(points[i] - miter * length * side);
+		}
+	}
+
+	for i in 0..segment_count {
+		let (mut start, mut end) = segment(i);
+		let dir = direction(i);
+		let normal = dir.perp() * half_width;
+		if !closed && style.cap == LineCap::Square {
+			if i == 0 {
+				start -= dir * half_width;
+			}
+			if i == segment_count - 1 {
+				end += dir * half_width;
+			}
+		}
+
+		// Each end of the segment is replaced by the inner corner on the side the path turns away from
+		let corner = |point: Vec2, index: usize, offset: Vec2| match inner[index % count] {
+			Some(corner) if (corner - point).dot(offset) > 0.0 => corner,
+			_ => point + offset,
+		};
+		let end_index = i + 1;
+		triangles.quad(corner(start, i, normal), corner(end, end_index, normal), corner(start, i, -normal), corner(end, end_index, -normal));
+	}
+
+	for (i, point) in points.iter().copied().enumerate() {
+		if !closed && (i == 0 || i == count - 1) {
+			continue;
+		}
+		let (previous, next) = (direction(i + count - 1), direction(i));
+		let turn = previous.perp_dot(next);
+		if turn.abs() < 1e-6 && previous.dot(next) > 0.0 {
+			continue;
+		}
+
+		// The join fills the gap on the outer side of the turn
+		let side = if turn > 0.0 { -1.0 } else { 1.0 };
+		let (from, to) = (previous.perp() * half_width * side, next.perp() * half_width * side);
+		let pivot = inner[i].unwrap_or(point);
+		match style.join {
+			LineJoin::Round => {
+				// Turning back goes around the front of the point
+				let angle = if turn.abs() < 1e-6 { -PI } else { from.angle_between(to) };
+				triangles.arc(pivot, point, from, angle, half_width)
+			}
+			LineJoin::Miter | LineJoin::Bevel => {
+				let miter = (from + to).try_normalize().map(|miter| (miter, half_width / miter.dot(from / half_width)));
+				match miter {
+					Some((miter, length)) if style.join == LineJoin::Miter && length <= half_width * style.miter_limit => {
+						triangles.fan(pivot, [point + from, point + miter * length, point + to]);
+					}
+					_ => triangles.fan(pivot, [point + from, point + to]),
+				}
+			}
+		}
+	}
+
+	if !closed && style.cap == LineCap::Round {
+		let (first, last) = (direction(0), direction(segment_count - 1));
+		triangles.arc(points[0], points[0], first.perp() * half_width, PI, half_width);
+		triangles.arc(points[count - 1], points[count - 1], -last.perp() * half_width, PI, half_width);
+	}
+
+	(triangles.positions, triangles.indices)
+}
+
+impl Mesh {
+	/// Makes a [stroked](tessellate_stroke) polyline mesh, with the points relative to `pos`
+	pub(crate) fn polyline(pos: Vec3, points: &[Vec2], closed: bool, style: &StrokeStyle, color: Rgba) -> Self {
+		let (positions, indices) = tessellate_stroke(points, closed, style);
+		let vertices = positions.into_iter().map(|position| Vertex::new(pos + vec3(position.x, position.y, 0.0), Vec2::ZERO, color)).collect();
+		Self { vertices, indices }
+	}
+}
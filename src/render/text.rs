@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use bevy_asset::Assets;
+use bevy_ecs::system::Resource;
+use glam::{vec2, vec3, Vec2, Vec4};
+use miniquad::TextureId;
+
+use super::geometry::Vertex;
+use super::rgba::Rgba;
+use super::sprite::Sprite;
+use super::{GeometryError, RenderingBackend};
+use crate::asset::{Font, GlyphQuad, Texture, TtfFont};
+
+/// Space left between the cached glyphs, so they don't bleed into each other with linear filtering
+const GLYPH_PADDING: u32 = 1;
+
+impl RenderingBackend {
+	/// Draws text with a bitmap [`Font`] using the current pipeline, with one [`geometry`](RenderingBackend::geometry) call per font page.
+	/// `position` is the top left corner of the first line, and `scale` multiplies the font's pixel size.
+	///
+	/// Pages that aren't uploaded yet are skipped.
+	pub fn draw_text(&mut self, font: &Font, textures: &Assets<Texture>, text: &str, position: Vec2, scale: f32, color: Rgba) -> Result<(), GeometryError> {
+		let quads = font.glyph_quads(text, position, scale, color);
+		let pages: Vec<_> = font.pages.iter().map(|page| textures.get(page).map(Texture::id)).collect();
+		self.draw_glyph_quads(&quads, &pages)
+	}
+
+	/// Draws text with a [`TtfFont`] at `size` pixels using the current pipeline, rasterizing missing glyphs into the [`GlyphCache`].
+	/// There's one [`geometry`](RenderingBackend::geometry) call per cache page. `position` is the top left corner of the first line
+	pub fn draw_ttf_text(&mut self, cache: &mut GlyphCache, font: &TtfFont, text: &str, position: Vec2, size: f32, color: Rgba) -> Result<(), GeometryError> {
+		let quads = cache.glyph_quads(font, text, position, size, color);
+		cache.flush(self);
+		let pages: Vec<_> = cache.pages.iter().map(|page| page.texture).collect();
+		self.draw_glyph_quads(&quads, &pages)
+	}
+
+	/// Batches the quads by page, skipping pages without a texture
+	pub(crate) fn draw_glyph_quads(&mut self, quads: &[GlyphQuad], pages: &[Option<TextureId>]) -> Result<(), GeometryError> {
+		let mut vertices: Vec<Vertex> = Vec::with_capacity(quads.len() * 4);
+		let mut indices: Vec<u32> = Vec::with_capacity(quads.len() * 6);
+
+		for (page, texture) in pages.iter().enumerate() {
+			let texture = match texture {
+				Some(texture) => *texture,
+				None => continue,
+			};
+
+			for quad in quads.iter().filter(|quad| quad.page == page) {
+				let start = vertices.len() as u32;
+				vertices.extend(quad.vertices);
+				indices.extend(Sprite::INDICES.map(|index| start + index));
+			}
+			if indices.is_empty() {
+				continue;
+			}
+
+			self.texture(Some(&texture));
+			self.geometry(&vertices, &indices)?;
+			vertices.clear();
+			indices.clear();
+		}
+
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+	font: u64,
+	glyph: u16,
+	/// Bits of the pixel size
+	size: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedGlyph {
+	/// Page and `(x, y, width, height)` region in pixels. `None` for glyphs without an outline, like spaces
+	region: Option<(usize, [u32; 4])>,
+	/// Offset of the bottom left corner from the pen position on the baseline, `y` pointing up
+	offset: Vec2,
+}
+
+#[derive(Debug)]
+struct CachePage {
+	texture: Option<TextureId>,
+	/// Size of the GPU texture, which lags behind `size` until the next flush
+	texture_size: (u32, u32),
+	size: (u32, u32),
+	/// RGBA8 copy of the texture
+	pixels: Vec<u8>,
+	/// `(y, height, used width)` of every shelf
+	shelves: Vec<(u32, u32, u32)>,
+	/// Region changed since the last flush, as `(x0, y0, x1, y1)`
+	dirty: Option<(u32, u32, u32, u32)>,
+	last_used: u64,
+}
+
+impl CachePage {
+	fn new(size: u32) -> Self {
+		Self {
+			texture: None,
+			texture_size: (0, 0),
+			size: (size, size),
+			pixels: vec![0; (size * size * 4) as usize],
+			shelves: Vec::new(),
+			dirty: None,
+			last_used: 0,
+		}
+	}
+
+	fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+		for (y, shelf_height, used) in self.shelves.iter_mut() {
+			if height <= *shelf_height && *used + width <= self.size.0 {
+				let x = *used;
+				*used += width;
+				return Some((x, *y));
+			}
+		}
+
+		let y = self.shelves.last().map(|(y, height, _)| y + height).unwrap_or(0);
+		if width > self.size.0 || y + height > self.size.1 {
+			return None;
+		}
+		self.shelves.push((y, height, width));
+		Some((0, y))
+	}
+
+	/// Doubles the shorter side, up to `max_size`. Returns `false` if the page is already at the max size
+	fn grow(&mut self, max_size: u32) -> bool {
+		let (width, height) = self.size;
+		let size = match width <= height {
+			true if width < max_size => ((width * 2).min(max_size), height),
+			_ if height < max_size => (width, (height * 2).min(max_size)),
+			_ if width < max_size => ((width * 2).min(max_size), height),
+			_ => return false,
+		};
+
+		let mut pixels = vec![0; (size.0 * size.1 * 4) as usize];
+		for row in 0..height as usize {
+			let (from, to) = (row * width as usize * 4, row * size.0 as usize * 4);
+			pixels[to..to + width as usize * 4].copy_from_slice(&self.pixels[from..from + width as usize * 4]);
+		}
+
+		self.size = size;
+		self.pixels = pixels;
+		true
+	}
+
+	fn clear(&mut self) {
+		self.pixels.fill(0);
+		self.shelves.clear();
+		self.dirty = Some((0, 0, self.size.0, self.size.1));
+	}
+
+	fn write(&mut self, x: u32, y: u32, width: u32, coverage: &[u8]) {
+		let height = coverage.len() as u32 / width.max(1);
+		for (i, alpha) in coverage.iter().enumerate() {
+			let (px, py) = (x + i as u32 % width, y + i as u32 / width);
+			let index = ((py * self.size.0 + px) * 4) as usize;
+			self.pixels[index..index + 4].copy_from_slice(&[255, 255, 255, *alpha]);
+		}
+
+		let (x0, y0, x1, y1) = self.dirty.unwrap_or((x, y, x + width, y + height));
+		self.dirty = Some((x0.min(x), y0.min(y), x1.max(x + width), y1.max(y + height)));
+	}
+}
+
+/// Rasterized glyphs of [`TtfFont`]s, packed into page textures that grow up to a max size.
+///
+/// When all pages are full, the least recently used page is cleared for the new glyphs. Pages used by the text being laid out are never cleared,
+/// and glyphs that don't fit anymore are skipped.
+#[derive(Debug, Resource)]
+pub struct GlyphCache {
+	pages: Vec<CachePage>,
+	glyphs: HashMap<GlyphKey, CachedGlyph>,
+	initial_size: u32,
+	max_size: u32,
+	max_pages: usize,
+	tick: u64,
+}
+
+impl Default for GlyphCache {
+	fn default() -> Self {
+		Self::new(256, 1024, 4)
+	}
+}
+
+impl GlyphCache {
+	/// Pages start at `initial_size` pixels squared and grow up to `max_size`, and there are at most `max_pages` of them
+	pub fn new(initial_size: u32, max_size: u32, max_pages: usize) -> Self {
+		Self {
+			pages: Vec::new(),
+			glyphs: HashMap::new(),
+			initial_size: initial_size.min(max_size),
+			max_size,
+			max_pages: max_pages.max(1),
+			tick: 0,
+		}
+	}
+
+	/// Number of cached glyphs, including the ones without an outline
+	pub fn len(&self) -> usize {
+		self.glyphs.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.glyphs.is_empty()
+	}
+
+	pub fn page_count(&self) -> usize {
+		self.pages.len()
+	}
+
+	/// Size of the page in pixels, which can be ahead of its texture until the next [`flush`](GlyphCache::flush)
+	pub fn page_size(&self, page: usize) -> Option<(u32, u32)> {
+		self.pages.get(page).map(|page| page.size)
+	}
+
+	/// Texture of the page, created on the first [`flush`](GlyphCache::flush) after the page
+	pub fn page_texture(&self, page: usize) -> Option<TextureId> {
+		self.pages.get(page).and_then(|page| page.texture)
+	}
+
+	/// Quads of the text glyphs at `size` pixels, where `position` is the top left corner of the first line. Missing glyphs are rasterized into the cache,
+	/// which has to be [flushed](GlyphCache::flush) before drawing the quads
+	pub fn glyph_quads(&mut self, font: &TtfFont, text: &str, position: Vec2, size: f32, color: Rgba) -> Vec<GlyphQuad> {
+		let line_height = font.line_height(size);
+		let mut pen = vec2(position.x, position.y - font.ascent(size));
+		let mut previous = None;
+		let mut glyphs = Vec::with_capacity(text.len());
+
+		for c in text.chars() {
+			if c == '\n' {
+				pen = vec2(position.x, pen.y - line_height);
+				previous = None;
+				continue;
+			}
+
+			if let Some(previous) = previous {
+				pen.x += font.kerning(previous, c, size);
+			}
+			previous = Some(c);
+			glyphs.push((font, c, size, pen, color));
+			pen.x += font.advance(c, size);
+		}
+
+		self.rasterize(glyphs)
+	}
+
+	/// Quads of glyphs given as `(font, character, size, pen position on the baseline, color)`, rasterizing the missing ones.
+	/// Pages used by any of the glyphs aren't evicted for the others
+	pub(crate) fn rasterize<'a>(&mut self, glyphs: impl IntoIterator<Item = (&'a TtfFont, char, f32, Vec2, Rgba)>) -> Vec<GlyphQuad> {
+		self.tick += 1;
+		let mut placed = Vec::new();
+
+		for (font, c, size, pen, color) in glyphs {
+			let glyph = match self.glyph(font, font.font().lookup_glyph_index(c), size) {
+				Some(glyph) => glyph,
+				None => {
+					#[cfg(feature = "log")]
+					bevy_log::warn!("Glyph {:?} doesn't fit into the glyph cache", c);
+					continue;
+				}
+			};
+			if let Some((page, rect)) = glyph.region {
+				self.pages[page].last_used = self.tick;
+				placed.push((page, rect, pen + glyph.offset, color));
+			}
+		}
+
+		// UVs are computed last, since pages could grow while the glyphs were added
+		placed
+			.into_iter()
+			.map(|(page, [x, y, w, h], bottom_left, color)| {
+				let (page_width, page_height) = self.pages[page].size;
+				let uv = Vec4::new(x as f32 / page_width as f32, y as f32 / page_height as f32, w as f32 / page_width as f32, h as f32 / page_height as f32);
+				let corner = |dx: f32, dy: f32, u: f32, v: f32| Vertex::new(vec3(bottom_left.x + dx, bottom_left.y + dy, 0.0), vec2(u, v), color);
+				let (w, h) = (w as f32, h as f32);
+
+				GlyphQuad {
+					page,
+					vertices: [
+						corner(0.0, h, uv.x, uv.y),
+						corner(w, h, uv.x + uv.z, uv.y),
+						corner(0.0, 0.0, uv.x, uv.y + uv.w),
+						corner(w, 0.0, uv.x + uv.z, uv.y + uv.w),
+					],
+				}
+			})
+			.collect()
+	}
+
+	/// Creates, resizes and updates the page textures changed since the last flush
+	pub fn flush(&mut self, backend: &mut RenderingBackend) {
+		for page in self.pages.iter_mut() {
+			match page.texture {
+				None => {
+					page.texture = Some(backend.new_texture_from_rgba8(page.size.0 as u16, page.size.1 as u16, &page.pixels));
+					page.texture_size = page.size;
+				}
+				Some(texture) if page.texture_size != page.size => {
+					backend.texture_resize(texture, page.size.0, page.size.1, Some(&page.pixels));
+					page.texture_size = page.size;
+				}
+				Some(texture) => {
+					if let Some((x0, y0, x1, y1)) = page.dirty {
+						let row = page.size.0 as usize * 4;
+						let bytes: Vec<u8> = (y0 as usize..y1 as usize).flat_map(|y| &page.pixels[y * row + x0 as usize * 4..y * row + x1 as usize * 4]).copied().collect();
+						backend.texture_update_part(texture, x0 as i32, y0 as i32, (x1 - x0) as i32, (y1 - y0) as i32, &bytes);
+					}
+				}
+			}
+			page.dirty = None;
+		}
+	}
+
+	/// Removes all the glyphs, keeping the page textures
+	pub fn clear(&mut self) {
+		self.glyphs.clear();
+		for page in self.pages.iter_mut() {
+			page.clear();
+		}
+	}
+
+	fn glyph(&mut self, font: &TtfFont, index: u16, size: f32) -> Option<CachedGlyph> {
+		let key = GlyphKey { font: font.id(), glyph: index, size: size.to_bits() };
+		if let Some(glyph) = self.glyphs.get(&key) {
+			return Some(*glyph);
+		}
+
+		let (metrics, coverage) = font.font().rasterize_indexed(index, size);
+		let mut glyph = CachedGlyph {
+			region: None,
+			offset: vec2(metrics.xmin as f32, metrics.ymin as f32),
+		};
+
+		if metrics.width > 0 && metrics.height > 0 {
+			let (width, height) = (metrics.width as u32, metrics.height as u32);
+			let (page, x, y) = self.allocate(width + GLYPH_PADDING, height + GLYPH_PADDING)?;
+			self.pages[page].write(x, y, width, &coverage);
+			glyph.region = Some((page, [x, y, width, height]));
+		}
+
+		self.glyphs.insert(key, glyph);
+		Some(glyph)
+	}
+
+	fn allocate(&mut self, width: u32, height: u32) -> Option<(usize, u32, u32)> {
+		if width > self.max_size || height > self.max_size {
+			return None;
+		}
+
+		for (index, page) in self.pages.iter_mut().enumerate() {
+			if let Some((x, y)) = page.allocate(width, height) {
+				return Some((index, x, y));
+			}
+		}
+
+		if let Some(page) = self.pages.last_mut() {
+			while page.grow(self.max_size) {
+				if let Some((x, y)) = page.allocate(width, height) {
+					return Some((self.pages.len() - 1, x, y));
+				}
+			}
+		}
+
+		if self.pages.len() < self.max_pages {
+			let mut page = CachePage::new(self.initial_size);
+			let (x, y) = loop {
+				match page.allocate(width, height) {
+					Some(position) => break position,
+					None if page.grow(self.max_size) => continue,
+					None => return None,
+				}
+			};
+			self.pages.push(page);
+			return Some((self.pages.len() - 1, x, y));
+		}
+
+		// Evict the least recently used page, unless the current text uses it
+		let tick = self.tick;
+		let (index, _) = self.pages.iter().enumerate().filter(|(_, page)| page.last_used != tick).min_by_key(|(_, page)| page.last_used)?;
+		self.glyphs.retain(|_, glyph| !matches!(glyph.region, Some((page, _)) if page == index));
+		let page = &mut self.pages[index];
+		page.clear();
+		page.allocate(width, height).map(|(x, y)| (index, x, y))
+	}
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use bevy_app::{App, Plugin, PostUpdate};
+use bevy_ecs::{prelude::*, system::EntityCommands};
+use bevy_reflect::Reflect;
+use glam::{Mat4, Quat, Vec3};
+
+/// Position, rotation and scale of an entity, relative to its [`Parent`] if it has one.
+///
+/// Entities with a [`Transform`] automatically get a [`GlobalTransform`], which is updated in [`PostUpdate`].
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect)]
+pub struct Transform {
+	pub translation: Vec3,
+	pub rotation: Quat,
+	pub scale: Vec3,
+}
+
+impl Default for Transform {
+	fn default() -> Self {
+		Self::IDENTITY
+	}
+}
+
+impl Transform {
+	pub const IDENTITY: Self = Self {
+		translation: Vec3::ZERO,
+		rotation: Quat::IDENTITY,
+		scale: Vec3::ONE,
+	};
+
+	pub fn from_translation(translation: Vec3) -> Self {
+		Self { translation, ..Self::IDENTITY }
+	}
+
+	pub fn from_xyz(x: f32, y: f32, z: f32) -> Self {
+		Self::from_translation(Vec3::new(x, y, z))
+	}
+
+	pub fn with_rotation(mut self, rotation: Quat) -> Self {
+		self.rotation = rotation;
+		self
+	}
+
+	pub fn with_scale(mut self, scale: Vec3) -> Self {
+		self.scale = scale;
+		self
+	}
+
+	/// Model matrix of the transform, applying scale, rotation and translation in that order
+	pub fn compute_matrix(&self) -> Mat4 {
+		Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+	}
+}
+
+/// World space transform of an entity, computed from its [`Transform`] and the ones of its ancestors in [`PostUpdate`].
+/// It's what rendering uses, so don't modify it directly.
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect)]
+pub struct GlobalTransform(Mat4);
+
+impl Default for GlobalTransform {
+	fn default() -> Self {
+		Self::IDENTITY
+	}
+}
+
+impl From<Transform> for GlobalTransform {
+	fn from(transform: Transform) -> Self {
+		Self(transform.compute_matrix())
+	}
+}
+
+impl GlobalTransform {
+	pub const IDENTITY: Self = Self(Mat4::IDENTITY);
+
+	pub fn compute_matrix(&self) -> Mat4 {
+		self.0
+	}
+
+	pub fn translation(&self) -> Vec3 {
+		self.0.w_axis.truncate()
+	}
+
+	/// Applies the local transform of a child
+	pub fn mul_transform(&self, transform: Transform) -> Self {
+		Self(self.0 * transform.compute_matrix())
+	}
+
+	/// Splits the matrix back into a [`Transform`]
+	pub fn compute_transform(&self) -> Transform {
+		let (scale, rotation, translation) = self.0.to_scale_rotation_translation();
+		Transform { translation, rotation, scale }
+	}
+}
+
+/// Makes the [`Transform`] of the entity relative to the [`GlobalTransform`] of the parent entity. The parent's [`Children`] are
+/// updated to match in [`PostUpdate`], see [`HierarchyCommands`] to build hierarchies from systems.
+///
+/// If the parent is despawned or doesn't have a [`Transform`], the entity is positioned as if it didn't have a parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct Parent(Entity);
+
+impl Parent {
+	pub fn new(parent: Entity) -> Self {
+		Self(parent)
+	}
+
+	pub fn get(&self) -> Entity {
+		self.0
+	}
+}
+
+/// Entities whose [`Parent`] is this entity. It's maintained from the [`Parent`] components, so don't modify it directly.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Component)]
+pub struct Children(Vec<Entity>);
+
+impl std::ops::Deref for Children {
+	type Target = [Entity];
+
+	fn deref(&self) -> &[Entity] {
+		&self.0
+	}
+}
+
+/// Spawns children of an entity, see [`HierarchyCommands::with_children`]
+pub struct ChildSpawner<'a> {
+	commands: Commands<'a, 'a>,
+	parent: Entity,
+}
+
+impl ChildSpawner<'_> {
+	/// Spawns an entity with the bundle and a [`Parent`]
+	pub fn spawn(&mut self, bundle: impl Bundle) -> EntityCommands<'_> {
+		self.commands.spawn((bundle, Parent(self.parent)))
+	}
+
+	pub fn parent(&self) -> Entity {
+		self.parent
+	}
+}
+
+/// Hierarchy helpers for [`EntityCommands`]
+pub trait HierarchyCommands {
+	/// Makes the entity a child of `parent`
+	fn set_parent(&mut self, parent: Entity) -> &mut Self;
+
+	/// Makes the entity a root again, its [`Transform`] is kept as is
+	fn remove_parent(&mut self) -> &mut Self;
+
+	/// Spawns children of the entity
+	fn with_children(&mut self, spawn: impl FnOnce(&mut ChildSpawner)) -> &mut Self;
+
+	/// Despawns the entity along with all of its descendants
+	fn despawn_recursive(&mut self);
+}
+
+impl HierarchyCommands for EntityCommands<'_> {
+	fn set_parent(&mut self, parent: Entity) -> &mut Self {
+		self.insert(Parent(parent))
+	}
+
+	fn remove_parent(&mut self) -> &mut Self {
+		self.remove::<Parent>()
+	}
+
+	fn with_children(&mut self, spawn: impl FnOnce(&mut ChildSpawner)) -> &mut Self {
+		let parent = self.id();
+		spawn(&mut ChildSpawner { commands: self.commands(), parent });
+		self
+	}
+
+	fn despawn_recursive(&mut self) {
+		self.add(despawn_with_children);
+	}
+}
+
+fn despawn_with_children(entity: Entity, world: &mut World) {
+	let Some(mut entity) = world.get_entity_mut(entity) else {
+		return;
+	};
+
+	// Taking the children out first also stops at parent cycles
+	let children = entity.take::<Children>();
+	entity.despawn();
+	for child in children.iter().flat_map(|children| children.iter()) {
+		despawn_with_children(*child, world);
+	}
+}
+
+/// Systems updating the [`Children`] and [`GlobalTransform`]s, in [`PostUpdate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub struct TransformPropagate;
+
+/// Adds [`GlobalTransform`] propagation
+pub struct TransformPlugin;
+
+impl Plugin for TransformPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(
+			PostUpdate,
+			(insert_global_transforms, sync_children, apply_deferred, propagate_transforms).chain().in_set(TransformPropagate),
+		);
+	}
+}
+
+fn insert_global_transforms(mut commands: Commands, transforms: Query<(Entity, &Transform), Without<GlobalTransform>>) {
+	for (entity, transform) in transforms.iter() {
+		commands.entity(entity).insert(GlobalTransform::from(*transform));
+	}
+}
+
+type ParentChanges<'w, 's> = ParamSet<'w, 's, (Query<'static, 'static, (Entity, &'static Parent), Changed<Parent>>, Query<'static, 'static, &'static mut Parent>)>;
+
+fn sync_children(
+	mut commands: Commands,
+	mut parents: ParentChanges,
+	mut removed_parents: RemovedComponents<Parent>,
+	mut removed_children: RemovedComponents<Children>,
+	mut children: Query<(Entity, &mut Children)>,
+) {
+	// The parent was despawned, mark its children as moved so they get positioned as roots
+	let orphaned: Vec<Entity> = removed_children.read().filter(|entity| !children.contains(*entity)).collect();
+	if !orphaned.is_empty() {
+		for mut parent in parents.p1().iter_mut().filter(|parent| orphaned.contains(&parent.get())) {
+			parent.set_changed();
+		}
+	}
+
+	let moved: Vec<(Entity, Entity)> = parents.p0().iter().map(|(entity, parent)| (entity, parent.get())).collect();
+	let removed: Vec<Entity> = removed_parents.read().collect();
+	if moved.is_empty() && removed.is_empty() {
+		return;
+	}
+
+	// Take the entities out of their previous parent, then add them to the new one
+	let left = |child: &Entity| removed.contains(child) || moved.iter().any(|(entity, _)| entity == child);
+	for (_, mut siblings) in children.iter_mut() {
+		if siblings.iter().any(left) {
+			siblings.0.retain(|child| !left(child));
+		}
+	}
+
+	let mut new_children: HashMap<Entity, Vec<Entity>> = HashMap::new();
+	for (child, parent) in moved {
+		match children.get_mut(parent) {
+			Ok((_, mut siblings)) => siblings.0.push(child),
+			Err(_) => new_children.entry(parent).or_default().push(child),
+		}
+	}
+
+	for (parent, siblings) in children.iter() {
+		if siblings.is_empty() {
+			commands.entity(parent).remove::<Children>();
+		}
+	}
+
+	for (parent, siblings) in new_children {
+		if let Some(mut parent) = commands.get_entity(parent) {
+			parent.insert(Children(siblings));
+		}
+	}
+}
+
+type Moved<'w, 's> = Query<'w, 's, (Entity, Option<&'static Parent>), (With<Transform>, Or<(Changed<Transform>, Changed<Parent>)>)>;
+
+/// Recomputes the [`GlobalTransform`]s of the entities that moved or whose [`Parent`] changed, along with their descendants.
+/// Entities that are moved along with an ancestor are only visited from that ancestor.
+fn propagate_transforms(
+	moved: Moved,
+	mut removed_parents: RemovedComponents<Parent>,
+	mut transforms: Query<(&Transform, &mut GlobalTransform, Option<&Children>)>,
+	mut stack: Local<Vec<(Entity, GlobalTransform, usize)>>,
+) {
+	let roots = removed_parents.read().filter_map(|entity| moved.get(entity).err().map(|_| (entity, None)));
+	let starts: Vec<(Entity, Option<Entity>)> = moved
+		.iter()
+		.filter(|(_, parent)| !parent.is_some_and(|parent| moved.contains(parent.get())))
+		.map(|(entity, parent)| (entity, parent.map(Parent::get)))
+		.chain(roots)
+		.collect();
+	let max_depth = transforms.iter().len();
+
+	for (entity, parent) in starts {
+		// The parent isn't positioned
+		let base = parent.and_then(|parent| transforms.get(parent).ok()).map_or(GlobalTransform::IDENTITY, |(_, global, _)| *global);
+		stack.push((entity, base, 0));
+
+		while let Some((entity, base, depth)) = stack.pop() {
+			let Ok((transform, mut global, children)) = transforms.get_mut(entity) else {
+				continue;
+			};
+			let new_global = base.mul_transform(*transform);
+			global.set_if_neq(new_global);
+
+			match children {
+				// A parent cycle
+				Some(_) if depth > max_depth => {
+					#[cfg(feature = "log")]
+					bevy_log::warn!("Entity {:?} is its own ancestor", entity);
+				}
+				Some(children) => stack.extend(children.iter().map(|child| (*child, new_global, depth + 1))),
+				None => {}
+			}
+		}
+	}
+}
@@ -0,0 +1,105 @@
+use bevy_app::{App, AppExit, Plugin, PluginsState};
+use bevy_ecs::event::{Events, ManualEventReader};
+use miniquad::conf::Conf;
+
+use super::{events, state};
+use crate::render::recording::{RecordedCalls, RecordingBackend};
+use crate::render::RenderingBackend;
+
+/// Replacement for the [`WindowPlugin`](super::WindowPlugin) that runs the app without a window or a GL context, useful for tests.
+/// Only available with the `testing` feature.
+/// Swap it in with `QuadifyPlugins.build().disable::<WindowPlugin>().add(HeadlessPlugin::default())`.
+///
+/// It inserts a [`RenderingBackend`] on top of a [`RecordingBackend`] and a [`WindowProperties`](events::WindowProperties) of the configured size.
+/// The [`RecordedCalls`] of the last frame are available as a non-send resource.
+///
+/// Frames can be stepped on demand with [`HeadlessApp::run_frame`], or in a loop with [`App::run`].
+pub struct HeadlessPlugin {
+	pub width: u32,
+	pub height: u32,
+	/// Amount of frames to run before [`App::run`] returns. `None` runs until an [`AppExit`] event is sent.
+	pub frames: Option<u32>,
+}
+
+impl Default for HeadlessPlugin {
+	fn default() -> Self {
+		let conf = Conf::default();
+
+		Self {
+			width: conf.window_width,
+			height: conf.window_height,
+			frames: None,
+		}
+	}
+}
+
+impl Plugin for HeadlessPlugin {
+	fn build(&self, app: &mut App) {
+		let window_entity = app.world.spawn(()).id();
+		let window_properties = events::WindowProperties {
+			window: window_entity,
+
+			position: None,
+			width: self.width,
+			height: self.height,
+			fullscreen: false,
+
+			cursor_grabbed: false,
+			cursor: miniquad::CursorIcon::Default,
+			cursor_position: glam::Vec2::ZERO,
+		};
+
+		super::init_window(app, window_properties);
+
+		let recording = RecordingBackend::new();
+		let calls = recording.calls();
+		let mut backend = RenderingBackend::from_backend(Box::new(recording));
+		backend.set_screen_size(Some((self.width as f32, self.height as f32)));
+		app.insert_non_send_resource(backend).insert_non_send_resource(calls);
+
+		let frames = self.frames;
+		app.set_runner(move |mut app| {
+			let mut app_exit = ManualEventReader::<AppExit>::default();
+			let mut frame = 0;
+
+			loop {
+				if frames.is_some_and(|frames| frame >= frames) {
+					break;
+				}
+
+				app.run_frame();
+				frame += 1;
+
+				if let Some(events) = app.world.get_resource::<Events<AppExit>>() {
+					if app_exit.read(events).last().is_some() {
+						break;
+					}
+				}
+			}
+		});
+	}
+}
+
+/// Manual frame stepping for apps built with the [`HeadlessPlugin`]
+pub trait HeadlessApp {
+	/// Runs a single frame the same way the windowed event loop does: [`App::update`] followed by the draw schedules.
+	fn run_frame(&mut self);
+}
+
+impl HeadlessApp for App {
+	fn run_frame(&mut self) {
+		if self.plugins_state() == PluginsState::Ready {
+			self.finish();
+			self.cleanup();
+		}
+
+		if let Some(calls) = self.world.get_non_send_resource::<RecordedCalls>() {
+			calls.clear();
+		}
+
+		self.update();
+		self.world.run_schedule(state::MiniquadPrepareDraw);
+		self.world.run_schedule(state::MiniquadDraw);
+		self.world.run_schedule(state::MiniquadEndDraw);
+	}
+}
@@ -0,0 +1,158 @@
+use std::path::PathBuf;
+
+use bevy_ecs::{change_detection::DetectChangesMut, system::Resource, world::World};
+use bevy_input::{keyboard::NativeKeyCode, mouse::MouseScrollUnit, prelude::*, ButtonState};
+use glam::{vec2, Vec2};
+
+use super::conversions::{mq_to_bevy_char, mq_to_bevy_keycode, mq_to_bevy_logical_key, mq_to_bevy_mbtn, mq_to_bevy_tch};
+use super::events;
+use super::state::{MiniquadKeyDownSchedule, MiniquadMouseDownSchedule, MiniquadMouseMotionSchedule};
+
+/// Last known mouse position, used to compute mouse motion deltas
+#[derive(Debug, Default, Resource)]
+struct PreviousMousePosition(Option<Vec2>);
+
+/// Feeds `miniquad` input into the app. It's what the window's event handler uses internally, so injected input
+/// goes through the same conversions and runs the same input schedules ([`MiniquadKeyDownSchedule`], [`MiniquadMouseDownSchedule`]
+/// and [`MiniquadMouseMotionSchedule`]).
+///
+/// Useful for testing input handling with the `HeadlessPlugin` of the `testing` feature.
+pub struct InputInjector<'w> {
+	world: &'w mut World,
+}
+
+impl<'w> InputInjector<'w> {
+	pub fn new(world: &'w mut World) -> Self {
+		Self { world }
+	}
+
+	fn window(&self) -> bevy_ecs::entity::Entity {
+		self.world.resource::<events::WindowProperties>().window()
+	}
+
+	/// Same as the `key_down_event` of the window
+	pub fn key_down(&mut self, keycode: miniquad::KeyCode) -> &mut Self {
+		let window = self.window();
+		self.world.send_event(bevy_input::keyboard::KeyboardInput {
+			key_code: mq_to_bevy_keycode(keycode),
+			state: ButtonState::Pressed,
+			logical_key: mq_to_bevy_logical_key(keycode),
+			window,
+		});
+
+		self.world.run_schedule(MiniquadKeyDownSchedule);
+		self
+	}
+
+	/// Same as the `key_up_event` of the window
+	pub fn key_up(&mut self, keycode: miniquad::KeyCode) -> &mut Self {
+		let window = self.window();
+		self.world.send_event(bevy_input::keyboard::KeyboardInput {
+			key_code: mq_to_bevy_keycode(keycode),
+			state: ButtonState::Released,
+			logical_key: mq_to_bevy_logical_key(keycode),
+			window,
+		});
+		self
+	}
+
+	/// Presses and releases the key
+	pub fn press_key(&mut self, keycode: miniquad::KeyCode) -> &mut Self {
+		self.key_down(keycode).key_up(keycode)
+	}
+
+	/// Same as the `char_event` of the window
+	pub fn char_input(&mut self, character: char) -> &mut Self {
+		let window = self.window();
+		self.world.send_event(bevy_input::keyboard::KeyboardInput {
+			key_code: KeyCode::Unidentified(NativeKeyCode::Unidentified),
+			state: ButtonState::Pressed, // ! Could be another bug, since the char state would always be `ButtonState::Pressed`
+			logical_key: mq_to_bevy_char(character),
+			window,
+		});
+		self
+	}
+
+	/// Same as the `mouse_button_down_event` of the window
+	pub fn mouse_button_down(&mut self, button: miniquad::MouseButton) -> &mut Self {
+		let window = self.window();
+		self.world.send_event(bevy_input::mouse::MouseButtonInput {
+			button: mq_to_bevy_mbtn(button),
+			state: ButtonState::Pressed,
+			window,
+		});
+
+		self.world.run_schedule(MiniquadMouseDownSchedule);
+		self
+	}
+
+	/// Same as the `mouse_button_up_event` of the window
+	pub fn mouse_button_up(&mut self, button: miniquad::MouseButton) -> &mut Self {
+		let window = self.window();
+		self.world.send_event(bevy_input::mouse::MouseButtonInput {
+			button: mq_to_bevy_mbtn(button),
+			state: ButtonState::Released,
+			window,
+		});
+
+		self.world.run_schedule(MiniquadMouseDownSchedule);
+		self
+	}
+
+	/// Presses and releases the mouse button
+	pub fn click(&mut self, button: miniquad::MouseButton) -> &mut Self {
+		self.mouse_button_down(button).mouse_button_up(button)
+	}
+
+	/// Same as the `mouse_motion_event` of the window. `x` and `y` are the absolute mouse position, not the delta
+	pub fn move_mouse(&mut self, x: f32, y: f32) -> &mut Self {
+		let current = vec2(x, y);
+		let mut previous_position = self.world.get_resource_or_insert_with(PreviousMousePosition::default);
+		let previous = *previous_position.0.get_or_insert(current);
+		previous_position.0 = Some(current);
+
+		// only send mouse motion events if the mouse has moved
+		if current != previous {
+			let delta = current - previous;
+			self.world.send_event(bevy_input::mouse::MouseMotion { delta });
+		}
+
+		if let Some(mut props) = self.world.get_resource_mut::<events::WindowProperties>() {
+			props.bypass_change_detection().cursor_position = current;
+		}
+
+		self.world.run_schedule(MiniquadMouseMotionSchedule);
+		self
+	}
+
+	/// Same as the `mouse_wheel_event` of the window
+	pub fn mouse_wheel(&mut self, x: f32, y: f32) -> &mut Self {
+		let window = self.window();
+		self.world.send_event(bevy_input::mouse::MouseWheel {
+			unit: MouseScrollUnit::Pixel,
+			x,
+			y,
+			window,
+		});
+		self
+	}
+
+	/// Same as the `touch_event` of the window
+	pub fn touch(&mut self, phase: miniquad::TouchPhase, id: u64, x: f32, y: f32) -> &mut Self {
+		let window = self.window();
+		self.world.send_event(bevy_input::touch::TouchInput {
+			phase: mq_to_bevy_tch(phase),
+			position: Vec2 { x, y },
+			id,
+			force: None,
+			window,
+		});
+		self
+	}
+
+	/// Same as a single file of the `files_dropped_event` of the window
+	pub fn drop_file(&mut self, path: Option<PathBuf>, bytes: Option<Vec<u8>>) -> &mut Self {
+		self.world.send_event(events::DroppedFileEvent { path, bytes });
+		self
+	}
+}
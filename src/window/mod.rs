@@ -4,7 +4,10 @@ use miniquad::conf::{Conf, PlatformSettings};
 
 mod conversions;
 pub(crate) mod events;
+#[cfg(feature = "testing")]
+pub(crate) mod headless;
 pub(crate) mod icon;
+pub(crate) mod input;
 pub(crate) mod state;
 
 /// Initializes main window and starts the `miniquad` event loop.
@@ -74,44 +77,48 @@ impl Plugin for WindowPlugin {
 			cursor_position: glam::Vec2::ZERO,
 		};
 
-		// Init Resources, Events, and Systems
-		app.add_event::<events::WindowEvent>()
-			.add_event::<events::DroppedFileEvent>()
-			.insert_resource(window_properties)
-			.insert_resource(state::AcceptQuitRequest(true))
-			.init_schedule(state::MiniquadPrepareDraw)
-			.edit_schedule(state::MiniquadPrepareDraw, |s| {
-				s.set_executor_kind(ExecutorKind::SingleThreaded);
-			})
-			.init_schedule(state::MiniquadDraw)
-			.edit_schedule(state::MiniquadDraw, |s| {
-				s.set_executor_kind(ExecutorKind::SingleThreaded);
-			})
-			.init_schedule(state::MiniquadEndDraw)
-			.edit_schedule(state::MiniquadEndDraw, |s| {
-				s.set_executor_kind(ExecutorKind::SingleThreaded);
-			})
-			.init_schedule(state::MiniquadKeyDownSchedule)
-			.edit_schedule(state::MiniquadKeyDownSchedule, |s| {
-				s.set_executor_kind(ExecutorKind::SingleThreaded);
-			})
-			.init_schedule(state::MiniquadMouseDownSchedule)
-			.edit_schedule(state::MiniquadMouseDownSchedule, |s| {
-				s.set_executor_kind(ExecutorKind::SingleThreaded);
-			})
-			.init_schedule(state::MiniquadMouseMotionSchedule)
-			.edit_schedule(state::MiniquadMouseMotionSchedule, |s| {
-				s.set_executor_kind(ExecutorKind::SingleThreaded);
-			})
-			.init_schedule(state::MiniquadQuitRequestedSchedule)
-			.edit_schedule(state::MiniquadQuitRequestedSchedule, |s| {
-				s.set_executor_kind(ExecutorKind::SingleThreaded);
-			})
-			.add_systems(Last, (events::apply_window_properties, events::quit_on_app_exit));
+		init_window(app, window_properties);
+		app.add_systems(Last, (events::apply_window_properties, events::quit_on_app_exit));
 
 		// Init Runner
 		app.set_runner(move |app| {
-			miniquad::start(conf, move || Box::new(state::QuadifyState::new(app, window_entity)));
+			miniquad::start(conf, move || Box::new(state::QuadifyState::new(app)));
 		});
 	}
 }
+
+/// Init Resources, Events and Schedules shared by the [`WindowPlugin`] and the `HeadlessPlugin`
+fn init_window(app: &mut App, window_properties: events::WindowProperties) {
+	app.add_event::<events::WindowEvent>()
+		.add_event::<events::DroppedFileEvent>()
+		.insert_resource(window_properties)
+		.insert_resource(state::AcceptQuitRequest(true))
+		.init_schedule(state::MiniquadPrepareDraw)
+		.edit_schedule(state::MiniquadPrepareDraw, |s| {
+			s.set_executor_kind(ExecutorKind::SingleThreaded);
+		})
+		.init_schedule(state::MiniquadDraw)
+		.edit_schedule(state::MiniquadDraw, |s| {
+			s.set_executor_kind(ExecutorKind::SingleThreaded);
+		})
+		.init_schedule(state::MiniquadEndDraw)
+		.edit_schedule(state::MiniquadEndDraw, |s| {
+			s.set_executor_kind(ExecutorKind::SingleThreaded);
+		})
+		.init_schedule(state::MiniquadKeyDownSchedule)
+		.edit_schedule(state::MiniquadKeyDownSchedule, |s| {
+			s.set_executor_kind(ExecutorKind::SingleThreaded);
+		})
+		.init_schedule(state::MiniquadMouseDownSchedule)
+		.edit_schedule(state::MiniquadMouseDownSchedule, |s| {
+			s.set_executor_kind(ExecutorKind::SingleThreaded);
+		})
+		.init_schedule(state::MiniquadMouseMotionSchedule)
+		.edit_schedule(state::MiniquadMouseMotionSchedule, |s| {
+			s.set_executor_kind(ExecutorKind::SingleThreaded);
+		})
+		.init_schedule(state::MiniquadQuitRequestedSchedule)
+		.edit_schedule(state::MiniquadQuitRequestedSchedule, |s| {
+			s.set_executor_kind(ExecutorKind::SingleThreaded);
+		});
+}
@@ -1,29 +1,25 @@
 use bevy_app::*;
-use bevy_ecs::{change_detection::DetectChangesMut, entity::Entity, schedule::ScheduleLabel, system::Resource};
-use bevy_input::{keyboard::NativeKeyCode, mouse::MouseScrollUnit, prelude::*, ButtonState};
-use glam::{vec2, Vec2};
+use bevy_ecs::{change_detection::DetectChangesMut, schedule::ScheduleLabel, system::Resource};
 use miniquad::{window, EventHandler};
 
-use super::conversions::{mq_to_bevy_char, mq_to_bevy_keycode, mq_to_bevy_logical_key, mq_to_bevy_mbtn, mq_to_bevy_tch};
 use super::events;
+use super::input::InputInjector;
 use crate::render::RenderingBackend;
 
 /// General `miniquad` state handler for the entire app. It stores bevy's [`App`], manages its event loop and so on
 pub(crate) struct QuadifyState {
 	app: App,
-	mouse_position: Option<Vec2>,
-	window_entity: Entity,
 }
 
 impl QuadifyState {
 	/// Creates a new `QuadifyState` object
-	pub(crate) fn new(mut app: App, window_entity: Entity) -> Self {
+	pub(crate) fn new(mut app: App) -> Self {
 		app.insert_non_send_resource(RenderingBackend::new());
-		Self {
-			app,
-			mouse_position: None,
-			window_entity,
-		}
+		Self { app }
+	}
+
+	fn input(&mut self) -> InputInjector<'_> {
+		InputInjector::new(&mut self.app.world)
 	}
 }
 
@@ -108,104 +104,44 @@ impl EventHandler for QuadifyState {
 
 	// File Drag and Drop
 	fn files_dropped_event(&mut self) {
-		self.app
-			.world
-			.send_event_batch((0..window::dropped_file_count()).map(|i| {
-				let path = window::dropped_file_path(i);
-				let bytes = window::dropped_file_bytes(i);
-
-				events::DroppedFileEvent { path, bytes }
-			}))
-			.unwrap();
+		let mut input = self.input();
+		for i in 0..window::dropped_file_count() {
+			input.drop_file(window::dropped_file_path(i), window::dropped_file_bytes(i));
+		}
 	}
 
 	// Mouse Events
 	fn mouse_button_down_event(&mut self, button: miniquad::MouseButton, _x: f32, _y: f32) {
-		self.app.world.send_event(bevy_input::mouse::MouseButtonInput {
-			button: mq_to_bevy_mbtn(button),
-			state: ButtonState::Pressed,
-			window: self.window_entity,
-		});
-
-		self.app.world.run_schedule(MiniquadMouseDownSchedule);
+		self.input().mouse_button_down(button);
 	}
 
 	fn mouse_motion_event(&mut self, x: f32, y: f32) {
-		// x and y are the absolute mouse position, not the delta
-		let current = vec2(x, y);
-		let previous = self.mouse_position.get_or_insert(current);
-
-		// only send mouse motion events if the mouse has moved
-		if current != *previous {
-			let delta = vec2(x, y) - *previous;
-			self.app.world.send_event(bevy_input::mouse::MouseMotion { delta });
-		}
-
-		if let Some(mut props) = self.app.world.get_resource_mut::<events::WindowProperties>() {
-			props.bypass_change_detection().cursor_position = current;
-		}
-
-		self.mouse_position = Some(current);
-		self.app.world.run_schedule(MiniquadMouseMotionSchedule);
+		self.input().move_mouse(x, y);
 	}
 
 	fn mouse_button_up_event(&mut self, button: miniquad::MouseButton, _x: f32, _y: f32) {
-		self.app.world.send_event(bevy_input::mouse::MouseButtonInput {
-			button: mq_to_bevy_mbtn(button),
-			state: ButtonState::Released,
-			window: self.window_entity,
-		});
-
-		self.app.world.run_schedule(MiniquadMouseDownSchedule);
+		self.input().mouse_button_up(button);
 	}
 
 	fn mouse_wheel_event(&mut self, x: f32, y: f32) {
-		self.app.world.send_event(bevy_input::mouse::MouseWheel {
-			unit: MouseScrollUnit::Pixel,
-			x,
-			y,
-			window: self.window_entity,
-		});
+		self.input().mouse_wheel(x, y);
 	}
 
 	// Touch Events
 	fn touch_event(&mut self, phase: miniquad::TouchPhase, id: u64, x: f32, y: f32) {
-		self.app.world.send_event(bevy_input::touch::TouchInput {
-			phase: mq_to_bevy_tch(phase),
-			position: Vec2 { x, y },
-			id,
-			force: None,
-			window: self.window_entity,
-		});
+		self.input().touch(phase, id, x, y);
 	}
 
 	// Keyboard Events
 	fn char_event(&mut self, character: char, _mods: miniquad::KeyMods, _repeat: bool) {
-		self.app.world.send_event(bevy_input::keyboard::KeyboardInput {
-			key_code: KeyCode::Unidentified(NativeKeyCode::Unidentified),
-			state: ButtonState::Pressed, // ! Could be another bug, since the char state would always be `ButtonState::Pressed`
-			logical_key: mq_to_bevy_char(character),
-			window: self.window_entity,
-		});
+		self.input().char_input(character);
 	}
 
 	fn key_down_event(&mut self, keycode: miniquad::KeyCode, _mods: miniquad::KeyMods, _repeat: bool) {
-		self.app.world.send_event(bevy_input::keyboard::KeyboardInput {
-			key_code: mq_to_bevy_keycode(keycode),
-			state: ButtonState::Pressed,
-			logical_key: mq_to_bevy_logical_key(keycode),
-			window: self.window_entity,
-		});
-
-		self.app.world.run_schedule(MiniquadKeyDownSchedule);
+		self.input().key_down(keycode);
 	}
 
 	fn key_up_event(&mut self, keycode: miniquad::KeyCode, _mods: miniquad::KeyMods) {
-		self.app.world.send_event(bevy_input::keyboard::KeyboardInput {
-			key_code: mq_to_bevy_keycode(keycode),
-			state: ButtonState::Released,
-			logical_key: mq_to_bevy_logical_key(keycode),
-			window: self.window_entity,
-		});
+		self.input().key_up(keycode);
 	}
 }
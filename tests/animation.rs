@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use bevy_app::*;
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::prelude::*;
+use bevy_time::{Time, TimeUpdateStrategy, Virtual};
+use quadify::prelude::*;
+
+fn app() -> App {
+	let mut app = App::new();
+	app.add_plugins(QuadifyPlugins.build().disable::<WindowPlugin>().add(HeadlessPlugin {
+		width: 320,
+		height: 240,
+		frames: None,
+	}))
+	.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)));
+	app
+}
+
+fn spawn(app: &mut App, clip: AnimationClip) -> (Entity, Handle<AnimationClip>) {
+	let clip = app.world.resource_mut::<Assets<AnimationClip>>().add(clip);
+	let entity = app.world.spawn((AnimationPlayer::new(clip.clone()), TextureAtlasSprite::new(Handle::default(), 0))).id();
+	(entity, clip)
+}
+
+/// Atlas indices shown over the next `frames` frames, 100ms each, and the events sent meanwhile
+fn play(app: &mut App, entity: Entity, frames: usize) -> (Vec<usize>, Vec<AnimationEvent>) {
+	let mut events = Vec::new();
+	let indices = (0..frames)
+		.map(|_| {
+			app.update();
+			events.extend(app.world.resource_mut::<Events<AnimationEvent>>().drain());
+			app.world.get::<TextureAtlasSprite>(entity).unwrap().index
+		})
+		.collect();
+	(indices, events)
+}
+
+#[test]
+fn loops_frames() {
+	let mut app = app();
+	let clip = AnimationClip::from_indices([4, 5, 6], 0.1, AnimationMode::Loop);
+	let (entity, _) = spawn(&mut app, clip);
+
+	assert_eq!(play(&mut app, entity, 8).0, vec![4, 5, 6, 4, 5, 6, 4, 5]);
+}
+
+#[test]
+fn ping_pongs_frames() {
+	let mut app = app();
+	let (entity, _) = spawn(&mut app, AnimationClip::from_indices([0, 1, 2], 0.1, AnimationMode::PingPong));
+
+	let (indices, events) = play(&mut app, entity, 9);
+	assert_eq!(indices, vec![0, 1, 2, 1, 0, 1, 2, 1, 0]);
+	assert_eq!(events.iter().filter(|event| matches!(event, AnimationEvent::End { .. })).count(), 2);
+}
+
+#[test]
+fn stops_once_clips_on_the_last_frame() {
+	let mut app = app();
+	let clip = AnimationClip::new(AnimationMode::Once).with_frame(3, 0.1).with_frame(7, 0.2);
+	let (entity, _) = spawn(&mut app, clip);
+
+	let (indices, events) = play(&mut app, entity, 6);
+	assert_eq!(indices, vec![3, 7, 7, 7, 7, 7]);
+	assert!(app.world.get::<AnimationPlayer>(entity).unwrap().is_finished());
+	assert_eq!(events, vec![AnimationEvent::End { entity }]);
+}
+
+#[test]
+fn sends_markers_when_frames_are_shown() {
+	let mut app = app();
+	let clip = AnimationClip::from_indices(0..4, 0.1, AnimationMode::Loop).with_marker(0, "start").with_marker(2, "step");
+	let (entity, _) = spawn(&mut app, clip);
+
+	let (_, events) = play(&mut app, entity, 5);
+
+	let marker = |frame: usize, name: &str| AnimationEvent::Marker { entity, frame, name: name.to_string() };
+	assert_eq!(events, vec![marker(0, "start"), marker(2, "step"), AnimationEvent::End { entity }, marker(0, "start")]);
+}
+
+#[test]
+fn respects_speed_pause_and_clip_changes() {
+	let mut app = app();
+	let (entity, _) = spawn(&mut app, AnimationClip::from_indices(0..4, 0.2, AnimationMode::Loop));
+	app.world.get_mut::<AnimationPlayer>(entity).unwrap().speed = 2.0;
+	assert_eq!(play(&mut app, entity, 3).0, vec![0, 1, 2]);
+
+	app.world.get_mut::<AnimationPlayer>(entity).unwrap().paused = true;
+	assert_eq!(play(&mut app, entity, 2).0, vec![2, 2]);
+
+	let other = app.world.resource_mut::<Assets<AnimationClip>>().add(AnimationClip::from_indices([9, 8], 0.1, AnimationMode::Loop));
+	let mut player = app.world.get_mut::<AnimationPlayer>(entity).unwrap();
+	player.paused = false;
+	player.speed = 1.0;
+	player.play(other);
+	assert_eq!(play(&mut app, entity, 3).0, vec![9, 8, 9]);
+}
+
+#[test]
+fn skips_ahead_after_long_frames() {
+	let mut app = app();
+	let clip = AnimationClip::from_indices(0..4, 0.25, AnimationMode::Loop).with_marker(1, "step");
+	let (entity, _) = spawn(&mut app, clip);
+	play(&mut app, entity, 1);
+
+	app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(10)));
+	app.world.resource_mut::<Time<Virtual>>().set_max_delta(Duration::from_secs(60));
+	let (_, events) = play(&mut app, entity, 1);
+	assert_eq!(events.iter().filter(|event| matches!(event, AnimationEvent::End { .. })).count(), 1);
+	assert_eq!(events.iter().filter(|event| matches!(event, AnimationEvent::Marker { .. })).count(), 1);
+}
@@ -0,0 +1,121 @@
+use bevy_asset::{AssetServer, Assets, Handle, LoadState};
+use glam::{vec2, vec4};
+use quadify::asset::{AsepriteDirection, AsepriteError, AsepriteFile, AsepriteSheet, Texture, TextureAtlas};
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+const HASH: &str = r##"{
+	"frames": {
+		"knight 2.png": { "frame": { "x": 16, "y": 0, "w": 16, "h": 24 }, "rotated": false, "trimmed": false, "duration": 200 },
+		"knight 0.png": { "frame": { "x": 0, "y": 0, "w": 16, "h": 24 }, "rotated": false, "trimmed": false, "duration": 100 },
+		"knight 1.png": { "frame": { "x": 0, "y": 24, "w": 16, "h": 24 }, "rotated": false, "trimmed": false, "duration": 150 }
+	},
+	"meta": {
+		"app": "https://www.aseprite.org/",
+		"image": "knight.png",
+		"format": "RGBA8888",
+		"size": { "w": 32, "h": 48 },
+		"frameTags": [
+			{ "name": "idle", "from": 0, "to": 1, "direction": "forward", "color": "#000000ff" },
+			{ "name": "swing", "from": 0, "to": 2, "direction": "pingpong_reverse", "color": "#000000ff" }
+		],
+		"slices": [
+			{ "name": "panel", "color": "#0000ffff", "keys": [
+				{ "frame": 0, "bounds": { "x": 0, "y": 0, "w": 16, "h": 16 }, "center": { "x": 4, "y": 4, "w": 8, "h": 8 } },
+				{ "frame": 2, "bounds": { "x": 1, "y": 1, "w": 14, "h": 14 }, "pivot": { "x": 7, "y": 7 } }
+			]}
+		]
+	}
+}"##;
+
+const ARRAY: &str = r#"{
+	"frames": [
+		{ "filename": "walk 0", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "duration": 50 },
+		{ "filename": "walk 1", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 50 }
+	],
+	"meta": { "image": "walk.png", "size": { "w": 16, "h": 8 }, "frameTags": [{ "name": "walk", "from": 0, "to": 1, "direction": "reverse" }] }
+}"#;
+
+#[test]
+fn parses_hash_sheets_in_export_order() {
+	let file = AsepriteFile::from_slice(HASH.as_bytes()).unwrap();
+	assert_eq!(file.image, "knight.png");
+	assert_eq!(file.size, vec2(32.0, 48.0));
+
+	let names: Vec<_> = file.frames.iter().map(|frame| frame.name.as_str()).collect();
+	assert_eq!(names, vec!["knight 2.png", "knight 0.png", "knight 1.png"]);
+	assert_eq!(file.frames[2].rect, vec4(0.0, 24.0, 16.0, 24.0));
+	assert_eq!(file.frames[0].duration, 0.2);
+
+	let atlas = file.atlas(Handle::default());
+	assert_eq!(atlas.len(), 3);
+	assert_eq!(atlas.index_of("knight 1.png"), Some(2));
+	assert_eq!(atlas.uv_rect(2), Some(vec4(0.0, 0.5, 0.5, 0.5)));
+}
+
+#[test]
+fn parses_array_sheets() {
+	let file = AsepriteFile::from_slice(ARRAY.as_bytes()).unwrap();
+	assert_eq!(file.frames.len(), 2);
+	assert_eq!(file.frames[1].name, "walk 1");
+	assert_eq!(file.frames[1].rect, vec4(8.0, 0.0, 8.0, 8.0));
+	assert!(file.slices.is_empty());
+}
+
+#[test]
+fn converts_tags_to_clips() {
+	let file = AsepriteFile::from_slice(HASH.as_bytes()).unwrap();
+	assert_eq!(file.tags[1].direction, AsepriteDirection::PingPongReverse);
+
+	let idle = file.clip(&file.tags[0]);
+	assert_eq!(idle.mode, AnimationMode::Loop);
+	assert_eq!(idle.frames.iter().map(|frame| (frame.index, frame.duration)).collect::<Vec<_>>(), vec![(0, 0.2), (1, 0.1)]);
+
+	let swing = file.clip(&file.tags[1]);
+	assert_eq!(swing.mode, AnimationMode::PingPong);
+	assert_eq!(swing.frames.iter().map(|frame| frame.index).collect::<Vec<_>>(), vec![2, 1, 0]);
+
+	let file = AsepriteFile::from_slice(ARRAY.as_bytes()).unwrap();
+	assert_eq!(file.clip(&file.tags[0]).frames.iter().map(|frame| frame.index).collect::<Vec<_>>(), vec![1, 0]);
+}
+
+#[test]
+fn parses_nine_patch_slices() {
+	let file = AsepriteFile::from_slice(HASH.as_bytes()).unwrap();
+	let panel = &file.slices[0];
+	assert_eq!(panel.name, "panel");
+
+	let key = panel.key(1).unwrap();
+	assert_eq!(key.bounds, vec4(0.0, 0.0, 16.0, 16.0));
+	assert_eq!(key.center, Some(vec4(4.0, 4.0, 8.0, 8.0)));
+	assert_eq!(key.pivot, None);
+
+	let key = panel.key(2).unwrap();
+	assert_eq!(key.center, None);
+	assert_eq!(key.pivot, Some(vec2(7.0, 7.0)));
+}
+
+#[test]
+fn rejects_tags_out_of_the_frames() {
+	let json = ARRAY.replace(r#""to": 1"#, r#""to": 5"#);
+	assert!(matches!(AsepriteFile::from_slice(json.as_bytes()), Err(AsepriteError::InvalidTag { name }) if name == "walk"));
+	assert!(matches!(AsepriteFile::from_slice(b"{ \"frames\": 1, \"meta\": { \"image\": \"\", \"size\": { \"w\": 1, \"h\": 1 } } }"), Err(AsepriteError::InvalidFrames)));
+}
+
+#[test]
+fn loads_through_the_asset_server() {
+	let mut app = headless_app();
+	let handle: Handle<AsepriteSheet> = app.world.resource::<AssetServer>().load("tests/walk.aseprite.json");
+	for _ in 0..4 {
+		app.run_frame();
+	}
+	assert_eq!(app.world.resource::<AssetServer>().load_state(&handle), LoadState::Loaded);
+
+	// The image next to the sheet is uploaded to the atlas
+	let atlas = app.world.resource::<Assets<AsepriteSheet>>().get(&handle).unwrap().atlas.clone();
+	let atlas = app.world.resource::<Assets<TextureAtlas>>().get(&atlas).unwrap();
+	assert_eq!(atlas.len(), 2);
+	assert!(app.world.resource::<Assets<Texture>>().contains(&atlas.texture));
+}
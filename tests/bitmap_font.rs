@@ -0,0 +1,117 @@
+use bevy_asset::{AssetServer, Assets, Handle, LoadState};
+use glam::{vec2, vec4, Vec2};
+use quadify::asset::{BmFont, Font, Texture};
+use quadify::color::WHITE;
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+const TEXT: &str = r#"info face="Pixel Sans" size=-16 bold=0 italic=0 charset="" unicode=1 stretchH=100 smooth=0 aa=1 padding=0,0,0,0 spacing=1,1
+common lineHeight=18 base=14 scaleW=64 scaleH=32 pages=2 packed=0
+page id=0 file="pixel_0.png"
+page id=1 file="pixel_1.png"
+chars count=3
+char id=32   x=0     y=0     width=0     height=0     xoffset=0     yoffset=0     xadvance=4     page=0  chnl=15
+char id=65   x=0     y=0     width=8     height=10    xoffset=1     yoffset=4     xadvance=9     page=0  chnl=15
+char id=86   x=16    y=16    width=8     height=10    xoffset=0     yoffset=4     xadvance=8     page=1  chnl=15
+kernings count=1
+kerning first=65  second=86  amount=-2
+"#;
+
+const XML: &str = r#"<?xml version="1.0"?>
+<font>
+  <info face="Pixel Sans" size="16" bold="0" italic="0" charset="" unicode="1"/>
+  <common lineHeight="18" base="14" scaleW="64" scaleH="32" pages="1" packed="0"/>
+  <pages>
+    <page id="0" file="pixel_0.png" />
+  </pages>
+  <chars count="1">
+    <char id="65" x="0" y="0" width="8" height="10" xoffset="1" yoffset="4" xadvance="9" page="0" chnl="15" />
+  </chars>
+</font>
+"#;
+
+fn corners(font: &Font, text: &str, scale: f32) -> Vec<(Vec2, Vec2)> {
+	font.glyph_quads(text, vec2(100.0, 50.0), scale, WHITE).iter().map(|quad| (quad.vertices[0].position.truncate(), quad.vertices[3].position.truncate())).collect()
+}
+
+#[test]
+fn parses_text_descriptors() {
+	let font = BmFont::from_slice(TEXT.as_bytes()).unwrap();
+	assert_eq!((font.size, font.line_height, font.base), (16.0, 18.0, 14.0));
+	assert_eq!(font.page_size, vec2(64.0, 32.0));
+	assert_eq!(font.pages, vec!["pixel_0.png".to_string(), "pixel_1.png".to_string()]);
+	assert_eq!(font.glyphs.len(), 3);
+	assert_eq!(font.glyphs[&'V'].page, 1);
+	assert_eq!(font.kerning[&('A', 'V')], -2.0);
+}
+
+#[test]
+fn parses_xml_descriptors() {
+	let font = BmFont::from_slice(XML.as_bytes()).unwrap();
+	assert_eq!(font.pages, vec!["pixel_0.png".to_string()]);
+	assert_eq!(font.glyphs[&'A'].rect, vec4(0.0, 0.0, 8.0, 10.0));
+	assert_eq!(font.glyphs[&'A'].offset, vec2(1.0, 4.0));
+	assert_eq!(font.glyphs[&'A'].advance, 9.0);
+
+	assert!(BmFont::from_slice(b"info size=12\nchar id=65 x=0").is_err());
+}
+
+#[test]
+fn positions_glyphs_with_kerning_and_line_height() {
+	let font = BmFont::from_slice(TEXT.as_bytes()).unwrap().into_font(vec![Handle::default(), Handle::default()]);
+
+	// The space doesn't produce a quad, the missing `?` is skipped
+	assert_eq!(
+		corners(&font, "AV A?\nV", 1.0),
+		vec![
+			(vec2(101.0, 46.0), vec2(109.0, 36.0)),
+			// Kerned by -2 after the advance of 9
+			(vec2(107.0, 46.0), vec2(115.0, 36.0)),
+			(vec2(120.0, 46.0), vec2(128.0, 36.0)),
+			(vec2(100.0, 28.0), vec2(108.0, 18.0)),
+		]
+	);
+	assert_eq!(corners(&font, "AV", 2.0)[1], (vec2(114.0, 42.0), vec2(130.0, 22.0)));
+
+	let quads = font.glyph_quads("V", Vec2::ZERO, 1.0, WHITE);
+	assert_eq!(quads[0].page, 1);
+	assert_eq!(quads[0].vertices[0].uv, vec2(0.25, 0.5));
+	assert_eq!(quads[0].vertices[3].uv, vec2(0.375, 0.8125));
+}
+
+#[test]
+fn draws_text_with_a_call_per_page() {
+	let mut app = headless_app();
+	let pixels = vec![255; 64 * 32 * 4];
+	let pages: Vec<_> = (0..2)
+		.map(|_| {
+			let id = app.world.non_send_resource_mut::<RenderingBackend>().new_texture_from_rgba8(64, 32, &pixels);
+			(app.world.resource_mut::<Assets<Texture>>().add(Texture::new(id)), id)
+		})
+		.collect();
+	let font = BmFont::from_slice(TEXT.as_bytes()).unwrap().into_font(pages.iter().map(|(handle, _)| handle.clone()).collect());
+
+	let world = app.world.cell();
+	let mut backend = world.non_send_resource_mut::<RenderingBackend>();
+	backend.draw_text(&font, &world.resource::<Assets<Texture>>(), "AAVA", Vec2::ZERO, 1.0, WHITE).unwrap();
+
+	let draws: Vec<_> = backend.draw_calls().iter().map(|dc| (dc.indices_count, dc.texture)).collect();
+	assert_eq!(draws, vec![(18, Some(pages[0].1)), (6, Some(pages[1].1))]);
+}
+
+#[test]
+fn loads_through_the_asset_server() {
+	let mut app = headless_app();
+	let handle: Handle<Font> = app.world.resource::<AssetServer>().load("tests/pixel.fnt");
+	for _ in 0..4 {
+		app.run_frame();
+	}
+	assert_eq!(app.world.resource::<AssetServer>().load_state(&handle), LoadState::Loaded);
+
+	// The page next to the font is uploaded
+	let font = app.world.resource::<Assets<Font>>().get(&handle).unwrap();
+	assert_eq!(font.pages.len(), 1);
+	assert!(app.world.resource::<Assets<Texture>>().contains(&font.pages[0]));
+}
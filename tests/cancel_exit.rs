@@ -16,7 +16,6 @@ fn main() {
 			resizeable: false,
 			..Default::default()
 		}))
-		.add_plugins(bevy_time::TimePlugin)
 		.add_systems(Startup, || {
 			println!("User needs to attempt Quitting the application twice to exit.");
 		})
@@ -0,0 +1,47 @@
+#![allow(dead_code)]
+
+use bevy_app::*;
+use glam::Vec2;
+use quadify::prelude::*;
+
+/// An app with all of Quadify's plugins, running on a 320x240 [`HeadlessPlugin`] instead of a window
+pub fn headless_app_with_frames(frames: Option<u32>) -> App {
+	let mut app = App::new();
+	app.add_plugins(QuadifyPlugins.build().disable::<WindowPlugin>().add(HeadlessPlugin { width: 320, height: 240, frames }));
+	app
+}
+
+/// Same as [`headless_app_with_frames`], with the first frame already run
+pub fn headless_app() -> App {
+	let mut app = headless_app_with_frames(None);
+	app.run_frame();
+	app
+}
+
+/// A [`RenderingBackend`] on top of a [`RecordingBackend`] with an 800x600 screen, and its (empty) call log
+pub fn recording_backend() -> (RenderingBackend, RecordedCalls) {
+	let recording = RecordingBackend::new();
+	let calls = recording.calls();
+	let mut backend = RenderingBackend::from_backend(Box::new(recording));
+	backend.set_screen_size(Some((800.0, 600.0)));
+	calls.clear();
+
+	(backend, calls)
+}
+
+/// Area covered by the triangles, counting overlaps twice
+pub fn area(positions: &[Vec2], indices: &[u32]) -> f32 {
+	indices
+		.chunks(3)
+		.map(|triangle| {
+			let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+			(b - a).perp_dot(c - a).abs() / 2.0
+		})
+		.sum()
+}
+
+/// [`area`] of the mesh, projected onto the xy plane
+pub fn mesh_area(mesh: &Mesh) -> f32 {
+	let positions: Vec<Vec2> = mesh.vertices.iter().map(|vertex| vertex.position.truncate()).collect();
+	area(&positions, &mesh.indices)
+}
@@ -0,0 +1,174 @@
+use glam::{vec2, vec3, Vec3};
+use quadify::color::WHITE;
+use quadify::prelude::*;
+
+mod common;
+use common::recording_backend;
+
+fn backend(max_vertices: usize, max_indices: usize) -> RenderingBackend {
+	let (mut backend, _) = recording_backend();
+	backend.update_drawcall_capacity(max_vertices, max_indices);
+	backend
+}
+
+/// A row of `n` quads, each made of 4 vertices and 6 indices
+fn quads(n: u32) -> (Vec<Vertex>, Vec<u32>) {
+	let mut vertices = vec![];
+	let mut indices = vec![];
+
+	for i in 0..n {
+		let x = i as f32;
+		for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+			vertices.push(Vertex::new(vec3(x + dx, dy, 0.0), vec2(dx, dy), WHITE));
+		}
+		indices.extend([0, 1, 2, 1, 2, 3].map(|index| i * 4 + index));
+	}
+
+	(vertices, indices)
+}
+
+/// Positions of every index across all draw calls, in order
+fn drawn_positions(backend: &RenderingBackend) -> Vec<Vec3> {
+	backend
+		.draw_calls()
+		.iter()
+		.flat_map(|dc| {
+			assert!(dc.indices().iter().all(|index| (*index as usize) < dc.vertices_count));
+			let vertices = dc.vertices::<Vertex>().unwrap();
+			dc.indices().iter().map(move |index| vertices[*index as usize].position)
+		})
+		.collect()
+}
+
+fn expected_positions<I: Copy + Into<u32>>(vertices: &[Vertex], indices: &[I]) -> Vec<Vec3> {
+	indices.iter().map(|index| vertices[(*index).into() as usize].position).collect()
+}
+
+#[test]
+fn geometry_that_fits_exactly_is_not_split() {
+	let mut backend = backend(8, 12);
+	let (vertices, indices) = quads(2);
+
+	backend.geometry(&vertices, &indices).unwrap();
+
+	assert_eq!(backend.draw_calls().len(), 1);
+	assert_eq!(backend.draw_calls()[0].vertices_count, 8);
+	assert_eq!(backend.draw_calls()[0].indices_count, 12);
+}
+
+#[test]
+fn splits_triangles_across_draw_calls() {
+	let mut backend = backend(10, 12);
+	let (vertices, indices) = quads(5);
+
+	backend.geometry(&vertices, &indices).unwrap();
+
+	let counts: Vec<_> = backend.draw_calls().iter().map(|dc| (dc.vertices_count, dc.indices_count)).collect();
+	assert_eq!(counts, vec![(8, 12), (8, 12), (4, 6)]);
+	assert_eq!(drawn_positions(&backend), expected_positions(&vertices, &indices));
+}
+
+#[test]
+fn splits_when_only_indices_overflow() {
+	let mut backend = backend(100, 9);
+	let (vertices, indices) = quads(3);
+
+	backend.geometry(&vertices, &indices).unwrap();
+
+	let counts: Vec<_> = backend.draw_calls().iter().map(|dc| dc.indices_count).collect();
+	assert_eq!(counts, vec![9, 9]);
+	assert_eq!(drawn_positions(&backend), expected_positions(&vertices, &indices));
+}
+
+#[test]
+fn splits_lines_on_line_boundaries() {
+	let mut backend = backend(4, 4);
+	let vertices: Vec<_> = (0..7).map(|i| Vertex::new(vec3(i as f32, 0.0, 0.0), vec2(0.0, 0.0), WHITE)).collect();
+	let indices: Vec<u16> = (0..6).flat_map(|i| [i, i + 1]).collect();
+
+	backend.draw_mode(pipeline::DrawMode::Lines);
+	backend.geometry(&vertices, &indices).unwrap();
+
+	assert_eq!(backend.draw_calls().len(), 3);
+	assert!(backend.draw_calls().iter().all(|dc| dc.draw_mode == pipeline::DrawMode::Lines && dc.indices_count == 4));
+	assert_eq!(drawn_positions(&backend), expected_positions(&vertices, &indices));
+}
+
+#[test]
+fn split_geometry_continues_batching() {
+	let mut backend = backend(10, 12);
+	let (vertices, indices) = quads(3);
+	let (small_vertices, small_indices) = quads(1);
+
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.geometry(&small_vertices, &small_indices).unwrap();
+
+	let counts: Vec<_> = backend.draw_calls().iter().map(|dc| (dc.vertices_count, dc.indices_count)).collect();
+	assert_eq!(counts, vec![(8, 12), (8, 12)]);
+}
+
+#[test]
+fn rejects_primitives_that_never_fit() {
+	let mut backend = backend(2, 2);
+	let (vertices, indices) = quads(1);
+
+	assert_eq!(
+		backend.geometry(&vertices, &indices),
+		Err(GeometryError::PrimitiveTooLarge { max_vertices: 2, max_indices: 2 })
+	);
+	assert!(backend.draw_calls().is_empty());
+}
+
+#[test]
+fn rejects_out_of_range_indices() {
+	let mut backend = backend(3, 3);
+	let (vertices, _) = quads(1);
+
+	assert_eq!(backend.geometry(&vertices, &[0u16, 1, 2, 1, 2, 4]), Err(GeometryError::IndexOutOfRange(4)));
+}
+
+#[test]
+fn accepts_u32_indices_past_u16_range() {
+	let mut backend = backend(10000, 5000);
+	let vertices: Vec<_> = (0..70000).map(|i| Vertex::new(vec3(i as f32, 0.0, 0.0), vec2(0.0, 0.0), WHITE)).collect();
+	let indices: Vec<u32> = vec![0, 1, 2, 69997, 69998, 69999];
+
+	backend.geometry(&vertices, &indices).unwrap();
+
+	assert_eq!(backend.draw_calls().len(), 1);
+	assert_eq!(backend.draw_calls()[0].vertices_count, 6);
+	assert_eq!(drawn_positions(&backend), expected_positions(&vertices, &indices));
+}
+
+#[test]
+fn large_draw_calls_use_32_bit_index_buffers() {
+	let recording = RecordingBackend::new();
+	let calls = recording.calls();
+	let mut backend = RenderingBackend::from_backend(Box::new(recording));
+	backend.set_screen_size(Some((800.0, 600.0)));
+	backend.update_drawcall_capacity(100_000, 150_000);
+
+	let (vertices, indices) = quads(20_000);
+
+	backend.geometry(&vertices, &indices).unwrap();
+	assert_eq!(backend.draw_calls().len(), 1);
+	assert_eq!(backend.draw_calls()[0].indices().last(), Some(&(80_000 - 1)));
+	assert_eq!(drawn_positions(&backend), expected_positions(&vertices, &indices));
+
+	calls.clear();
+	backend.draw(glam::Mat4::IDENTITY);
+
+	let index_buffer_sizes: Vec<_> = calls
+		.get()
+		.into_iter()
+		.filter_map(|call| match call {
+			RecordedCall::NewBuffer {
+				buffer_type: miniquad::BufferType::IndexBuffer,
+				size,
+				..
+			} => size,
+			_ => None,
+		})
+		.collect();
+	assert_eq!(index_buffer_sizes, vec![150_000 * std::mem::size_of::<u32>()]);
+}
@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use bevy_app::*;
+use bevy_ecs::prelude::*;
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app_with_frames;
+
+#[derive(Resource, Default)]
+struct Frames {
+	updates: u32,
+	draws: u32,
+}
+
+fn headless_app(frames: Option<u32>) -> App {
+	let mut app = headless_app_with_frames(frames);
+	app.init_resource::<Frames>()
+		.add_systems(Update, |mut frames: ResMut<Frames>| frames.updates += 1)
+		.add_systems(MiniquadDraw, |mut frames: ResMut<Frames>| frames.draws += 1);
+	app
+}
+
+#[test]
+fn steps_frames_on_demand() {
+	let mut app = headless_app(None);
+
+	app.run_frame();
+	app.run_frame();
+
+	let frames = app.world.resource::<Frames>();
+	assert_eq!((frames.updates, frames.draws), (2, 2));
+
+	let properties = app.world.resource::<WindowProperties>();
+	assert_eq!((properties.width, properties.height), (320, 240));
+	assert_eq!(app.world.non_send_resource::<RenderingBackend>().get_viewport(), (0, 0, 320, 240));
+}
+
+#[test]
+fn records_the_last_frame() {
+	let mut app = headless_app(None);
+
+	app.run_frame();
+	app.run_frame();
+
+	let calls = app.world.non_send_resource::<RecordedCalls>().get();
+	assert_eq!(calls, vec![RecordedCall::BeginPass(None), RecordedCall::EndPass, RecordedCall::CommitFrame]);
+}
+
+#[test]
+fn runs_until_app_exit() {
+	static UPDATES: AtomicU32 = AtomicU32::new(0);
+
+	headless_app(None)
+		.add_systems(Update, |mut app_exit: EventWriter<AppExit>| {
+			if UPDATES.fetch_add(1, Ordering::Relaxed) == 4 {
+				app_exit.send(AppExit);
+			}
+		})
+		.run();
+
+	assert_eq!(UPDATES.load(Ordering::Relaxed), 5);
+}
+
+#[test]
+fn runs_a_fixed_amount_of_frames() {
+	static UPDATES: AtomicU32 = AtomicU32::new(0);
+
+	headless_app(Some(3))
+		.add_systems(Update, || {
+			UPDATES.fetch_add(1, Ordering::Relaxed);
+		})
+		.run();
+
+	assert_eq!(UPDATES.load(Ordering::Relaxed), 3);
+}
@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+
+use bevy_app::*;
+use bevy_ecs::prelude::*;
+use bevy_input::{
+	keyboard::{Key, KeyboardInput},
+	mouse::{MouseMotion, MouseWheel},
+	prelude::*,
+	touch::{TouchInput, TouchPhase},
+};
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app_with_frames;
+
+#[derive(Resource, Default)]
+struct Received {
+	keyboard: Vec<KeyboardInput>,
+	mouse_motion: Vec<glam::Vec2>,
+	wheel: Vec<(f32, f32)>,
+	touches: Vec<(TouchPhase, u64)>,
+	dropped: Vec<DroppedFileEvent>,
+	key_down_schedule: u32,
+	mouse_down_schedule: u32,
+}
+
+fn collect(
+	mut received: ResMut<Received>,
+	mut keyboard: EventReader<KeyboardInput>,
+	mut motion: EventReader<MouseMotion>,
+	mut wheel: EventReader<MouseWheel>,
+	mut touches: EventReader<TouchInput>,
+	mut dropped: EventReader<DroppedFileEvent>,
+) {
+	received.keyboard.extend(keyboard.read().cloned());
+	received.mouse_motion.extend(motion.read().map(|event| event.delta));
+	received.wheel.extend(wheel.read().map(|event| (event.x, event.y)));
+	received.touches.extend(touches.read().map(|event| (event.phase, event.id)));
+	received.dropped.extend(dropped.read().cloned());
+}
+
+fn headless_app() -> App {
+	let mut app = headless_app_with_frames(None);
+	app.init_resource::<Received>()
+		.add_systems(Update, collect)
+		.add_systems(MiniquadKeyDownSchedule, |mut received: ResMut<Received>| received.key_down_schedule += 1)
+		.add_systems(MiniquadMouseDownSchedule, |mut received: ResMut<Received>| received.mouse_down_schedule += 1);
+	app.run_frame();
+	app
+}
+
+#[test]
+fn keyboard_input() {
+	let mut app = headless_app();
+
+	InputInjector::new(&mut app.world).key_down(miniquad::KeyCode::F).char_input('f');
+	app.run_frame();
+
+	assert!(app.world.resource::<ButtonInput<KeyCode>>().pressed(KeyCode::KeyF));
+
+	InputInjector::new(&mut app.world).key_up(miniquad::KeyCode::F).press_key(miniquad::KeyCode::Escape);
+	app.run_frame();
+
+	assert!(!app.world.resource::<ButtonInput<KeyCode>>().pressed(KeyCode::KeyF));
+	assert!(app.world.resource::<ButtonInput<KeyCode>>().just_released(KeyCode::Escape));
+
+	let received = app.world.resource::<Received>();
+	let keys: Vec<_> = received.keyboard.iter().map(|event| (event.key_code, event.state.is_pressed())).collect();
+	assert_eq!(
+		keys,
+		vec![
+			(KeyCode::KeyF, true),
+			(KeyCode::Unidentified(bevy_input::keyboard::NativeKeyCode::Unidentified), true),
+			(KeyCode::KeyF, false),
+			(KeyCode::Escape, true),
+			(KeyCode::Escape, false),
+		]
+	);
+	assert_eq!(received.keyboard[1].logical_key, Key::Character("f".into()));
+	assert_eq!(received.key_down_schedule, 2);
+}
+
+#[test]
+fn exit_on_esc_receives_injected_keys() {
+	let mut app = headless_app();
+	app.add_systems(Update, exit_on_esc);
+
+	InputInjector::new(&mut app.world).press_key(miniquad::KeyCode::Escape);
+	app.run_frame();
+
+	assert!(!app.world.resource::<Events<AppExit>>().is_empty());
+}
+
+#[test]
+fn mouse_input() {
+	let mut app = headless_app();
+
+	InputInjector::new(&mut app.world)
+		.move_mouse(10.0, 10.0)
+		.move_mouse(15.0, 12.0)
+		.mouse_button_down(miniquad::MouseButton::Left)
+		.mouse_wheel(0.0, -1.0);
+	app.run_frame();
+
+	assert!(app.world.resource::<ButtonInput<MouseButton>>().pressed(MouseButton::Left));
+	assert_eq!(app.world.resource::<WindowProperties>().cursor_position(), glam::vec2(15.0, 12.0));
+
+	InputInjector::new(&mut app.world).mouse_button_up(miniquad::MouseButton::Left).click(miniquad::MouseButton::Right);
+	app.run_frame();
+
+	assert!(!app.world.resource::<ButtonInput<MouseButton>>().pressed(MouseButton::Left));
+	assert!(app.world.resource::<ButtonInput<MouseButton>>().just_released(MouseButton::Right));
+
+	let received = app.world.resource::<Received>();
+	assert_eq!(received.mouse_motion, vec![glam::vec2(5.0, 2.0)]);
+	assert_eq!(received.wheel, vec![(0.0, -1.0)]);
+	assert_eq!(received.mouse_down_schedule, 4);
+}
+
+#[test]
+fn touch_and_file_drop() {
+	let mut app = headless_app();
+
+	InputInjector::new(&mut app.world)
+		.touch(miniquad::TouchPhase::Started, 1, 4.0, 4.0)
+		.touch(miniquad::TouchPhase::Ended, 1, 4.0, 4.0)
+		.drop_file(Some(PathBuf::from("level.txt")), Some(b"level".to_vec()));
+	app.run_frame();
+
+	let received = app.world.resource::<Received>();
+	assert_eq!(received.touches, vec![(TouchPhase::Started, 1), (TouchPhase::Ended, 1)]);
+	assert_eq!(received.dropped.len(), 1);
+	assert_eq!(received.dropped[0].path, Some(PathBuf::from("level.txt")));
+	assert_eq!(received.dropped[0].bytes.as_deref(), Some(&b"level"[..]));
+}
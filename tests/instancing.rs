@@ -0,0 +1,130 @@
+use glam::{vec2, vec3, Mat4};
+use miniquad::BufferType;
+use quadify::color::WHITE;
+use quadify::prelude::*;
+
+mod common;
+use common::recording_backend;
+
+fn quad_mesh() -> Mesh {
+	MeshBuilder::default().as_quad(vec2(1.0, 1.0)).at_position(vec3(0.0, 0.0, 0.0)).with_color(WHITE).build().unwrap()
+}
+
+fn instances(n: usize) -> Vec<InstanceData> {
+	(0..n).map(|i| InstanceData::new(Mat4::from_translation(vec3(i as f32, 0.0, 0.0)), WHITE)).collect()
+}
+
+#[test]
+fn uploads_the_mesh_once() {
+	let (mut backend, calls) = recording_backend();
+	let mesh = backend.upload_mesh(&quad_mesh()).unwrap();
+
+	for _ in 0..3 {
+		backend.draw_instanced(&mesh, &instances(1000)).unwrap();
+		backend.draw(Mat4::IDENTITY);
+	}
+
+	let calls = calls.take();
+	let index_buffers = calls.iter().filter(|call| matches!(call, RecordedCall::NewBuffer { buffer_type: BufferType::IndexBuffer, .. })).count();
+	// The mesh's index buffer and the (unused) one of the draw call
+	assert_eq!(index_buffers, 2);
+
+	let draws: Vec<_> = calls
+		.iter()
+		.filter_map(|call| match call {
+			RecordedCall::Draw { num_elements, num_instances, .. } => Some((*num_elements, *num_instances)),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(draws, vec![(6, 1000); 3]);
+}
+
+#[test]
+fn binds_the_instance_buffer() {
+	let (mut backend, calls) = recording_backend();
+	let mesh = backend.upload_mesh(&quad_mesh()).unwrap();
+
+	backend.draw_instanced(&mesh, &instances(10)).unwrap();
+	backend.draw(Mat4::IDENTITY);
+
+	let calls = calls.take();
+	let (mesh_buffer, mesh_index_buffer) = match &calls[0..2] {
+		[RecordedCall::NewBuffer { buffer: vertices, .. }, RecordedCall::NewBuffer { buffer: indices, .. }] => (*vertices, *indices),
+		other => panic!("Expected the mesh buffers, got {:?}", other),
+	};
+	let instance_buffer = calls
+		.iter()
+		.find_map(|call| match call {
+			RecordedCall::NewBuffer { buffer, size: Some(size), .. } if *size == (10 * std::mem::size_of::<InstanceData>()).next_power_of_two() => Some(*buffer),
+			_ => None,
+		})
+		.expect("No instance buffer was created");
+
+	let bindings: Vec<_> = calls.iter().filter(|call| matches!(call, RecordedCall::ApplyBindings { .. })).collect();
+	assert_eq!(bindings.len(), 1);
+	match bindings[0] {
+		RecordedCall::ApplyBindings { vertex_buffers, index_buffer, .. } => {
+			assert_eq!(vertex_buffers, &vec![mesh_buffer, instance_buffer]);
+			assert_eq!(*index_buffer, mesh_index_buffer);
+		}
+		_ => unreachable!(),
+	}
+}
+
+#[test]
+fn keeps_order_with_batched_geometry() {
+	let (mut backend, calls) = recording_backend();
+	let quad = quad_mesh();
+	let mesh = backend.upload_mesh(&quad).unwrap();
+
+	backend.geometry(&quad.vertices, &quad.indices).unwrap();
+	backend.draw_instanced(&mesh, &instances(5)).unwrap();
+	backend.geometry(&quad.vertices, &quad.indices).unwrap();
+	backend.geometry(&quad.vertices, &quad.indices).unwrap();
+	assert_eq!(backend.draw_calls().len(), 3);
+
+	backend.draw(Mat4::IDENTITY);
+
+	let draws: Vec<_> = calls
+		.take()
+		.into_iter()
+		.filter_map(|call| match call {
+			RecordedCall::Draw { num_elements, num_instances, .. } => Some((num_elements, num_instances)),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(draws, vec![(6, 1), (6, 5), (12, 1)]);
+}
+
+#[test]
+fn rejects_mismatched_pipelines() {
+	let (mut backend, _calls) = recording_backend();
+	let quad = quad_mesh();
+	let mesh = backend.upload_mesh(&quad).unwrap();
+
+	// The default pipeline isn't instanced
+	let params = MaterialParams {
+		pipeline_params: Default::default(),
+		uniforms: vec![],
+		textures: vec![],
+	};
+	let material = backend.request_material(miniquad::ShaderSource::Glsl { vertex: "", fragment: "" }, params.clone()).unwrap();
+	backend.set_material(&material);
+	assert!(matches!(backend.draw_instanced(&mesh, &instances(1)), Err(GeometryError::InstanceLayoutMismatch { expected: None, .. })));
+
+	// Instanced materials can't draw plain geometry
+	let instanced = backend.request_instanced_material::<Vertex, InstanceData>(miniquad::ShaderSource::Glsl { vertex: "", fragment: "" }, params).unwrap();
+	backend.set_material(&instanced);
+	assert!(matches!(backend.geometry(&quad.vertices, &quad.indices), Err(GeometryError::InstanceLayoutMismatch { found: None, .. })));
+	backend.draw_instanced(&mesh, &instances(1)).unwrap();
+}
+
+#[test]
+fn rejects_out_of_range_indices() {
+	let (mut backend, _calls) = recording_backend();
+	let mut quad = quad_mesh();
+
+	assert_eq!(backend.upload_geometry(&quad.vertices, &[0u16, 1, 4]), Err(GeometryError::IndexOutOfRange(4)));
+	quad.indices[5] = 7;
+	assert_eq!(backend.upload_mesh(&quad), Err(GeometryError::IndexOutOfRange(7)));
+}
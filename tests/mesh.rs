@@ -1,13 +1,11 @@
-/// This test should be changed in the future to use more higher-level functionality like `Material`s, to avoid writing repetitive shaders;
-/// Thus this test is only for mesh demonstration purposes.
+/// Mesh demonstration: the circle entity is drawn by the default mesh rendering, clicking adds more points to it.
 use bevy_app::prelude::*;
 use bevy_asset::{Assets, Handle};
 use bevy_ecs::prelude::*;
 use bevy_input::mouse::MouseButtonInput;
-use glam::{vec3, Mat4};
+use glam::vec3;
 use quadify::color::RED;
 use quadify::prelude::geometry::{Mesh, MeshBuilder};
-use quadify::prelude::RenderingBackend;
 use quadify::prelude::WindowPlugin;
 use quadify::prelude::*;
 
@@ -30,23 +28,23 @@ fn main() {
 		}))
 		.add_systems(Startup, setup_render_state)
 		.add_systems(Update, change_on_click)
-		.add_systems(MiniquadDraw, draw_circle)
 		.run();
 }
 
 fn setup_render_state(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
-	commands.insert_resource(MeshHandle {
-		#[rustfmt::skip]
-		mesh: meshes.add(
-			MeshBuilder::default()
-				.as_circle(0.2)
-				.circle_points(3)
-				.with_color(RED)
-				.at_position(vec3(0.0, 0.0, 0.0))
-				.build()
-			),
-		parts_count: 3,
-	});
+	#[rustfmt::skip]
+	let mesh = meshes.add(
+		MeshBuilder::default()
+			.as_circle(0.2)
+			.circle_points(3)
+			.with_color(RED)
+			.at_position(vec3(0.0, 0.0, 0.0))
+			.build()
+			.unwrap()
+	);
+
+	commands.spawn((mesh.clone(), Transform::default()));
+	commands.insert_resource(MeshHandle { mesh, parts_count: 3 });
 }
 
 fn change_on_click(mut mesh: ResMut<MeshHandle>, mut click: EventReader<MouseButtonInput>, mut meshes: ResMut<Assets<Mesh>>) {
@@ -62,18 +60,8 @@ fn change_on_click(mut mesh: ResMut<MeshHandle>, mut click: EventReader<MouseBut
 					.with_color(RED)
 					.at_position(vec3(0.0, 0.0, 0.0))
 					.build()
+					.unwrap()
 			);
 		}
 	}
 }
-
-fn draw_circle(mesh: Res<MeshHandle>, mut render_ctx: NonSendMut<RenderingBackend>, meshes: Res<Assets<Mesh>>, clear_colour: Res<ClearColor>) {
-	if let Some(mesh) = meshes.get(&mesh.mesh) {
-		let (verts, inds) = (&mesh.vertices, &mesh.indices);
-		render_ctx.clear(clear_colour.0);
-		render_ctx.texture(None);
-		render_ctx.draw_mode(pipeline::DrawMode::Triangles);
-		render_ctx.geometry(&verts[..], &inds[..]);
-		render_ctx.draw(Mat4::IDENTITY);
-	}
-}
@@ -0,0 +1,116 @@
+use bevy_app::*;
+use bevy_asset::{Assets, Handle};
+use glam::{vec2, vec3};
+use quadify::asset::Texture;
+use quadify::color::WHITE;
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+fn quad(app: &mut App) -> Handle<Mesh> {
+	let mesh = MeshBuilder::default().as_quad(vec2(1.0, 1.0)).at_position(vec3(0.0, 0.0, 0.0)).with_color(WHITE).build().unwrap();
+	app.world.resource_mut::<Assets<Mesh>>().add(mesh)
+}
+
+fn texture(app: &mut App) -> (Handle<Texture>, miniquad::TextureId) {
+	let id = app.world.non_send_resource_mut::<RenderingBackend>().new_texture_from_rgba8(1, 1, &[255, 0, 0, 255]);
+	(app.world.resource_mut::<Assets<Texture>>().add(Texture::new(id)), id)
+}
+
+fn draws(app: &App) -> Vec<i32> {
+	app.world
+		.non_send_resource::<RecordedCalls>()
+		.get()
+		.into_iter()
+		.filter_map(|call| match call {
+			RecordedCall::Draw { num_elements, .. } => Some(num_elements),
+			_ => None,
+		})
+		.collect()
+}
+
+fn bound_textures(app: &App) -> Vec<miniquad::TextureId> {
+	app.world
+		.non_send_resource::<RecordedCalls>()
+		.get()
+		.into_iter()
+		.filter_map(|call| match call {
+			RecordedCall::ApplyBindings { images, .. } => Some(images[0]),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn batches_mesh_entities() {
+	let mut app = headless_app();
+	let mesh = quad(&mut app);
+
+	app.world.spawn((mesh.clone(), Transform::from_xyz(-0.5, 0.0, 0.0)));
+	app.world.spawn((mesh.clone(), Transform::from_xyz(0.5, 0.0, 0.0)));
+	// Entities without a transform aren't drawn
+	app.world.spawn(mesh);
+	app.run_frame();
+
+	assert_eq!(draws(&app), vec![12]);
+}
+
+#[test]
+fn draws_textured_meshes_in_z_order() {
+	let mut app = headless_app();
+	let mesh = quad(&mut app);
+	let (texture, texture_id) = texture(&mut app);
+
+	app.world.spawn((mesh.clone(), texture.clone(), Transform::from_xyz(0.0, 0.0, 2.0)));
+	app.world.spawn((mesh.clone(), Transform::from_xyz(0.0, 0.0, 1.0)));
+	app.world.spawn((mesh, texture, Transform::from_xyz(0.0, 0.0, 0.0)));
+	app.run_frame();
+
+	assert_eq!(draws(&app), vec![6, 6, 6]);
+
+	let textures = bound_textures(&app);
+	assert_eq!(textures[0], texture_id);
+	assert_ne!(textures[1], texture_id);
+	assert_eq!(textures[2], texture_id);
+}
+
+#[test]
+fn applies_materials() {
+	let mut app = headless_app();
+	let mesh = quad(&mut app);
+	let params = MaterialParams {
+		pipeline_params: Default::default(),
+		uniforms: vec![],
+		textures: vec![],
+	};
+	let material = app
+		.world
+		.non_send_resource_mut::<RenderingBackend>()
+		.request_material(miniquad::ShaderSource::Glsl { vertex: "", fragment: "" }, params)
+		.unwrap();
+	let material = app.world.resource_mut::<Assets<Material>>().add(material);
+
+	app.world.spawn((mesh.clone(), Transform::default()));
+	app.world.spawn((mesh, material, Transform::from_xyz(0.0, 0.0, 1.0)));
+	app.run_frame();
+
+	let calls = app.world.non_send_resource::<RecordedCalls>().get();
+	let pipelines: Vec<_> = calls
+		.iter()
+		.filter_map(|call| match call {
+			RecordedCall::ApplyPipeline(pipeline) => Some(*pipeline),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(pipelines.len(), 2);
+	assert_ne!(pipelines[0], pipelines[1]);
+}
+
+#[test]
+fn renders_nothing_without_meshes() {
+	let mut app = headless_app();
+	app.run_frame();
+
+	assert!(draws(&app).is_empty());
+}
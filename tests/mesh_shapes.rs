@@ -0,0 +1,110 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use glam::{uvec2, vec2, vec3, Vec2};
+use quadify::color::RED;
+use quadify::prelude::*;
+
+mod common;
+use common::mesh_area;
+
+fn uvs_in_range(mesh: &Mesh) -> bool {
+	mesh.vertices.iter().all(|vertex| vertex.uv.cmpge(Vec2::splat(-1e-5)).all() && vertex.uv.cmple(Vec2::splat(1.0 + 1e-5)).all())
+}
+
+fn build(shape: impl FnOnce(&mut MeshBuilder) -> &mut MeshBuilder) -> Mesh {
+	let mut builder = MeshBuilder::default();
+	shape(&mut builder).circle_points(32).at_position(vec3(1.0, 2.0, 3.0)).with_color(RED).build().unwrap()
+}
+
+#[test]
+fn maps_circle_uvs_to_the_texture() {
+	let mesh = build(|builder| builder.as_circle(2.0));
+	assert!(uvs_in_range(&mesh));
+	// The rightmost point samples the right edge, halfway down
+	assert_eq!((mesh.vertices[0].position, mesh.vertices[0].uv), (vec3(3.0, 2.0, 3.0), vec2(1.0, 0.5)));
+	let top = mesh.vertices[8];
+	assert!(top.position.abs_diff_eq(vec3(1.0, 4.0, 3.0), 1e-5) && top.uv.abs_diff_eq(vec2(0.5, 0.0), 1e-5));
+}
+
+#[test]
+fn builds_round_shapes() {
+	let ellipse = build(|builder| builder.as_ellipse(vec2(4.0, 2.0)));
+	assert_eq!((ellipse.vertices.len(), ellipse.indices.len()), (33, 32 * 3));
+	assert!((mesh_area(&ellipse) - PI * 8.0).abs() < 0.2);
+	assert_eq!(ellipse.vertices[0].uv, vec2(0.5, 0.5));
+
+	let ring = build(|builder| builder.as_ring(1.0, 2.0));
+	assert!((mesh_area(&ring) - PI * 3.0).abs() < 0.1);
+	assert_eq!(ring.indices.len(), 32 * 6);
+
+	// A quarter of the circle, keeping to its quarter of the texture
+	let sector = build(|builder| builder.as_arc(0.0, 2.0, 0.0, FRAC_PI_2));
+	assert_eq!(sector.indices.len(), 8 * 3);
+	assert!((mesh_area(&sector) - PI).abs() < 0.05);
+	assert!(sector.vertices.iter().all(|vertex| vertex.uv.x >= 0.5 - 1e-5 && vertex.uv.y <= 0.5 + 1e-5));
+
+	let arc = build(|builder| builder.as_arc(1.0, 2.0, PI, -FRAC_PI_2));
+	assert!((mesh_area(&arc) - PI * 3.0 / 4.0).abs() < 0.05);
+	// Going clockwise from the left to the top
+	assert!(arc.vertices.iter().all(|vertex| vertex.uv.x <= 0.5 + 1e-5 && vertex.uv.y <= 0.5 + 1e-5));
+
+	for mesh in [&ellipse, &ring, &sector, &arc] {
+		assert!(uvs_in_range(mesh));
+		assert!(mesh.vertices.iter().all(|vertex| vertex.color == RED && vertex.position.z == 3.0));
+	}
+}
+
+#[test]
+fn builds_rounded_rectangles_and_capsules() {
+	let rect = build(|builder| builder.as_rounded_rect(vec2(4.0, 2.0), 0.5));
+	assert!((mesh_area(&rect) - (8.0 - (1.0 - PI / 4.0))).abs() < 0.01);
+	assert!(uvs_in_range(&rect));
+
+	// Without a radius, the corners collapse into plain rectangle corners
+	let square = build(|builder| builder.as_rounded_rect(vec2(2.0, 2.0), 0.0));
+	assert_eq!(square.vertices.len(), 5);
+	assert!((mesh_area(&square) - 4.0).abs() < 1e-4);
+
+	let capsule = build(|builder| builder.as_capsule(vec2(2.0, 6.0)));
+	assert!((mesh_area(&capsule) - (8.0 + PI)).abs() < 0.05);
+	let positions: Vec<Vec2> = capsule.vertices.iter().map(|vertex| vertex.position.truncate() - vec2(1.0, 2.0)).collect();
+	assert!(positions.iter().any(|position| position.abs_diff_eq(vec2(0.0, 3.0), 1e-5)));
+	assert!(positions.iter().all(|position| position.x.abs() <= 1.0 + 1e-5 && position.y.abs() <= 3.0 + 1e-5));
+}
+
+#[test]
+fn builds_polygons_grids_and_triangles() {
+	let hexagon = build(|builder| builder.as_polygon(6, 1.0));
+	assert_eq!((hexagon.vertices.len(), hexagon.indices.len()), (7, 18));
+	assert!((mesh_area(&hexagon) - 3.0 * 3f32.sqrt() / 2.0).abs() < 1e-4);
+
+	let grid = build(|builder| builder.as_grid(vec2(4.0, 2.0), uvec2(4, 2)));
+	assert_eq!((grid.vertices.len(), grid.indices.len()), (15, 8 * 6));
+	assert!((mesh_area(&grid) - 8.0).abs() < 1e-4);
+	// Rows go from the top down, like the quad's vertices
+	assert_eq!((grid.vertices[0].position, grid.vertices[0].uv), (vec3(-1.0, 3.0, 3.0), vec2(0.0, 0.0)));
+	assert_eq!((grid.vertices[14].position, grid.vertices[14].uv), (vec3(3.0, 1.0, 3.0), vec2(1.0, 1.0)));
+
+	let triangle = build(|builder| builder.as_triangle(vec2(0.0, 0.0), vec2(2.0, 0.0), vec2(0.0, 1.0)));
+	assert_eq!(triangle.indices, vec![0, 1, 2]);
+	assert_eq!(triangle.vertices.iter().map(|vertex| vertex.uv).collect::<Vec<_>>(), vec![vec2(0.0, 1.0), vec2(1.0, 1.0), vec2(0.0, 0.0)]);
+	assert_eq!(triangle.vertices[2].position, vec3(1.0, 3.0, 3.0));
+}
+
+#[test]
+fn fails_without_shape_or_position() {
+	assert_eq!(MeshBuilder::default().at_position(vec3(0.0, 0.0, 0.0)).build().err(), Some(MeshError::MissingShape));
+	assert_eq!(MeshBuilder::default().as_quad(vec2(1.0, 1.0)).build().err(), Some(MeshError::MissingPosition));
+}
+
+#[test]
+fn fails_with_too_few_circle_points() {
+	let mut builder = MeshBuilder::default();
+	builder.circle_points(2).at_position(vec3(0.0, 0.0, 0.0));
+	assert_eq!(builder.as_circle(1.0).build().err(), Some(MeshError::TooFewPoints(2)));
+	assert_eq!(builder.as_ring(1.0, 2.0).build().err(), Some(MeshError::TooFewPoints(2)));
+
+	// Shapes without a round outline don't use the points
+	assert!(builder.as_quad(vec2(1.0, 1.0)).build().is_ok());
+	assert_eq!(builder.circle_points(3).as_circle(1.0).build().unwrap().indices.len(), 3);
+}
@@ -0,0 +1,79 @@
+use glam::{uvec2, vec2, vec3, vec4, Affine2, Mat4, Quat, Vec3};
+use quadify::color::{RED, WHITE};
+use quadify::prelude::*;
+
+fn quad(position: Vec3) -> Mesh {
+	MeshBuilder::default().as_quad(vec2(2.0, 2.0)).at_position(position).with_color(WHITE).build().unwrap()
+}
+
+#[test]
+fn computes_bounding_boxes() {
+	let mesh = Mesh::merge([&quad(vec3(0.0, 0.0, 0.0)), &quad(vec3(4.0, 1.0, -2.0))]);
+	let aabb = mesh.aabb().unwrap();
+	assert_eq!(aabb, Aabb::new(vec3(-1.0, -1.0, -2.0), vec3(5.0, 2.0, 0.0)));
+	assert_eq!((aabb.center(), aabb.size()), (vec3(2.0, 0.5, -1.0), vec3(6.0, 3.0, 2.0)));
+	assert!(aabb.contains(vec3(5.0, 2.0, 0.0)) && !aabb.contains(vec3(5.1, 0.0, 0.0)));
+
+	assert!(aabb.intersects(&Aabb::new(vec3(5.0, 0.0, 0.0), vec3(6.0, 1.0, 1.0))));
+	assert!(!aabb.intersects(&Aabb::new(vec3(0.0, 3.0, 0.0), vec3(1.0, 4.0, 0.0))));
+	assert_eq!(aabb.union(&Aabb::new(vec3(0.0, 3.0, 0.0), vec3(1.0, 4.0, 0.0))).max, vec3(5.0, 4.0, 0.0));
+
+	// Rotating a quarter turn around z swaps the width and the height
+	let rotated = aabb.transformed_by(&Mat4::from_rotation_translation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2), vec3(10.0, 0.0, 0.0)));
+	assert!(rotated.min.abs_diff_eq(vec3(8.0, -1.0, -2.0), 1e-5) && rotated.max.abs_diff_eq(vec3(11.0, 5.0, 0.0), 1e-5), "{:?}", rotated);
+
+	assert_eq!(Mesh { vertices: Vec::new(), indices: Vec::new() }.aabb(), None);
+}
+
+#[test]
+fn merges_meshes_with_rebased_indices() {
+	let (first, second) = (quad(vec3(0.0, 0.0, 0.0)), quad(vec3(4.0, 0.0, 0.0)).colored_as(RED));
+	let merged = Mesh::merge([&first, &second]);
+	assert_eq!(merged.vertices.len(), 8);
+	assert_eq!(merged.indices, vec![0, 1, 2, 1, 2, 3, 4, 5, 6, 5, 6, 7]);
+	assert_eq!(merged.vertices[4].color, RED);
+
+	let mut appended = first.clone();
+	appended.append(&second);
+	assert_eq!(appended, merged);
+	assert!(merged.validate().is_ok());
+}
+
+#[test]
+fn builds_wireframes_without_repeated_edges() {
+	// The diagonal is shared by both triangles of the quad
+	assert_eq!(quad(Vec3::ZERO).to_wireframe(), vec![0, 1, 1, 2, 2, 0, 2, 3, 3, 1]);
+
+	let grid = MeshBuilder::default().as_grid(vec2(2.0, 2.0), uvec2(2, 2)).at_position(Vec3::ZERO).build().unwrap();
+	let lines = grid.to_wireframe();
+	// 12 sides of the cells and 4 diagonals
+	assert_eq!(lines.len(), (12 + 4) * 2);
+
+	let mut backend = RenderingBackend::from_backend(Box::new(RecordingBackend::new()));
+	backend.set_screen_size(Some((800.0, 600.0)));
+	backend.draw_mode(pipeline::DrawMode::Lines);
+	backend.geometry(&grid.vertices, &lines).unwrap();
+	assert_eq!(backend.draw_calls()[0].indices_count, lines.len());
+}
+
+#[test]
+fn transforms_uvs() {
+	let mut mesh = quad(Vec3::ZERO);
+	mesh.flip_uvs(true, false);
+	assert_eq!(mesh.vertices.iter().map(|vertex| vertex.uv).collect::<Vec<_>>(), vec![vec2(1.0, 0.0), vec2(0.0, 0.0), vec2(1.0, 1.0), vec2(0.0, 1.0)]);
+
+	// A 16x8 frame at (16, 8) of a 64x32 texture
+	let mut mesh = quad(Vec3::ZERO);
+	mesh.map_uvs_to_region(vec4(16.0, 8.0, 16.0, 8.0), vec2(64.0, 32.0));
+	assert_eq!((mesh.vertices[0].uv, mesh.vertices[3].uv), (vec2(0.25, 0.25), vec2(0.5, 0.5)));
+
+	let mesh = quad(Vec3::ZERO).uvs_transformed_by(Affine2::from_scale(vec2(2.0, 3.0)));
+	assert_eq!(mesh.vertices[3].uv, vec2(2.0, 3.0));
+}
+
+#[test]
+fn validates_indices() {
+	let mut mesh = quad(Vec3::ZERO);
+	mesh.indices.push(4);
+	assert_eq!(mesh.validate(), Err(GeometryError::IndexOutOfRange(4)));
+}
@@ -0,0 +1,149 @@
+use bevy_asset::{AssetServer, Assets, Handle, LoadState};
+use glam::{vec2, vec3};
+use quadify::asset::{MtlFile, ObjFile};
+use quadify::color::{Rgba, WHITE};
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+const MATERIALS: &str = "# Exported materials
+newmtl red
+Kd 1.0 0.0 0.0
+d 0.5
+
+newmtl blue paint
+Kd 0 0 1
+Tr 0.25
+";
+
+#[test]
+fn parses_material_libraries() {
+	let library = MtlFile::from_slice(MATERIALS.as_bytes()).unwrap();
+	assert_eq!(library.materials.len(), 2);
+	assert_eq!(library.materials["red"], Rgba::new(255, 0, 0, 128));
+	assert_eq!(library.materials["blue paint"], Rgba::new(0, 0, 255, 191));
+
+	assert!(MtlFile::from_slice(b"Kd 1 1 1").is_err());
+	assert!(MtlFile::from_slice(b"newmtl broken\nKd 1 one 1").is_err());
+}
+
+#[test]
+fn shares_vertices_of_triangulated_faces() {
+	let obj = "mtllib props.mtl
+v -1 -1 0
+v 1 -1 0
+v 1 1 0
+v -1 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+# A quad, split into two triangles sharing the diagonal
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+	let file = ObjFile::from_slice(obj.as_bytes()).unwrap();
+	assert_eq!(file.material_libraries, vec!["props.mtl"]);
+
+	let mesh = file.mesh(&MtlFile::default());
+	assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+	assert_eq!(mesh.vertices.len(), 4);
+	// Texture coordinates are flipped to point down, like the texture rows
+	assert_eq!((mesh.vertices[0].position, mesh.vertices[0].uv), (vec3(-1.0, -1.0, 0.0), vec2(0.0, 1.0)));
+	assert_eq!(mesh.vertices[2].uv, vec2(1.0, 0.0));
+	assert!(mesh.vertices.iter().all(|vertex| vertex.color == WHITE));
+	assert!(mesh.validate().is_ok());
+}
+
+#[test]
+fn colors_vertices_by_material_and_vertex_colors() {
+	let obj = "v 0 0 0 1 1 1
+v 1 0 0 0.5 0.5 0.5
+v 0 1 0
+v 1 1 0
+usemtl red
+f 1 2 3
+usemtl missing
+f 2 4 3
+usemtl blue paint
+f -4 -3 -2
+";
+	let file = ObjFile::from_slice(obj.as_bytes()).unwrap();
+	let mesh = file.mesh(&MtlFile::from_slice(MATERIALS.as_bytes()).unwrap());
+	// Corners shared by faces of different materials are split
+	assert_eq!(mesh.vertices.len(), 9);
+	assert_eq!(mesh.indices.len(), 9);
+
+	let colors: Vec<Rgba> = mesh.indices.iter().map(|index| mesh.vertices[*index as usize].color).collect();
+	assert_eq!(colors[0..3], [Rgba::new(255, 0, 0, 128), Rgba::new(128, 0, 0, 128), Rgba::new(255, 0, 0, 128)]);
+	// Unknown materials leave the vertex colors alone
+	assert_eq!(colors[3..6], [Rgba::new(128, 128, 128, 255), WHITE, WHITE]);
+	assert_eq!(colors[6..9], [Rgba::new(0, 0, 255, 191), Rgba::new(0, 0, 128, 191), Rgba::new(0, 0, 255, 191)]);
+	// Faces without texture coordinates sample the corner of the texture
+	assert!(mesh.vertices.iter().all(|vertex| vertex.uv == vec2(0.0, 0.0)));
+}
+
+#[test]
+fn splits_objects_and_groups() {
+	let obj = "v 0 0 0
+v 1 0 0
+v 0 1 0
+v 1 1 0
+f 1 2 3
+o body
+f 2 4 3
+g wheel
+f 1 2 4
+g empty
+g body
+f 1//1 3//1 4//1
+";
+	let file = ObjFile::from_slice(obj.as_bytes()).unwrap();
+	let meshes = file.meshes(&MtlFile::default());
+	assert_eq!(meshes.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["", "body", "wheel"]);
+	// Returning to a group keeps adding to it
+	assert_eq!((meshes[1].1.vertices.len(), meshes[1].1.indices.len()), (4, 6));
+
+	let mesh = file.mesh(&MtlFile::default());
+	assert_eq!(mesh.vertices.len(), 3 + 4 + 3);
+	assert_eq!(mesh.indices.len(), 4 * 3);
+	assert!(mesh.validate().is_ok());
+}
+
+#[test]
+fn rejects_malformed_models() {
+	assert!(ObjFile::from_slice(b"v 1 2").is_err());
+	assert!(ObjFile::from_slice(b"v 0 0 0\nv 1 0 0\nf 1 2").is_err());
+	assert!(ObjFile::from_slice(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4").is_err());
+	assert!(ObjFile::from_slice(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2").is_err());
+	assert!(ObjFile::from_slice(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1/1 2/2 3/3").is_err());
+	assert!(ObjFile::from_slice(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 -4").is_err());
+	assert!(ObjFile::from_slice(b"v 0 0 0\nv 1 0 0\nv 0 1 0\nf a b c").is_err());
+
+	// Unsupported statements are skipped
+	let file = ObjFile::from_slice(b"s off\nl 1 2\ncstype bezier\n").unwrap();
+	assert!(file.meshes(&MtlFile::default()).is_empty());
+}
+
+#[test]
+fn loads_through_the_asset_server() {
+	let mut app = headless_app();
+	let handle: Handle<Mesh> = app.world.resource::<AssetServer>().load("tests/sign.obj");
+	let post: Handle<Mesh> = app.world.resource::<AssetServer>().load("tests/sign.obj#objects/post");
+	let board: Handle<Mesh> = app.world.resource::<AssetServer>().load("tests/sign.obj#objects/board");
+	for _ in 0..4 {
+		app.run_frame();
+	}
+	assert_eq!(app.world.resource::<AssetServer>().load_state(&handle), LoadState::Loaded);
+
+	let meshes = app.world.resource::<Assets<Mesh>>();
+	let mesh = meshes.get(&handle).unwrap();
+	assert_eq!((mesh.vertices.len(), mesh.indices.len()), (8, 12));
+	// Colored by the material library next to the model
+	assert!(mesh.vertices.iter().all(|vertex| vertex.color == Rgba::new(128, 64, 0, 255)));
+
+	let (post, board) = (meshes.get(&post).unwrap(), meshes.get(&board).unwrap());
+	assert_eq!(post.aabb().map(|aabb| (aabb.min, aabb.max)), Some((vec3(0.0, 0.0, 0.0), vec3(1.0, 1.0, 0.0))));
+	assert_eq!(board.aabb().map(|aabb| (aabb.min, aabb.max)), Some((vec3(-2.0, 1.0, 0.0), vec3(2.0, 2.0, 0.0))));
+}
@@ -0,0 +1,132 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use glam::{vec2, Vec2};
+use quadify::color::{RED, WHITE};
+use quadify::prelude::*;
+
+mod common;
+use common::area;
+
+fn fill_area(path: &Path, rule: FillRule) -> f32 {
+	let (positions, indices) = path.tessellate_fill(rule);
+	area(&positions, &indices)
+}
+
+fn square(builder: &mut PathBuilder, min: Vec2, size: f32, clockwise: bool) {
+	let mut corners = [min, min + vec2(size, 0.0), min + vec2(size, size), min + vec2(0.0, size)];
+	if clockwise {
+		corners.reverse();
+	}
+	builder.polygon(&corners);
+}
+
+#[test]
+fn builds_contours() {
+	let path = PathBuilder::default().move_to(vec2(0.0, 0.0)).line_to(vec2(1.0, 0.0)).line_to(vec2(1.0, 1.0)).close().line_to(vec2(0.0, 1.0)).move_to(vec2(5.0, 5.0)).build();
+
+	// Closing starts the next contour at the first point, and lone points are dropped
+	assert_eq!(
+		path.contours,
+		vec![
+			Contour {
+				points: vec![vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0)],
+				closed: true
+			},
+			Contour {
+				points: vec![vec2(0.0, 0.0), vec2(0.0, 1.0)],
+				closed: false
+			},
+		]
+	);
+}
+
+#[test]
+fn flattens_curves_within_the_tolerance() {
+	let mut builder = PathBuilder::default();
+	builder.tolerance(0.01).move_to(vec2(0.0, 0.0)).quadratic_to(vec2(5.0, 10.0), vec2(10.0, 0.0));
+	let path = builder.build();
+	let points = &path.contours[0].points;
+	assert!(points.len() > 4);
+	assert_eq!(*points.last().unwrap(), vec2(10.0, 0.0));
+	// The top of the curve is halfway to the control point
+	assert!(points.iter().any(|point| (point.y - 5.0).abs() < 0.01));
+	assert!(points.iter().all(|point| point.y <= 5.0 + 1e-4));
+
+	let path = PathBuilder::default().cubic_to(vec2(0.0, 0.0), vec2(0.0, 10.0), vec2(10.0, 10.0)).build();
+	assert_eq!(path.contours[0].points[0], vec2(0.0, 0.0));
+	assert_eq!(*path.contours[0].points.last().unwrap(), vec2(10.0, 10.0));
+
+	// A whole circle covers its area up to the tolerance
+	let path = PathBuilder::default().arc(vec2(0.0, 0.0), 10.0, 0.0, TAU).close().build();
+	let circle = fill_area(&path, FillRule::NonZero);
+	assert!(circle < PI * 100.0 && circle > PI * 100.0 - 2.0 * PI * 10.0 * 0.1);
+
+	// Arcs go counter-clockwise for positive sweeps
+	let path = PathBuilder::default().elliptical_arc(vec2(0.0, 0.0), vec2(2.0, 1.0), FRAC_PI_2, 0.0, FRAC_PI_2).build();
+	let points = &path.contours[0].points;
+	assert!(points[0].abs_diff_eq(vec2(0.0, 2.0), 1e-5));
+	assert!(points.last().unwrap().abs_diff_eq(vec2(-1.0, 0.0), 1e-5));
+}
+
+#[test]
+fn fills_concave_polygons() {
+	// An L shape
+	let path = PathBuilder::default().polygon(&[vec2(0.0, 0.0), vec2(4.0, 0.0), vec2(4.0, 1.0), vec2(1.0, 1.0), vec2(1.0, 3.0), vec2(0.0, 3.0)]).build();
+	let (positions, indices) = path.tessellate_fill(FillRule::NonZero);
+	assert!((area(&positions, &indices) - 6.0).abs() < 1e-4);
+	assert!(positions.iter().all(|position| position.x <= 1.0 || position.y <= 1.0));
+
+	// Neighbouring trapezoids share their corners
+	assert!(positions.len() < indices.len());
+}
+
+#[test]
+fn cuts_holes_by_the_fill_rule() {
+	let mut builder = PathBuilder::default();
+	square(&mut builder, vec2(0.0, 0.0), 10.0, false);
+	square(&mut builder, vec2(2.0, 2.0), 4.0, false);
+	let same_direction = builder.build();
+	square(&mut builder, vec2(0.0, 0.0), 10.0, false);
+	square(&mut builder, vec2(2.0, 2.0), 4.0, true);
+	let opposite_direction = builder.build();
+
+	assert!((fill_area(&same_direction, FillRule::NonZero) - 100.0).abs() < 1e-3);
+	assert!((fill_area(&same_direction, FillRule::EvenOdd) - 84.0).abs() < 1e-3);
+	assert!((fill_area(&opposite_direction, FillRule::NonZero) - 84.0).abs() < 1e-3);
+	assert!((fill_area(&opposite_direction, FillRule::EvenOdd) - 84.0).abs() < 1e-3);
+}
+
+#[test]
+fn fills_self_intersecting_paths() {
+	// A pentagram, with its center going around twice
+	let star: Vec<Vec2> = (0..5).map(|i| Vec2::from_angle(FRAC_PI_2 + TAU * (i * 2) as f32 / 5.0) * 10.0).collect();
+	let path = PathBuilder::default().polygon(&star).build();
+
+	let (non_zero, even_odd) = (fill_area(&path, FillRule::NonZero), fill_area(&path, FillRule::EvenOdd));
+	assert!(non_zero > even_odd);
+
+	// The inner pentagon's radius is the outer one's scaled by cos(72°) / cos(36°)
+	let pentagon = |radius: f32| 2.5 * radius * radius * (TAU / 5.0).sin();
+	let inner = pentagon(10.0 * (TAU / 5.0).cos() / (PI / 5.0).cos());
+	assert!((non_zero - even_odd - inner).abs() < 1e-2);
+}
+
+#[test]
+fn fills_and_strokes_meshes() {
+	let path = PathBuilder::default().polygon(&[vec2(0.0, 0.0), vec2(4.0, 0.0), vec2(4.0, 2.0), vec2(0.0, 2.0)]).build();
+	let mesh = path.fill(FillRule::NonZero, RED);
+	assert!(mesh.vertices.iter().all(|vertex| vertex.color == RED));
+	for vertex in mesh.vertices.iter() {
+		let expected = vec2(vertex.position.x / 4.0, 1.0 - vertex.position.y / 2.0);
+		assert_eq!(vertex.uv, expected);
+	}
+
+	let stroke = path.stroke(&StrokeStyle::new(1.0), WHITE);
+	assert_eq!(stroke.indices.len(), 4 * 6 + 4 * 6);
+
+	let mut backend = RenderingBackend::from_backend(Box::new(RecordingBackend::new()));
+	backend.set_screen_size(Some((800.0, 600.0)));
+	backend.fill_path(&path, FillRule::EvenOdd, RED).unwrap();
+	backend.stroke_path(&path, &StrokeStyle::new(1.0), WHITE).unwrap();
+	assert_eq!(backend.draw_calls()[0].indices_count, mesh.indices.len() + stroke.indices.len());
+}
@@ -0,0 +1,77 @@
+use glam::{vec2, vec3, Mat4};
+use miniquad::{PipelineParams, ShaderSource, UniformType};
+use quadify::color::WHITE;
+use quadify::prelude::*;
+
+mod common;
+use common::recording_backend;
+
+const SHADER: ShaderSource = ShaderSource::Glsl { vertex: "", fragment: "" };
+
+fn pipeline(backend: &mut RenderingBackend) -> GlPipeline {
+	backend.make_pipeline(SHADER, PipelineParams::default(), vec![("Tint".to_owned(), UniformType::Float4)], vec![]).unwrap()
+}
+
+fn triangle() -> (Vec<Vertex>, Vec<u16>) {
+	let vertices = vec![
+		Vertex::new(vec3(0.0, 0.0, 0.0), vec2(0.0, 0.0), WHITE),
+		Vertex::new(vec3(1.0, 0.0, 0.0), vec2(1.0, 0.0), WHITE),
+		Vertex::new(vec3(0.0, 1.0, 0.0), vec2(0.0, 1.0), WHITE),
+	];
+	(vertices, vec![0, 1, 2])
+}
+
+#[test]
+fn creates_more_than_32_pipelines() {
+	let (mut backend, _calls) = recording_backend();
+
+	let pipelines: Vec<_> = (0..100).map(|_| pipeline(&mut backend)).collect();
+
+	for pipeline in pipelines {
+		backend.set_uniform(pipeline, "Tint", [1.0f32; 4]).unwrap();
+	}
+}
+
+#[test]
+fn deleted_pipeline_handle_is_rejected() {
+	let (mut backend, calls) = recording_backend();
+	let pipeline = pipeline(&mut backend);
+
+	backend.delete_pipeline(pipeline).unwrap();
+
+	assert_eq!(backend.set_uniform(pipeline, "Tint", [1.0f32; 4]), Err(PipelineError::InvalidHandle(pipeline)));
+	assert_eq!(backend.delete_pipeline(pipeline), Err(PipelineError::InvalidHandle(pipeline)));
+
+	let deletes: Vec<_> = calls.take().into_iter().filter(|call| matches!(call, RecordedCall::DeletePipeline(_) | RecordedCall::DeleteShader(_))).collect();
+	assert_eq!(deletes.len(), 2);
+}
+
+#[test]
+fn reused_slot_gets_a_new_handle() {
+	let (mut backend, _calls) = recording_backend();
+	let old = pipeline(&mut backend);
+	backend.delete_pipeline(old).unwrap();
+
+	let new = pipeline(&mut backend);
+
+	assert_ne!(old, new);
+	assert!(backend.set_uniform(new, "Tint", [1.0f32; 4]).is_ok());
+	assert!(backend.set_uniform(old, "Tint", [1.0f32; 4]).is_err());
+}
+
+#[test]
+fn geometry_with_a_deleted_pipeline_fails() {
+	let (mut backend, calls) = recording_backend();
+	let (vertices, indices) = triangle();
+	let pipeline = pipeline(&mut backend);
+
+	backend.pipeline(Some(pipeline));
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.delete_pipeline(pipeline).unwrap();
+
+	assert_eq!(backend.geometry(&vertices, &indices), Err(GeometryError::Pipeline(PipelineError::InvalidHandle(pipeline))));
+
+	// the already queued draw call is skipped
+	backend.draw(Mat4::IDENTITY);
+	assert!(!calls.take().iter().any(|call| matches!(call, RecordedCall::Draw { .. })));
+}
@@ -0,0 +1,135 @@
+use glam::{vec2, vec3, Mat4};
+use miniquad::BufferType;
+use quadify::color::WHITE;
+use quadify::prelude::*;
+
+mod common;
+use common::recording_backend;
+
+fn quad() -> (Vec<Vertex>, Vec<u16>) {
+	let vertices = vec![
+		Vertex::new(vec3(0.0, 0.0, 0.0), vec2(0.0, 0.0), WHITE),
+		Vertex::new(vec3(1.0, 0.0, 0.0), vec2(1.0, 0.0), WHITE),
+		Vertex::new(vec3(0.0, 1.0, 0.0), vec2(0.0, 1.0), WHITE),
+		Vertex::new(vec3(1.0, 1.0, 0.0), vec2(1.0, 1.0), WHITE),
+	];
+	(vertices, vec![0, 1, 2, 1, 2, 3])
+}
+
+fn draws(calls: &[RecordedCall]) -> Vec<i32> {
+	calls
+		.iter()
+		.filter_map(|call| match call {
+			RecordedCall::Draw { num_elements, .. } => Some(*num_elements),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn batches_geometry_with_the_same_state() {
+	let (mut backend, calls) = recording_backend();
+	let (vertices, indices) = quad();
+
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.draw(Mat4::IDENTITY);
+
+	let calls = calls.take();
+	assert_eq!(draws(&calls), vec![12]);
+
+	let new_buffers: Vec<_> = calls
+		.iter()
+		.filter_map(|call| match call {
+			RecordedCall::NewBuffer { buffer_type, .. } => Some(*buffer_type),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(new_buffers, vec![BufferType::VertexBuffer, BufferType::IndexBuffer]);
+	assert_eq!(calls.iter().filter(|call| matches!(call, RecordedCall::BufferUpdate { .. })).count(), 2);
+	assert_eq!(calls.iter().filter(|call| matches!(call, RecordedCall::ApplyPipeline(_))).count(), 1);
+}
+
+#[test]
+fn breaks_batching_on_texture_change() {
+	let (mut backend, calls) = recording_backend();
+	let (vertices, indices) = quad();
+	let texture = backend.new_texture_from_rgba8(1, 1, &[255, 255, 255, 255]);
+
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.texture(Some(&texture));
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.draw(Mat4::IDENTITY);
+
+	let calls = calls.take();
+	assert_eq!(draws(&calls), vec![6, 6]);
+
+	let bound_textures: Vec<_> = calls
+		.iter()
+		.filter_map(|call| match call {
+			RecordedCall::ApplyBindings { images, .. } => Some(images[0]),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(bound_textures.len(), 2);
+	assert_ne!(bound_textures[0], texture);
+	assert_eq!(bound_textures[1], texture);
+}
+
+#[test]
+fn flips_scissor_rect_to_framebuffer_space() {
+	let (mut backend, calls) = recording_backend();
+	let (vertices, indices) = quad();
+
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.scissor(Some((10, 20, 30, 40)));
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.draw(Mat4::IDENTITY);
+
+	let scissors: Vec<_> = calls
+		.take()
+		.into_iter()
+		.filter(|call| matches!(call, RecordedCall::ApplyScissorRect { .. }))
+		.collect();
+	assert_eq!(
+		scissors,
+		vec![
+			RecordedCall::ApplyScissorRect { x: 0, y: 0, w: 800, h: 600 },
+			RecordedCall::ApplyScissorRect { x: 10, y: 540, w: 30, h: 40 },
+		]
+	);
+}
+
+#[test]
+fn begins_and_ends_a_pass_per_draw_call() {
+	let (mut backend, calls) = recording_backend();
+	let (vertices, indices) = quad();
+	let texture = backend.new_render_texture(miniquad::TextureParams {
+		width: 64,
+		height: 32,
+		..Default::default()
+	});
+	let render_pass = backend.new_render_pass(texture, None);
+
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.render_pass(Some(render_pass));
+	backend.geometry(&vertices, &indices).unwrap();
+	backend.draw(Mat4::IDENTITY);
+
+	let passes: Vec<_> = calls
+		.take()
+		.into_iter()
+		.filter(|call| matches!(call, RecordedCall::BeginPass(_) | RecordedCall::EndPass | RecordedCall::ApplyViewport { .. }))
+		.collect();
+	assert_eq!(
+		passes,
+		vec![
+			RecordedCall::BeginPass(None),
+			RecordedCall::ApplyViewport { x: 0, y: 0, w: 800, h: 600 },
+			RecordedCall::EndPass,
+			RecordedCall::BeginPass(Some(render_pass)),
+			RecordedCall::ApplyViewport { x: 0, y: 0, w: 64, h: 32 },
+			RecordedCall::EndPass,
+		]
+	);
+}
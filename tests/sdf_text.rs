@@ -0,0 +1,113 @@
+use bevy_asset::{AssetServer, Assets, Handle, LoadState};
+use glam::{vec2, vec4, Vec2};
+use quadify::asset::{BmFont, DistanceField, DistanceFieldType, Font, Texture};
+use quadify::color::{BLACK, WHITE};
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+/// Text descriptor as written by msdf-bmfont
+const FNT: &str = r#"info face="Sans" size=32
+common lineHeight=40 base=24 scaleW=64 scaleH=64 pages=1
+page id=0 file="sans.png"
+distanceField fieldType=msdf distanceRange=4
+char id=65 x=1 y=0 width=14 height=28 xoffset=2 yoffset=0 xadvance=16 page=0
+"#;
+
+/// msdf-atlas-gen output, with the glyph bounds given bottom up
+const ATLAS: &str = r#"{
+	"atlas": { "type": "mtsdf", "distanceRange": 4, "size": 32, "width": 64, "height": 128, "yOrigin": "bottom" },
+	"name": "Sans",
+	"metrics": { "emSize": 1, "lineHeight": 1.25, "ascender": 0.75, "descender": -0.25, "underlineY": -0.1, "underlineThickness": 0.05 },
+	"glyphs": [
+		{ "unicode": 32, "advance": 0.25 },
+		{
+			"unicode": 65,
+			"advance": 0.5,
+			"planeBounds": { "left": 0.0625, "bottom": -0.125, "right": 0.5, "top": 0.75 },
+			"atlasBounds": { "left": 1, "bottom": 100, "right": 15, "top": 128 }
+		}
+	],
+	"kerning": [{ "unicode1": 65, "unicode2": 65, "advance": -0.125 }]
+}"#;
+
+#[test]
+fn parses_distance_fields_of_bmfont_descriptors() {
+	let font = BmFont::from_slice(FNT.as_bytes()).unwrap();
+	assert_eq!(font.distance_field, Some(DistanceField { field_type: DistanceFieldType::Msdf, range: 4.0 }));
+	assert_eq!(BmFont::from_slice(FNT.replace("distanceField fieldType=msdf distanceRange=4\n", "").as_bytes()).unwrap().distance_field, None);
+	assert!(BmFont::from_slice(FNT.replace("fieldType=msdf", "fieldType=vector").as_bytes()).is_err());
+}
+
+#[test]
+fn converts_atlas_metrics_to_pixels() {
+	let font = BmFont::from_sdf_atlas(ATLAS.as_bytes(), "sans.sdf.png".to_string()).unwrap();
+	assert_eq!((font.size, font.line_height, font.base), (32.0, 40.0, 24.0));
+	assert_eq!(font.page_size, vec2(64.0, 128.0));
+	assert_eq!(font.pages, vec!["sans.sdf.png".to_string()]);
+	assert_eq!(font.distance_field, Some(DistanceField { field_type: DistanceFieldType::Mtsdf, range: 4.0 }));
+
+	let glyph = font.glyphs[&'A'];
+	assert_eq!(glyph.rect, vec4(1.0, 0.0, 14.0, 28.0));
+	// The top of `A` is at the ascender, so right at the top of the line
+	assert_eq!((glyph.offset, glyph.advance), (vec2(2.0, 0.0), 16.0));
+	assert_eq!(font.glyphs[&' '].rect, vec4(0.0, 0.0, 0.0, 0.0));
+	assert_eq!(font.kerning[&('A', 'A')], -4.0);
+
+	// The same glyph with the origin at the top
+	let top_down = ATLAS.replace("\"bottom\" }", "\"top\" }").replace(r#""bottom": -0.125, "right": 0.5, "top": 0.75"#, r#""bottom": 0.125, "right": 0.5, "top": -0.75"#).replace(r#""bottom": 100, "right": 15, "top": 128"#, r#""bottom": 28, "right": 15, "top": 0"#);
+	let font = BmFont::from_sdf_atlas(top_down.as_bytes(), String::new()).unwrap();
+	assert_eq!((font.glyphs[&'A'].rect, font.glyphs[&'A'].offset), (vec4(1.0, 0.0, 14.0, 28.0), vec2(2.0, 0.0)));
+
+	assert!(BmFont::from_sdf_atlas(b"{}", String::new()).is_err());
+}
+
+#[test]
+fn draws_text_with_the_sdf_material_uniforms() {
+	let mut app = headless_app();
+	let id = app.world.non_send_resource_mut::<RenderingBackend>().new_texture_from_rgba8(64, 64, &[255; 64 * 64 * 4]);
+	let page = app.world.resource_mut::<Assets<Texture>>().add(Texture::new(id));
+	let font = BmFont::from_slice(FNT.as_bytes()).unwrap().into_font(vec![page]);
+
+	let world = app.world.cell();
+	let mut backend = world.non_send_resource_mut::<RenderingBackend>();
+	let material = backend.request_sdf_material().unwrap();
+	let style = SdfStyle {
+		outline_color: BLACK,
+		outline_width: 0.25,
+		shadow_offset: vec2(2.0, -4.0),
+		..Default::default()
+	};
+	backend.set_sdf_style(&material, &font, &style).unwrap();
+	backend.material_set_uniform(&material, "ShadowSoftness", 0.5f32).unwrap();
+
+	backend.set_material(&material);
+	backend.draw_text(&font, &world.resource::<Assets<Texture>>(), "AA", Vec2::ZERO, 4.0, WHITE).unwrap();
+
+	let draw = &backend.draw_calls()[0];
+	assert_eq!((draw.indices_count, draw.texture), (12, Some(id)));
+
+	// The custom uniforms follow the projection, model and time
+	let floats: Vec<f32> = draw.uniforms.as_ref().unwrap()[144..].chunks(4).map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap())).collect();
+	assert_eq!(floats[0..4], [0.0, 0.0, 0.0, 1.0]);
+	assert_eq!(floats[4..8], [0.0; 4]);
+	// The shadow offset is in texture coordinates, which point down
+	assert_eq!(floats[8..12], [2.0 / 64.0, 4.0 / 64.0, 0.25, 0.5]);
+}
+
+#[test]
+fn loads_through_the_asset_server() {
+	let mut app = headless_app();
+	let handle: Handle<Font> = app.world.resource::<AssetServer>().load("tests/sans.sdf.json");
+	for _ in 0..4 {
+		app.run_frame();
+	}
+	assert_eq!(app.world.resource::<AssetServer>().load_state(&handle), LoadState::Loaded);
+
+	// The atlas image with the same name is uploaded as the only page
+	let font = app.world.resource::<Assets<Font>>().get(&handle).unwrap();
+	assert_eq!(font.page_size, vec2(64.0, 128.0));
+	assert_eq!(font.pages.len(), 1);
+	assert!(app.world.resource::<Assets<Texture>>().contains(&font.pages[0]));
+}
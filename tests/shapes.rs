@@ -0,0 +1,130 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy_app::*;
+use bevy_ecs::system::RunSystemOnce;
+use glam::{vec2, vec4, Vec2};
+use quadify::color::{RED, WHITE};
+use quadify::prelude::*;
+
+mod common;
+use common::recording_backend;
+
+/// Vertices of the draw call, with positions rounded to get rid of the rotation error
+fn vertices(backend: &RenderingBackend, draw_call: usize) -> Vec<Vertex> {
+	let mut vertices = backend.draw_calls()[draw_call].vertices::<Vertex>().unwrap();
+	for vertex in vertices.iter_mut() {
+		vertex.position = (vertex.position * 1000.0).round() / 1000.0;
+	}
+	vertices
+}
+
+fn positions(backend: &RenderingBackend, draw_call: usize) -> Vec<Vec2> {
+	vertices(backend, draw_call).iter().map(|vertex| vertex.position.truncate()).collect()
+}
+
+#[test]
+fn batches_shapes_into_one_draw_call() {
+	let (mut backend, _) = recording_backend();
+	backend.draw_rectangle(vec2(0.0, 0.0), vec2(10.0, 5.0), RED).unwrap();
+	backend.draw_circle(vec2(20.0, 0.0), 4.0, WHITE).unwrap();
+	backend.draw_line(vec2(0.0, 0.0), vec2(10.0, 10.0), 2.0, WHITE).unwrap();
+	backend.draw_triangle(vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(0.0, 1.0), WHITE).unwrap();
+	backend.draw_poly(vec2(0.0, 0.0), 6, 3.0, 0.0, WHITE).unwrap();
+
+	let draw_calls = backend.draw_calls();
+	assert_eq!(draw_calls.len(), 1);
+	// Rectangle, 32-sided circle, line, triangle and hexagon
+	assert_eq!(draw_calls[0].indices_count, 6 + 32 * 3 + 6 + 3 + 6 * 3);
+	assert_eq!(draw_calls[0].texture, None);
+}
+
+#[test]
+fn rotates_rectangles_around_the_pivot() {
+	let (mut backend, _) = recording_backend();
+	backend.draw_rectangle_ex(vec2(0.0, 0.0), vec2(2.0, 1.0), &DrawRectangleParams { rotation: FRAC_PI_2, pivot: Some(Vec2::ZERO), color: RED }).unwrap();
+	// Rotating around the center by default
+	backend.draw_rectangle_ex(vec2(0.0, 0.0), vec2(2.0, 1.0), &DrawRectangleParams { rotation: FRAC_PI_2, ..Default::default() }).unwrap();
+
+	assert_eq!(
+		positions(&backend, 0),
+		vec![
+			vec2(-1.0, 0.0),
+			vec2(-1.0, 2.0),
+			vec2(0.0, 0.0),
+			vec2(0.0, 2.0),
+			vec2(0.5, -0.5),
+			vec2(0.5, 1.5),
+			vec2(1.5, -0.5),
+			vec2(1.5, 1.5),
+		]
+	);
+	assert_eq!(vertices(&backend, 0)[0].color, RED);
+}
+
+#[test]
+fn centers_outlines_on_the_edges() {
+	let (mut backend, _) = recording_backend();
+	backend.draw_rectangle_lines(vec2(0.0, 0.0), vec2(4.0, 2.0), 1.0, WHITE).unwrap();
+
+	assert_eq!(backend.draw_calls()[0].indices_count, 4 * 6);
+	let corners = positions(&backend, 0);
+	assert_eq!(corners.len(), 8);
+	// Both sides of the mitered corners
+	for corner in [vec2(-0.5, -0.5), vec2(0.5, 0.5), vec2(4.5, 2.5), vec2(3.5, 1.5), vec2(-0.5, 2.5), vec2(4.5, -0.5)] {
+		assert!(corners.contains(&corner), "{:?} isn't in {:?}", corner, corners);
+	}
+
+	// Sharp corners are cut at the miter limit
+	backend.draw_triangle_lines(vec2(0.0, 0.0), vec2(100.0, 0.0), vec2(0.0, 1.0), 1.0, WHITE).unwrap();
+	assert!(positions(&backend, 0).iter().all(|position| position.x <= 102.0));
+}
+
+#[test]
+fn draws_texture_regions() {
+	let (mut backend, _) = recording_backend();
+	let texture = backend.new_texture_from_rgba8(8, 4, &[255; 8 * 4 * 4]);
+
+	backend.draw_rectangle(vec2(0.0, 0.0), vec2(1.0, 1.0), WHITE).unwrap();
+	backend
+		.draw_texture_ex(
+			texture,
+			vec2(10.0, 0.0),
+			WHITE,
+			&DrawTextureParams {
+				source: Some(vec4(2.0, 0.0, 4.0, 4.0)),
+				dest_size: Some(vec2(8.0, 8.0)),
+				flip_x: true,
+				..Default::default()
+			},
+		)
+		.unwrap();
+	backend.draw_texture(texture, vec2(0.0, 0.0), WHITE).unwrap();
+
+	let draw_calls = backend.draw_calls();
+	assert_eq!(draw_calls.iter().map(|dc| (dc.texture, dc.indices_count)).collect::<Vec<_>>(), vec![(None, 6), (Some(texture), 12)]);
+
+	let vertices = vertices(&backend, 1);
+	assert_eq!((vertices[0].position.truncate(), vertices[0].uv), (vec2(10.0, 8.0), vec2(0.75, 0.0)));
+	assert_eq!((vertices[3].position.truncate(), vertices[3].uv), (vec2(18.0, 0.0), vec2(0.25, 1.0)));
+	// The whole texture at its size
+	assert_eq!((vertices[7].position.truncate(), vertices[7].uv), (vec2(8.0, 0.0), vec2(1.0, 1.0)));
+}
+
+#[test]
+fn shapes_draw_from_systems() {
+	let mut app = App::new();
+	app.add_plugins(QuadifyPlugins.build().disable::<WindowPlugin>().add(HeadlessPlugin {
+		width: 320,
+		height: 240,
+		frames: None,
+	}));
+	app.run_frame();
+
+	app.world.run_system_once(|mut shapes: Shapes| {
+		shapes.draw_circle_lines(vec2(0.0, 0.0), 10.0, 1.0, WHITE).unwrap();
+		shapes.draw_poly_lines(vec2(0.0, 0.0), 3, 10.0, 0.0, 1.0, WHITE).unwrap();
+	});
+
+	let backend = app.world.non_send_resource::<RenderingBackend>();
+	assert_eq!(backend.draw_calls().last().unwrap().indices_count, (32 + 3) * 6);
+}
@@ -0,0 +1,101 @@
+use bevy_app::*;
+use bevy_asset::{Assets, Handle};
+use glam::{vec2, vec3, vec4, Vec2};
+use quadify::asset::Texture;
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+fn texture(app: &mut App, width: u16, height: u16) -> (Handle<Texture>, miniquad::TextureId) {
+	let pixels = vec![255; width as usize * height as usize * 4];
+	let id = app.world.non_send_resource_mut::<RenderingBackend>().new_texture_from_rgba8(width, height, &pixels);
+	(app.world.resource_mut::<Assets<Texture>>().add(Texture::new(id)), id)
+}
+
+fn draws(app: &App) -> Vec<(i32, miniquad::TextureId)> {
+	let calls = app.world.non_send_resource::<RecordedCalls>().get();
+	let textures = calls.iter().filter_map(|call| match call {
+		RecordedCall::ApplyBindings { images, .. } => Some(images[0]),
+		_ => None,
+	});
+	let elements = calls.iter().filter_map(|call| match call {
+		RecordedCall::Draw { num_elements, .. } => Some(*num_elements),
+		_ => None,
+	});
+	elements.zip(textures).collect()
+}
+
+fn positions(sprite: &Sprite, transform: Transform, texture_size: Vec2) -> Vec<Vec2> {
+	sprite.vertices(&GlobalTransform::from(transform), texture_size).iter().map(|vertex| vertex.position.truncate()).collect()
+}
+
+#[test]
+fn batches_sprites_by_texture() {
+	let mut app = headless_app();
+	let (a, a_id) = texture(&mut app, 4, 4);
+	let (b, b_id) = texture(&mut app, 8, 8);
+
+	for i in 0..10 {
+		let texture = if i % 2 == 0 { a.clone() } else { b.clone() };
+		app.world.spawn((Sprite::new(texture), Transform::from_xyz(i as f32, 0.0, 0.0)));
+	}
+	app.run_frame();
+
+	let draws = draws(&app);
+	assert_eq!(draws.len(), 2);
+	assert!(draws.contains(&(30, a_id)));
+	assert!(draws.contains(&(30, b_id)));
+}
+
+#[test]
+fn keeps_z_order_across_textures() {
+	let mut app = headless_app();
+	let (a, a_id) = texture(&mut app, 4, 4);
+	let (b, b_id) = texture(&mut app, 4, 4);
+
+	app.world.spawn((Sprite::new(a.clone()), Transform::from_xyz(0.0, 0.0, 0.0)));
+	app.world.spawn((Sprite::new(b), Transform::from_xyz(0.0, 0.0, 1.0)));
+	app.world.spawn((Sprite::new(a), Transform::from_xyz(0.0, 0.0, 2.0)));
+	app.run_frame();
+
+	assert_eq!(draws(&app), vec![(6, a_id), (6, b_id), (6, a_id)]);
+}
+
+#[test]
+fn sizes_sprites_by_texture_rect_or_custom_size() {
+	let sprite = Sprite::new(Handle::default());
+	assert_eq!(positions(&sprite, Transform::default(), vec2(8.0, 4.0)), vec![vec2(-4.0, 2.0), vec2(4.0, 2.0), vec2(-4.0, -2.0), vec2(4.0, -2.0)]);
+
+	let sprite = Sprite {
+		rect: Some(vec4(2.0, 0.0, 2.0, 2.0)),
+		..Sprite::new(Handle::default())
+	};
+	assert_eq!(positions(&sprite, Transform::default(), vec2(8.0, 4.0)), vec![vec2(-1.0, 1.0), vec2(1.0, 1.0), vec2(-1.0, -1.0), vec2(1.0, -1.0)]);
+	let uvs: Vec<_> = sprite.vertices(&GlobalTransform::IDENTITY, vec2(8.0, 4.0)).iter().map(|vertex| vertex.uv).collect();
+	assert_eq!(uvs, vec![vec2(0.25, 0.0), vec2(0.5, 0.0), vec2(0.25, 0.5), vec2(0.5, 0.5)]);
+
+	let sprite = Sprite {
+		custom_size: Some(vec2(1.0, 1.0)),
+		..sprite
+	};
+	assert_eq!(positions(&sprite, Transform::from_xyz(10.0, 0.0, 0.0), vec2(8.0, 4.0)), vec![vec2(9.5, 0.5), vec2(10.5, 0.5), vec2(9.5, -0.5), vec2(10.5, -0.5)]);
+}
+
+#[test]
+fn applies_anchor_and_flips() {
+	let sprite = Sprite {
+		anchor: Anchor::BottomLeft,
+		flip_x: true,
+		flip_y: true,
+		custom_size: Some(vec2(2.0, 2.0)),
+		..Sprite::new(Handle::default())
+	};
+	let vertices = sprite.vertices(&GlobalTransform::IDENTITY, vec2(4.0, 4.0));
+
+	assert_eq!(vertices[0].position, vec3(0.0, 2.0, 0.0));
+	assert_eq!(vertices[3].position, vec3(2.0, 0.0, 0.0));
+	// The top left corner samples the bottom right of the texture
+	assert_eq!(vertices[0].uv, vec2(1.0, 1.0));
+	assert_eq!(vertices[3].uv, vec2(0.0, 0.0));
+}
@@ -0,0 +1,99 @@
+use glam::{vec2, vec3, Vec2};
+use quadify::color::{RED, WHITE};
+use quadify::prelude::*;
+
+mod common;
+use common::{area, recording_backend};
+
+fn rounded(positions: &[Vec2]) -> Vec<Vec2> {
+	positions.iter().map(|position| (*position * 1000.0).round() / 1000.0).collect()
+}
+
+#[test]
+fn caps_open_polylines() {
+	let points = [vec2(0.0, 0.0), vec2(10.0, 0.0)];
+
+	let (positions, indices) = tessellate_stroke(&points, false, &StrokeStyle::new(2.0));
+	assert_eq!(rounded(&positions), vec![vec2(0.0, 1.0), vec2(10.0, 1.0), vec2(0.0, -1.0), vec2(10.0, -1.0)]);
+	assert_eq!(indices, vec![0, 1, 2, 1, 2, 3]);
+
+	let (positions, _) = tessellate_stroke(&points, false, &StrokeStyle::new(2.0).with_cap(LineCap::Square));
+	assert_eq!(rounded(&positions), vec![vec2(-1.0, 1.0), vec2(11.0, 1.0), vec2(-1.0, -1.0), vec2(11.0, -1.0)]);
+
+	// Two half circles make a whole one
+	let (positions, indices) = tessellate_stroke(&points, false, &StrokeStyle::new(2.0).with_cap(LineCap::Round));
+	assert!((area(&positions, &indices) - 20.0 - std::f32::consts::PI).abs() < 0.05);
+	assert!(positions.iter().all(|position| position.x >= -1.0 - 1e-4 && position.x <= 11.0 + 1e-4));
+}
+
+#[test]
+fn joins_corners() {
+	let points = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0)];
+
+	// The miter reaches the outer corner, and the inner corner is shared by both segments
+	let (positions, indices) = tessellate_stroke(&points, false, &StrokeStyle::new(2.0));
+	let positions = rounded(&positions);
+	assert!(positions.contains(&vec2(11.0, -1.0)));
+	assert!(positions.contains(&vec2(9.0, 1.0)));
+	assert!((area(&positions, &indices) - 40.0).abs() < 1e-3);
+
+	let (positions, indices) = tessellate_stroke(&points, false, &StrokeStyle::new(2.0).with_join(LineJoin::Bevel));
+	assert!(!rounded(&positions).contains(&vec2(11.0, -1.0)));
+	assert!((area(&positions, &indices) - 39.5).abs() < 1e-3);
+
+	let (positions, indices) = tessellate_stroke(&points, false, &StrokeStyle::new(2.0).with_join(LineJoin::Round));
+	assert!((area(&positions, &indices) - 39.0 - std::f32::consts::PI / 4.0).abs() < 0.01);
+
+	// Straight points don't need a join
+	let (_, indices) = tessellate_stroke(&[vec2(0.0, 0.0), vec2(5.0, 0.0), vec2(10.0, 0.0)], false, &StrokeStyle::new(2.0));
+	assert_eq!(indices.len(), 12);
+}
+
+#[test]
+fn bevels_miters_past_the_limit() {
+	let points = [vec2(0.0, 0.0), vec2(100.0, 0.0), vec2(0.0, 1.0)];
+	let (positions, _) = tessellate_stroke(&points, false, &StrokeStyle::new(2.0));
+	assert!(positions.iter().all(|position| position.x <= 101.0 + 1e-3));
+
+	let (positions, _) = tessellate_stroke(&points, false, &StrokeStyle::new(2.0).with_miter_limit(1000.0));
+	assert!(positions.iter().any(|position| position.x > 150.0));
+}
+
+#[test]
+fn closes_loops_without_caps() {
+	let square = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0), vec2(0.0, 10.0)];
+	let style = StrokeStyle::new(2.0).with_cap(LineCap::Round);
+	let (positions, indices) = tessellate_stroke(&square, true, &style);
+
+	// Four segments and four mitered joins, covering the ring between the 12 and 8 units wide squares
+	assert_eq!(indices.len(), 4 * 6 + 4 * 6);
+	assert!((area(&positions, &indices) - (144.0 - 64.0)).abs() < 1e-3);
+	let positions = rounded(&positions);
+	assert!(positions.iter().all(|position| position.x >= -1.0 && position.x <= 11.0 && position.y >= -1.0 && position.y <= 11.0));
+
+	// Repeating the first point doesn't add a segment
+	let repeated: Vec<Vec2> = square.iter().chain(&square[..1]).copied().collect();
+	assert_eq!(tessellate_stroke(&repeated, true, &style).1, indices);
+
+	assert!(tessellate_stroke(&square[..1], true, &style).1.is_empty());
+}
+
+#[test]
+fn draws_polylines_and_builds_meshes() {
+	let points = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0)];
+	let style = StrokeStyle::new(2.0).with_join(LineJoin::Round).with_cap(LineCap::Round);
+	let (positions, indices) = tessellate_stroke(&points, false, &style);
+
+	let (mut backend, _) = recording_backend();
+	backend.draw_rectangle(vec2(0.0, 0.0), vec2(1.0, 1.0), WHITE).unwrap();
+	backend.draw_polyline(&points, false, &style, RED).unwrap();
+	let draw_calls = backend.draw_calls();
+	assert_eq!(draw_calls.len(), 1);
+	assert_eq!(draw_calls[0].indices_count, 6 + indices.len());
+
+	let mesh = MeshBuilder::default().as_polyline(&points, false, style).at_position(vec3(5.0, 5.0, 1.0)).with_color(RED).build().unwrap();
+	assert_eq!(mesh.indices, indices);
+	assert_eq!(mesh.vertices.len(), positions.len());
+	assert_eq!(mesh.vertices[0].position, (positions[0] + vec2(5.0, 5.0)).extend(1.0));
+	assert!(mesh.vertices.iter().all(|vertex| vertex.color == RED));
+}
@@ -0,0 +1,184 @@
+use bevy_asset::{AssetServer, Assets, Handle, LoadState};
+use glam::{vec2, Vec2};
+use quadify::asset::{Svg, SvgFile};
+use quadify::color::Rgba;
+use quadify::prelude::*;
+
+mod common;
+use common::{headless_app, mesh_area};
+
+fn bounds(mesh: &Mesh) -> (Vec2, Vec2) {
+	mesh.vertices.iter().fold((Vec2::MAX, Vec2::MIN), |(min, max), vertex| (min.min(vertex.position.truncate()), max.max(vertex.position.truncate())))
+}
+
+fn close(a: Vec2, b: Vec2) -> bool {
+	a.abs_diff_eq(b, 1e-3)
+}
+
+#[test]
+fn flips_the_view_box_into_meshes() {
+	let svg = r##"<?xml version="1.0" encoding="UTF-8"?>
+	<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
+	<!-- Exported icon -->
+	<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="10 10 20 10">
+		<title>Icon &amp; more</title>
+		<rect id="corner" x="10" y="10" width="2" height="1" fill="#f00"/>
+	</svg>"##;
+	let file = SvgFile::from_slice(svg.as_bytes()).unwrap();
+	assert_eq!(file.size, vec2(200.0, 100.0));
+	assert_eq!(file.shapes.len(), 1);
+
+	// The top left corner of the view box ends up at the top left of the size, with y pointing up
+	let shape = &file.shapes[0];
+	assert_eq!(shape.id.as_deref(), Some("corner"));
+	let (min, max) = bounds(&shape.mesh);
+	assert!(close(min, vec2(0.0, 90.0)) && close(max, vec2(20.0, 100.0)), "{:?} {:?}", min, max);
+	assert!(shape.mesh.vertices.iter().all(|vertex| vertex.color == Rgba::new(255, 0, 0, 255)));
+}
+
+#[test]
+fn fills_and_strokes_basic_shapes() {
+	let svg = r#"<svg viewBox="0 0 100 100">
+		<circle cx="50" cy="50" r="10" fill="none" stroke="blue" stroke-width="2"/>
+		<ellipse cx="50" cy="50" rx="20" ry="10"/>
+		<polygon points="0,0 10,0 10,10" fill="rgb(0, 128, 255)" fill-opacity="0.5"/>
+		<polyline points="0 0 5 5 10 0" fill="none" stroke="black"/>
+		<line x1="0" y1="0" x2="10" y2="0" stroke="black" stroke-width="4" stroke-linecap="square"/>
+		<rect width="10" height="10" rx="2" style="fill: #00ff0080; stroke: none"/>
+		<text x="0" y="0">Skipped</text>
+	</svg>"#;
+	let file = SvgFile::from_slice(svg.as_bytes()).unwrap();
+	assert_eq!(file.shapes.len(), 6);
+	let shapes: Vec<&Mesh> = file.shapes.iter().map(|shape| &shape.mesh).collect();
+
+	// A ring 2 units wide around the circle
+	assert!((mesh_area(shapes[0]) - std::f32::consts::PI * (11.0 * 11.0 - 9.0 * 9.0)).abs() < 1.0);
+	assert_eq!(shapes[0].vertices[0].color, Rgba::new(0, 0, 255, 255));
+	// Flattened curves stay inside of the ellipse, within the tolerance
+	assert!(mesh_area(shapes[1]) < std::f32::consts::PI * 200.0 && mesh_area(shapes[1]) > std::f32::consts::PI * 200.0 - 10.0);
+	assert_eq!(shapes[1].vertices[0].color, Rgba::new(0, 0, 0, 255));
+
+	assert!((mesh_area(shapes[2]) - 50.0).abs() < 1e-3);
+	assert_eq!(shapes[2].vertices[0].color, Rgba::new(0, 128, 255, 128));
+
+	let (min, max) = bounds(shapes[4]);
+	assert!(close(min, vec2(-2.0, 98.0)) && close(max, vec2(12.0, 102.0)), "{:?} {:?}", min, max);
+
+	// Rounded corners cut into the square
+	assert!(mesh_area(shapes[5]) < 100.0 && mesh_area(shapes[5]) > 100.0 - 4.0 * 4.0);
+	assert_eq!(shapes[5].vertices[0].color, Rgba::new(0, 255, 0, 128));
+}
+
+#[test]
+fn applies_group_transforms_and_inherited_styles() {
+	let svg = r#"<svg width="100" height="100">
+		<g transform="translate(50, 50)" fill="red" opacity="0.5">
+			<g transform="scale(2) rotate(90)">
+				<rect id="rotated" width="10" height="5"/>
+			</g>
+			<rect id="hidden" width="10" height="10" display="none"/>
+			<rect id="blue" width="1" height="1" fill="blue" opacity="0.5"/>
+		</g>
+	</svg>"#;
+	let file = SvgFile::from_slice(svg.as_bytes()).unwrap();
+	assert_eq!(file.shapes.iter().map(|shape| shape.id.as_deref().unwrap()).collect::<Vec<_>>(), vec!["rotated", "blue"]);
+
+	// Rotating 90° clockwise on screen puts the rectangle left of the translation, 10 wide and 20 tall
+	let (min, max) = bounds(&file.shapes[0].mesh);
+	assert!(close(min, vec2(40.0, 30.0)) && close(max, vec2(50.0, 50.0)), "{:?} {:?}", min, max);
+	assert_eq!(file.shapes[0].mesh.vertices[0].color, Rgba::new(255, 0, 0, 128));
+	assert_eq!(file.shapes[1].mesh.vertices[0].color, Rgba::new(0, 0, 255, 64));
+
+	let mesh = file.mesh();
+	assert_eq!(mesh.vertices.len(), file.shapes.iter().map(|shape| shape.mesh.vertices.len()).sum::<usize>());
+	assert!(mesh.indices.iter().all(|index| (*index as usize) < mesh.vertices.len()));
+}
+
+#[test]
+fn parses_path_data() {
+	// Compact path data, with implicit commands, relative coordinates and packed numbers
+	let svg = r#"<svg width="100" height="100">
+		<path d="M0 0h10v10H0z" />
+		<path d="M20,20 l10-0 0 10-10.0.0Z m2 2 l6 0 0 6-6 0z" fill-rule="evenodd"/>
+		<path d="M0 50 C 0 40 10 40 10 50 S 20 60 20 50 Q 25 40 30 50 T 40 50 Z" />
+		<path d="M60 60 a10 10 0 1 0 20 0 a10 10 0 1 0-20 0z" />
+		<path d="M0 0 L 10 10 X 5" />
+	</svg>"#;
+	let file = SvgFile::from_slice(svg.as_bytes()).unwrap();
+	assert_eq!(file.shapes.len(), 5);
+
+	assert!((mesh_area(&file.shapes[0].mesh) - 100.0).abs() < 1e-3);
+	// The second subpath starts relative to the end of the closed one, cutting a hole into it
+	assert!((mesh_area(&file.shapes[1].mesh) - 64.0).abs() < 1e-3);
+
+	// Smooth curves mirror the previous control point, so the wave ends where it started
+	let (min, max) = bounds(&file.shapes[2].mesh);
+	assert!(min.x.abs() < 1e-3 && (max.x - 40.0).abs() < 1e-3);
+	assert!(min.y > 100.0 - 60.0 - 1e-3 && max.y < 100.0 - 40.0 + 1e-3);
+
+	// Two half circle arcs make a whole circle
+	let circle = &file.shapes[3].mesh;
+	assert!(mesh_area(circle) < std::f32::consts::PI * 100.0 && mesh_area(circle) > std::f32::consts::PI * 100.0 - 5.0);
+	let (min, max) = bounds(circle);
+	assert!(close(min, vec2(60.0, 30.0)) && close(max, vec2(80.0, 50.0)), "{:?} {:?}", min, max);
+
+	// Broken path data keeps the path up to the error
+	assert_eq!(file.shapes[4].id, None);
+}
+
+#[test]
+fn rejects_malformed_documents() {
+	assert!(SvgFile::from_slice(b"<html></html>").is_err());
+	assert!(SvgFile::from_slice(b"<svg><rect width=10/></svg>").is_err());
+	assert!(SvgFile::from_slice(br#"<svg><g transform="spin(3)"/></svg>"#).is_err());
+	assert!(SvgFile::from_slice(b"no markup").is_err());
+}
+
+#[test]
+fn skips_unsupported_paints_and_lengths() {
+	let svg = r#"<svg viewBox="0 0 100 100">
+		<rect width="10" height="10" fill="gold" stroke="hsl(120, 100%, 25%)" stroke-width="1em"/>
+		<rect width="10" height="10" fill="bogus" stroke="DarkGray" stroke-width="3pt"/>
+		<rect width="10" height="10" fill="url(#gradient)" stroke="hsla(0, 0%, 100%, 0.5)" stroke-width="50%"/>
+	</svg>"#;
+	let file = SvgFile::from_slice(svg.as_bytes()).unwrap();
+	assert_eq!(file.shapes.len(), 3);
+	let shapes: Vec<&Mesh> = file.shapes.iter().map(|shape| &shape.mesh).collect();
+
+	// The stroke keeps its default width of 1 when its own can't be used
+	assert_eq!(shapes[0].vertices[0].color, Rgba::new(255, 215, 0, 255));
+	assert!(shapes[0].vertices.iter().any(|vertex| vertex.color == Rgba::new(0, 128, 0, 255)));
+	let (min, max) = bounds(shapes[0]);
+	assert!(close(min, vec2(-0.5, 89.5)) && close(max, vec2(10.5, 100.5)), "{:?} {:?}", min, max);
+
+	// Only the stroke is left of a shape with an unknown fill
+	assert!(shapes[1].vertices.iter().all(|vertex| vertex.color == Rgba::new(169, 169, 169, 255)));
+	let (min, max) = bounds(shapes[1]);
+	assert!(close(min, vec2(-2.0, 88.0)) && close(max, vec2(12.0, 102.0)), "{:?} {:?}", min, max);
+
+	assert!(shapes[2].vertices.iter().all(|vertex| vertex.color == Rgba::new(255, 255, 255, 128)));
+}
+
+#[test]
+fn loads_through_the_asset_server() {
+	let mut app = headless_app();
+	let handle: Handle<Svg> = app.world.resource::<AssetServer>().load("tests/shapes.svg");
+	for _ in 0..4 {
+		app.run_frame();
+	}
+	assert_eq!(app.world.resource::<AssetServer>().load_state(&handle), LoadState::Loaded);
+
+	let svg = app.world.resource::<Assets<Svg>>().get(&handle).unwrap();
+	assert_eq!(svg.size, vec2(20.0, 10.0));
+	let meshes = app.world.resource::<Assets<Mesh>>();
+	let (left, right) = (meshes.get(&svg.shapes["left"]).unwrap(), meshes.get(&svg.shapes["right"]).unwrap());
+	assert_eq!(left.vertices[0].color, Rgba::new(255, 215, 0, 255));
+	assert_eq!(right.vertices[0].color, Rgba::new(70, 130, 180, 255));
+
+	// The whole document is labeled `mesh`, and the shape without an id is only part of it
+	let mesh = meshes.get(&svg.mesh).unwrap();
+	assert_eq!(svg.shapes.len(), 2);
+	assert!(mesh.vertices.len() > left.vertices.len() + right.vertices.len());
+	let labeled = app.world.resource::<AssetServer>().get_handle::<Mesh>("tests/shapes.svg#shapes/left");
+	assert_eq!(labeled.as_ref(), Some(&svg.shapes["left"]));
+}
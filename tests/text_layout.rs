@@ -0,0 +1,134 @@
+use bevy_asset::{Assets, Handle};
+use glam::{vec2, Vec2};
+use quadify::asset::{BmFont, Font, Texture};
+use quadify::color::{RED, WHITE};
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+/// A 10 pixel font with 12 pixel lines. `a` and `b` advance 10 and the space 5, with `b` kerned by -2 after `a`
+const TEXT: &str = r#"info face="Layout" size=10
+common lineHeight=12 base=10 scaleW=64 scaleH=64 pages=1
+page id=0 file="layout_0.png"
+char id=32 x=0  y=0 width=0 height=0  xoffset=0 yoffset=0 xadvance=5  page=0
+char id=97 x=0  y=0 width=8 height=8  xoffset=1 yoffset=2 xadvance=10 page=0
+char id=98 x=16 y=0 width=8 height=10 xoffset=1 yoffset=0 xadvance=10 page=0
+kerning first=97 second=98 amount=-2
+"#;
+
+fn font(pages: Vec<Handle<Texture>>) -> Font {
+	BmFont::from_slice(TEXT.as_bytes()).unwrap().into_font(pages)
+}
+
+/// Characters of every line
+fn lines(text: &LaidOutText) -> Vec<String> {
+	text.lines.iter().map(|line| text.glyphs[line.glyphs.clone()].iter().map(|glyph| glyph.character).collect()).collect()
+}
+
+/// Pen position of the first glyph of every line
+fn line_starts(text: &LaidOutText) -> Vec<Vec2> {
+	text.lines.iter().map(|line| text.glyphs[line.glyphs.start].position).collect()
+}
+
+#[test]
+fn measures_and_breaks_at_newlines() {
+	let font = font(Vec::new());
+	let spans = [TextSpan::new("ab a\n\na", &font, 10.0)];
+	let layout = TextLayout::new();
+
+	// The kerned `b` ends at 18, the space and `a` add 15
+	assert_eq!(layout.measure_text(&spans), vec2(33.0, 36.0));
+
+	let text = layout.layout(&spans, vec2(0.0, 0.0));
+	assert_eq!(text.size, vec2(33.0, 36.0));
+	assert_eq!(lines(&text), vec!["ab a", "", "a"]);
+	assert_eq!(text.lines.iter().map(|line| line.baseline).collect::<Vec<_>>(), vec![-10.0, -22.0, -34.0]);
+	assert_eq!(text.glyphs[1].position, vec2(8.0, -10.0));
+}
+
+#[test]
+fn wraps_words_and_breaks_long_ones() {
+	let font = font(Vec::new());
+	let spans = [TextSpan::new("aa aa aaa aaaaaaa", &font, 10.0)];
+	let layout = TextLayout::new().with_max_width(30.0);
+
+	let text = layout.layout(&spans, Vec2::ZERO);
+	assert_eq!(lines(&text), vec!["aa", "aa", "aaa", "aaa", "aaa", "a"]);
+	assert_eq!(text.lines.iter().map(|line| line.width).collect::<Vec<_>>(), vec![20.0, 20.0, 30.0, 30.0, 30.0, 10.0]);
+	assert_eq!(layout.measure_text(&spans), vec2(30.0, 72.0));
+
+	// Leading spaces of a paragraph don't end up on a line of their own
+	assert_eq!(lines(&layout.layout(&[TextSpan::new("  aaaa", &font, 10.0)], Vec2::ZERO)), vec!["  aa", "aa"]);
+}
+
+#[test]
+fn aligns_lines_within_the_box() {
+	let font = font(Vec::new());
+	let spans = [TextSpan::new("aa\naaa", &font, 10.0)];
+
+	let center = TextLayout::new().with_max_width(50.0).with_align(TextAlign::Center).layout(&spans, Vec2::ZERO);
+	assert_eq!(line_starts(&center), vec![vec2(15.0, -10.0), vec2(10.0, -22.0)]);
+
+	let right = TextLayout::new().with_max_width(50.0).with_align(TextAlign::Right).layout(&spans, Vec2::ZERO);
+	assert_eq!(line_starts(&right), vec![vec2(30.0, -10.0), vec2(20.0, -22.0)]);
+
+	// Without a max width, lines align within the widest one
+	let right = TextLayout::new().with_align(TextAlign::Right).layout(&spans, vec2(100.0, 0.0));
+	assert_eq!(line_starts(&right), vec![vec2(110.0, -10.0), vec2(100.0, -22.0)]);
+
+	let bottom = TextLayout::new().with_height(100.0).with_vertical_align(VerticalAlign::Bottom).layout(&spans, Vec2::ZERO);
+	assert_eq!(bottom.lines[1].baseline, -98.0);
+	let middle = TextLayout::new().with_height(100.0).with_vertical_align(VerticalAlign::Middle).layout(&spans, Vec2::ZERO);
+	assert_eq!(middle.lines[0].baseline, -48.0);
+}
+
+#[test]
+fn justifies_wrapped_lines() {
+	let font = font(Vec::new());
+	let spans = [TextSpan::new("a a a a", &font, 10.0)];
+	let text = TextLayout::new().with_max_width(30.0).with_align(TextAlign::Justify).layout(&spans, Vec2::ZERO);
+
+	assert_eq!(lines(&text), vec!["a a", "a a"]);
+	// The wrapped line is stretched to the max width, the last one stays left aligned
+	assert_eq!(text.glyphs[2].position.x, 20.0);
+	assert_eq!(text.glyphs[1].advance, 10.0);
+	assert_eq!(text.glyphs[5].position.x, 15.0);
+	assert_eq!(text.lines.iter().map(|line| line.width).collect::<Vec<_>>(), vec![30.0, 25.0]);
+}
+
+#[test]
+fn mixes_span_sizes_and_colors() {
+	let font = font(Vec::new());
+	let spans = [TextSpan::new("a", &font, 10.0), TextSpan::new("a", &font, 20.0).with_color(RED)];
+	let text = TextLayout::new().layout(&spans, Vec2::ZERO);
+
+	// The line is as tall as its largest span, and all spans share the baseline
+	assert_eq!((text.lines[0].baseline, text.lines[0].height), (-20.0, 24.0));
+	assert_eq!(text.glyphs.iter().map(|glyph| (glyph.span, glyph.position)).collect::<Vec<_>>(), vec![(0, vec2(0.0, -20.0)), (1, vec2(10.0, -20.0))]);
+	assert_eq!(text.size, vec2(30.0, 24.0));
+
+	let quads = text.glyph_quads(&spans, &mut GlyphCache::default());
+	let corners: Vec<_> = quads.iter().map(|quad| (quad.vertices[0].position.truncate(), quad.vertices[3].position.truncate())).collect();
+	assert_eq!(corners, vec![(vec2(1.0, -12.0), vec2(9.0, -20.0)), (vec2(12.0, -4.0), vec2(28.0, -20.0))]);
+	assert_eq!(quads[1].page, TextPage::Font { span: 1, page: 0 });
+	assert_eq!((quads[0].vertices[0].color, quads[1].vertices[0].color), (WHITE, RED));
+}
+
+#[test]
+fn draws_spans_of_a_font_in_one_call() {
+	let mut app = headless_app();
+	let id = app.world.non_send_resource_mut::<RenderingBackend>().new_texture_from_rgba8(64, 64, &[255; 64 * 64 * 4]);
+	let page = app.world.resource_mut::<Assets<Texture>>().add(Texture::new(id));
+	let font = font(vec![page]);
+
+	let spans = [TextSpan::new("ab ", &font, 10.0), TextSpan::new("ba", &font, 20.0).with_color(RED)];
+	let text = TextLayout::new().layout(&spans, Vec2::ZERO);
+
+	let world = app.world.cell();
+	let mut backend = world.non_send_resource_mut::<RenderingBackend>();
+	backend.draw_text_layout(&text, &spans, &mut GlyphCache::default(), &world.resource::<Assets<Texture>>()).unwrap();
+
+	let draws: Vec<_> = backend.draw_calls().iter().map(|dc| (dc.indices_count, dc.texture)).collect();
+	assert_eq!(draws, vec![(24, Some(id))]);
+}
@@ -0,0 +1,86 @@
+use bevy_asset::Assets;
+use glam::{vec2, vec4};
+use image::{Rgba as Pixel, RgbaImage};
+use quadify::asset::{Texture, TextureAtlas, TextureAtlasBuilder, TextureAtlasBuilderError};
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+fn image(width: u32, height: u32, value: u8) -> RgbaImage {
+	RgbaImage::from_pixel(width, height, Pixel([value, value, value, 255]))
+}
+
+#[test]
+fn slices_grids() {
+	let atlas = TextureAtlas::from_grid(Default::default(), vec2(64.0, 32.0), vec2(16.0, 16.0), 3, 2, Some(vec2(2.0, 0.0)), Some(vec2(1.0, 0.0)));
+
+	assert_eq!(atlas.len(), 6);
+	assert_eq!(atlas.rect(0), Some(vec4(1.0, 0.0, 16.0, 16.0)));
+	assert_eq!(atlas.rect(2), Some(vec4(37.0, 0.0, 16.0, 16.0)));
+	assert_eq!(atlas.rect(3), Some(vec4(1.0, 16.0, 16.0, 16.0)));
+	assert_eq!(atlas.rect(6), None);
+	assert_eq!(atlas.uv_rect(4), Some(vec4(19.0 / 64.0, 0.5, 0.25, 0.5)));
+}
+
+#[test]
+fn packs_images_without_overlaps() {
+	let mut builder = TextureAtlasBuilder::default();
+	builder.padding(1);
+	for i in 0..10 {
+		builder.add_image(image(3 + i, 10 - i, i as u8));
+	}
+	builder.add_named_image("big", image(20, 12, 100));
+
+	let (packed, rects) = builder.pack().unwrap();
+	assert_eq!(rects.len(), 11);
+	assert!(packed.width().is_power_of_two() && packed.height().is_power_of_two());
+
+	for (i, a) in rects.iter().enumerate() {
+		// Indices follow the order of addition
+		let expected = if i == 10 { vec2(20.0, 12.0) } else { vec2(3.0 + i as f32, 10.0 - i as f32) };
+		assert_eq!(vec2(a.z, a.w), expected);
+		assert!(a.x + a.z <= packed.width() as f32 && a.y + a.w <= packed.height() as f32);
+		assert_eq!(packed.get_pixel(a.x as u32, a.y as u32)[0], if i == 10 { 100 } else { i as u8 });
+
+		for b in rects.iter().skip(i + 1) {
+			let overlaps = a.x < b.x + b.z && b.x < a.x + a.z && a.y < b.y + b.w && b.y < a.y + a.w;
+			assert!(!overlaps, "{a} overlaps {b}");
+		}
+	}
+}
+
+#[test]
+fn fails_when_images_dont_fit() {
+	let mut builder = TextureAtlasBuilder::default();
+	builder.max_size(32, 32).add_image(image(20, 20, 0)).add_image(image(20, 20, 0));
+
+	assert_eq!(builder.pack().unwrap_err(), TextureAtlasBuilderError::NotEnoughSpace { max_size: (32, 32) });
+}
+
+#[test]
+fn atlas_sprites_draw_packed_regions() {
+	let mut app = headless_app();
+
+	let mut builder = TextureAtlasBuilder::default();
+	builder.add_image(image(8, 8, 0)).add_named_image("player", image(4, 2, 255));
+	let atlas = {
+		let world = app.world.cell();
+		let mut backend = world.non_send_resource_mut::<RenderingBackend>();
+		let mut textures = world.resource_mut::<Assets<Texture>>();
+		builder.build(&mut backend, &mut textures).unwrap()
+	};
+	let index = atlas.index_of("player").unwrap();
+	let rect = atlas.rect(index);
+	let texture = app.world.resource::<Assets<Texture>>().get(&atlas.texture).unwrap().id();
+	let handle = app.world.resource_mut::<Assets<TextureAtlas>>().add(atlas);
+
+	let entity = app.world.spawn((TextureAtlasSprite::new(handle, index), Transform::default())).id();
+	app.run_frame();
+	app.run_frame();
+
+	assert_eq!(app.world.get::<Sprite>(entity).unwrap().rect, rect);
+	let calls = app.world.non_send_resource::<RecordedCalls>().get();
+	assert!(calls.iter().any(|call| matches!(call, RecordedCall::ApplyBindings { images, .. } if images[0] == texture)));
+	assert!(calls.iter().any(|call| matches!(call, RecordedCall::Draw { num_elements: 6, .. })));
+}
@@ -0,0 +1,126 @@
+use bevy_app::*;
+use bevy_ecs::{prelude::*, system::RunSystemOnce};
+use glam::{vec3, Quat, Vec3};
+use quadify::prelude::*;
+
+fn app() -> App {
+	let mut app = App::new();
+	app.add_plugins(TransformPlugin);
+	app
+}
+
+fn global_translation(app: &App, entity: Entity) -> Vec3 {
+	app.world.get::<GlobalTransform>(entity).unwrap().translation()
+}
+
+#[test]
+fn inserts_global_transforms() {
+	let mut app = app();
+	let entity = app.world.spawn(Transform::from_xyz(1.0, 2.0, 3.0)).id();
+
+	app.update();
+
+	assert_eq!(app.world.get::<GlobalTransform>(entity), Some(&GlobalTransform::from(Transform::from_xyz(1.0, 2.0, 3.0))));
+}
+
+#[test]
+fn propagates_through_the_hierarchy() {
+	let mut app = app();
+	// Children are spawned before their parents, so the propagation can't rely on the iteration order
+	let grandchild = app.world.spawn(Transform::from_xyz(0.0, 1.0, 0.0)).id();
+	let child = app.world.spawn(Transform::from_xyz(1.0, 0.0, 0.0).with_scale(Vec3::splat(2.0))).id();
+	let root = app.world.spawn(Transform::from_xyz(10.0, 0.0, 0.0).with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))).id();
+	app.world.entity_mut(grandchild).insert(Parent::new(child));
+	app.world.entity_mut(child).insert(Parent::new(root));
+
+	app.update();
+
+	assert_eq!(global_translation(&app, root), vec3(10.0, 0.0, 0.0));
+	assert!(global_translation(&app, child).abs_diff_eq(vec3(10.0, 1.0, 0.0), 1e-5));
+	assert!(global_translation(&app, grandchild).abs_diff_eq(vec3(8.0, 1.0, 0.0), 1e-5));
+}
+
+#[test]
+fn follows_parent_changes() {
+	let mut app = app();
+	let parent = app.world.spawn(Transform::default()).id();
+	let child = app.world.spawn((Transform::from_xyz(1.0, 0.0, 0.0), Parent::new(parent))).id();
+	app.update();
+
+	app.world.get_mut::<Transform>(parent).unwrap().translation = vec3(0.0, 5.0, 0.0);
+	app.update();
+
+	assert_eq!(global_translation(&app, child), vec3(1.0, 5.0, 0.0));
+}
+
+#[test]
+fn orphans_are_positioned_as_roots() {
+	let mut app = app();
+	let parent = app.world.spawn(Transform::from_xyz(0.0, 5.0, 0.0)).id();
+	let child = app.world.spawn((Transform::from_xyz(1.0, 0.0, 0.0), Parent::new(parent))).id();
+	app.update();
+	assert_eq!(global_translation(&app, child), vec3(1.0, 5.0, 0.0));
+
+	app.world.despawn(parent);
+	app.update();
+
+	assert_eq!(global_translation(&app, child), vec3(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn survives_parent_cycles() {
+	let mut app = app();
+	let a = app.world.spawn(Transform::from_xyz(1.0, 0.0, 0.0)).id();
+	let b = app.world.spawn((Transform::from_xyz(1.0, 0.0, 0.0), Parent::new(a))).id();
+	app.world.entity_mut(a).insert(Parent::new(b));
+
+	app.update();
+
+	assert!(app.world.get::<GlobalTransform>(a).is_some());
+	assert!(app.world.get::<GlobalTransform>(b).is_some());
+}
+
+#[test]
+fn keeps_children_in_sync() {
+	let mut app = app();
+	let a = app.world.spawn(Transform::default()).id();
+	let b = app.world.spawn(Transform::default()).id();
+	let child = app.world.spawn((Transform::from_xyz(1.0, 0.0, 0.0), Parent::new(a))).id();
+	app.update();
+	assert_eq!(&**app.world.get::<Children>(a).unwrap(), &[child]);
+
+	app.world.get_mut::<Transform>(b).unwrap().translation = vec3(0.0, 3.0, 0.0);
+	app.world.entity_mut(child).insert(Parent::new(b));
+	app.update();
+	assert!(app.world.get::<Children>(a).is_none());
+	assert_eq!(&**app.world.get::<Children>(b).unwrap(), &[child]);
+	assert_eq!(global_translation(&app, child), vec3(1.0, 3.0, 0.0));
+
+	app.world.entity_mut(child).remove::<Parent>();
+	app.update();
+	assert!(app.world.get::<Children>(b).is_none());
+	assert_eq!(global_translation(&app, child), vec3(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn builds_and_despawns_hierarchies() {
+	let mut app = app();
+	let root = app.world.spawn(Transform::from_xyz(1.0, 0.0, 0.0)).id();
+	app.world.run_system_once(move |mut commands: Commands| {
+		commands.entity(root).with_children(|children| {
+			children.spawn(Transform::from_xyz(0.0, 1.0, 0.0)).with_children(|grandchildren| {
+				grandchildren.spawn(Transform::from_xyz(0.0, 0.0, 1.0));
+			});
+		});
+	});
+	app.update();
+
+	let child = app.world.get::<Children>(root).unwrap()[0];
+	let grandchild = app.world.get::<Children>(child).unwrap()[0];
+	assert_eq!(global_translation(&app, grandchild), vec3(1.0, 1.0, 1.0));
+
+	app.world.run_system_once(move |mut commands: Commands| commands.entity(child).despawn_recursive());
+	app.update();
+	assert!(app.world.get_entity(child).is_none() && app.world.get_entity(grandchild).is_none());
+	assert!(app.world.get::<Children>(root).is_none());
+}
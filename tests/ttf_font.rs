@@ -0,0 +1,175 @@
+use glam::{vec2, Vec2};
+use quadify::asset::{GlyphQuad, TtfFont};
+use quadify::color::WHITE;
+use quadify::prelude::*;
+
+mod common;
+use common::headless_app;
+
+/// A minimal TrueType font with 1000 units per em, an ascender of 800 and a descender of -200.
+/// `A` is a 500x700 rectangle on the baseline advancing 600, `B` a 300x900 rectangle from (100, -200) advancing 500, and the space advances 250
+fn test_font() -> Vec<u8> {
+	fn rect(x0: i16, y0: i16, x1: i16, y1: i16) -> Vec<u8> {
+		let mut glyph = Vec::new();
+		for value in [1, x0, y0, x1, y1, 3, 0] {
+			glyph.extend(value.to_be_bytes());
+		}
+		glyph.extend([1u8; 4]);
+		for value in [x0, x1 - x0, 0, x0 - x1, y0, 0, y1 - y0, 0] {
+			glyph.extend(value.to_be_bytes());
+		}
+		glyph
+	}
+	fn u16s(values: &[u16]) -> Vec<u8> {
+		values.iter().flat_map(|value| value.to_be_bytes()).collect()
+	}
+
+	let glyphs = [Vec::new(), Vec::new(), rect(0, 0, 500, 700), rect(100, -200, 400, 700)];
+	let advances = [500u16, 250, 600, 500];
+
+	let mut glyf = Vec::new();
+	let mut loca = Vec::new();
+	for glyph in &glyphs {
+		loca.extend((glyf.len() as u32).to_be_bytes());
+		glyf.extend(glyph);
+	}
+	loca.extend((glyf.len() as u32).to_be_bytes());
+
+	let hmtx: Vec<u8> = advances.iter().flat_map(|advance| [advance.to_be_bytes(), [0, 0]].concat()).collect();
+	let mut head = u16s(&[1, 0, 1, 0, 0, 0, 0x5F0F, 0x3CF5, 0, 1000]);
+	head.extend([0; 16]);
+	head.extend(u16s(&[0, (-200i16) as u16, 500, 700, 0, 8, 2, 1, 0]));
+	let mut hhea = u16s(&[1, 0, 800, (-200i16) as u16, 0, 600, 0, 0, 500, 1, 0, 0, 0, 0, 0, 0, 0]);
+	hhea.extend(u16s(&[advances.len() as u16]));
+	let maxp = u16s(&[0, 0x5000, glyphs.len() as u16]);
+
+	// Format 4 segments: the space to glyph 1, `A` and `B` to glyphs 2 and 3
+	let (starts, ends, deltas) = ([32u16, 65, 0xFFFF], [32u16, 66, 0xFFFF], [(1i16 - 32) as u16, (2i16 - 65) as u16, 1]);
+	let mut subtable = u16s(&[4, 16 + 8 * 3, 0, 6, 4, 1, 2]);
+	subtable.extend(u16s(&ends));
+	subtable.extend(u16s(&[0]));
+	subtable.extend(u16s(&starts));
+	subtable.extend(u16s(&deltas));
+	subtable.extend(u16s(&[0, 0, 0]));
+	let mut cmap = u16s(&[0, 1, 3, 1, 0, 12]);
+	cmap.extend(subtable);
+
+	let tables: [(&[u8; 4], Vec<u8>); 7] = [(b"cmap", cmap), (b"glyf", glyf), (b"head", head), (b"hhea", hhea), (b"hmtx", hmtx), (b"loca", loca), (b"maxp", maxp)];
+	let mut font = u16s(&[1, 0, tables.len() as u16, 64, 2, 48]);
+	let mut offset = 12 + 16 * tables.len();
+	let mut data = Vec::new();
+	for (tag, table) in &tables {
+		font.extend(*tag);
+		font.extend([0; 4]);
+		font.extend((offset as u32).to_be_bytes());
+		font.extend((table.len() as u32).to_be_bytes());
+		let padded = (table.len() + 3) & !3;
+		data.extend(table);
+		data.resize(data.len() + padded - table.len(), 0);
+		offset += padded;
+	}
+	font.extend(data);
+	font
+}
+
+/// Top left and bottom right corners of every quad
+fn corners(quads: &[GlyphQuad]) -> Vec<(Vec2, Vec2)> {
+	quads.iter().map(|quad| (quad.vertices[0].position.truncate(), quad.vertices[3].position.truncate())).collect()
+}
+
+#[test]
+fn lays_out_glyphs_from_the_font_metrics() {
+	let font = TtfFont::from_bytes(&test_font()).unwrap();
+	let mut cache = GlyphCache::default();
+	assert_eq!(font.ascent(100.0), 80.0);
+	assert_eq!(font.line_height(100.0), 100.0);
+
+	let quads = cache.glyph_quads(&font, "A B\nA", vec2(10.0, 0.0), 100.0, WHITE);
+	assert_eq!(
+		corners(&quads),
+		vec![
+			(vec2(10.0, -10.0), vec2(60.0, -80.0)),
+			// After the advances of `A` and the space, offset by the left side bearing of `B`
+			(vec2(105.0, -10.0), vec2(135.0, -100.0)),
+			(vec2(10.0, -110.0), vec2(60.0, -180.0)),
+		]
+	);
+	// Two outlined glyphs and the space
+	assert_eq!(cache.len(), 3);
+}
+
+#[test]
+fn uploads_only_new_glyphs() {
+	let mut app = headless_app();
+	let font = TtfFont::from_bytes(&test_font()).unwrap();
+	let mut cache = GlyphCache::default();
+	let calls = app.world.non_send_resource::<RecordedCalls>().clone();
+	let mut backend = app.world.non_send_resource_mut::<RenderingBackend>();
+
+	cache.glyph_quads(&font, "A", Vec2::ZERO, 20.0, WHITE);
+	cache.flush(&mut backend);
+	let texture = cache.page_texture(0).unwrap();
+	assert_eq!(backend.texture_size(texture), (256.0, 256.0));
+
+	let updates = || {
+		let calls = calls.get();
+		calls
+			.iter()
+			.filter_map(|call| match call {
+				RecordedCall::TextureUpdate { texture, width, height, .. } => Some((*texture, *width, *height)),
+				_ => None,
+			})
+			.collect::<Vec<_>>()
+	};
+	let before = updates().len();
+
+	cache.glyph_quads(&font, "AA", Vec2::ZERO, 20.0, WHITE);
+	cache.flush(&mut backend);
+	assert_eq!(updates().len(), before);
+
+	let quads = cache.glyph_quads(&font, "AB", Vec2::ZERO, 20.0, WHITE);
+	cache.flush(&mut backend);
+	// Only the region of `B`, which is 6x18 pixels
+	assert_eq!(updates()[before..], [(texture, 6, 18)]);
+	assert!(quads.iter().all(|quad| quad.page == 0));
+}
+
+#[test]
+fn grows_pages_then_evicts_the_least_recently_used() {
+	let font = TtfFont::from_bytes(&test_font()).unwrap();
+	let mut cache = GlyphCache::new(32, 64, 2);
+
+	// 25x35 pixel glyphs, so a 32x32 page has to grow to fit even one
+	cache.glyph_quads(&font, "A", Vec2::ZERO, 50.0, WHITE);
+	assert_eq!(cache.page_size(0), Some((64, 64)));
+
+	// Every size is a new glyph, and two pages can't hold all of them
+	let mut pages = Vec::new();
+	for size in 51..60 {
+		pages.push(cache.glyph_quads(&font, "A", Vec2::ZERO, size as f32, WHITE)[0].page);
+	}
+	assert_eq!(cache.page_count(), 2);
+	assert!(cache.len() < 10);
+
+	// The first page was used the longest ago, so it was cleared when the second one filled up
+	let first_eviction = pages.iter().position(|page| *page == 0 && pages.contains(&1)).unwrap();
+	assert!(pages[..first_eviction].contains(&1));
+
+	// Recently used glyphs stay cached
+	let len = cache.len();
+	cache.glyph_quads(&font, "A", Vec2::ZERO, 59.0, WHITE);
+	assert_eq!(cache.len(), len);
+}
+
+#[test]
+fn draws_text_with_a_call_per_page() {
+	let mut app = headless_app();
+	let font = TtfFont::from_bytes(&test_font()).unwrap();
+	let mut cache = GlyphCache::default();
+	let mut backend = app.world.non_send_resource_mut::<RenderingBackend>();
+
+	backend.draw_ttf_text(&mut cache, &font, "AB BA", Vec2::ZERO, 30.0, WHITE).unwrap();
+
+	let draws: Vec<_> = backend.draw_calls().iter().map(|dc| (dc.indices_count, dc.texture)).collect();
+	assert_eq!(draws, vec![(24, cache.page_texture(0))]);
+}
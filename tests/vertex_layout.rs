@@ -0,0 +1,108 @@
+use glam::{vec2, vec3, Mat4, Vec2, Vec3};
+use miniquad::{PipelineParams, ShaderSource, VertexAttribute, VertexFormat};
+use quadify::color::WHITE;
+use quadify::prelude::*;
+
+mod common;
+use common::recording_backend;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LitVertex {
+	position: Vec3,
+	normal: Vec3,
+	uv: Vec2,
+}
+
+unsafe impl VertexLayout for LitVertex {
+	const ATTRIBUTES: &'static [VertexAttribute] = &[
+		VertexAttribute::new("position", VertexFormat::Float3),
+		VertexAttribute::new("normal", VertexFormat::Float3),
+		VertexAttribute::new("texcoord", VertexFormat::Float2),
+	];
+}
+
+fn lit_material(backend: &mut RenderingBackend) -> Material {
+	let params = MaterialParams {
+		pipeline_params: PipelineParams::default(),
+		uniforms: vec![],
+		textures: vec![],
+	};
+	backend.request_material_with_layout::<LitVertex>(ShaderSource::Glsl { vertex: "", fragment: "" }, params).unwrap()
+}
+
+fn lit_triangle() -> Vec<LitVertex> {
+	(0..3)
+		.map(|i| LitVertex {
+			position: vec3(i as f32, 0.0, 0.0),
+			normal: Vec3::Z,
+			uv: vec2(0.0, i as f32),
+		})
+		.collect()
+}
+
+#[test]
+fn draws_custom_vertices() {
+	let (mut backend, calls) = recording_backend();
+	let material = lit_material(&mut backend);
+	let vertices = lit_triangle();
+
+	backend.set_material(&material);
+	backend.geometry(&vertices, &[0u16, 1, 2]).unwrap();
+	backend.geometry(&vertices, &[2u16, 1, 0]).unwrap();
+
+	assert_eq!(backend.draw_calls().len(), 1);
+	let drawn = backend.draw_calls()[0].vertices::<LitVertex>().unwrap();
+	assert_eq!(drawn, [vertices.clone(), vertices].concat());
+	assert!(backend.draw_calls()[0].vertices::<Vertex>().is_none());
+
+	backend.draw(Mat4::IDENTITY);
+
+	// The vertex buffer is recreated to fit the larger vertices
+	let vertex_buffer_sizes: Vec<_> = calls
+		.take()
+		.into_iter()
+		.filter_map(|call| match call {
+			RecordedCall::NewBuffer {
+				buffer_type: miniquad::BufferType::VertexBuffer,
+				size,
+				..
+			} => size,
+			_ => None,
+		})
+		.collect();
+	assert_eq!(vertex_buffer_sizes, vec![10000 * std::mem::size_of::<Vertex>(), 10000 * std::mem::size_of::<LitVertex>()]);
+}
+
+#[test]
+fn rejects_vertices_of_another_layout() {
+	let (mut backend, _calls) = recording_backend();
+	let material = lit_material(&mut backend);
+	let vertex = Vertex::new(Vec3::ZERO, Vec2::ZERO, WHITE);
+
+	backend.set_material(&material);
+	assert!(matches!(backend.geometry(&[vertex; 3], &[0u16, 1, 2]), Err(GeometryError::VertexLayoutMismatch { .. })));
+
+	backend.pipeline(None);
+	assert!(matches!(backend.geometry(&lit_triangle(), &[0u16, 1, 2]), Err(GeometryError::VertexLayoutMismatch { .. })));
+	assert!(backend.draw_calls().is_empty());
+}
+
+#[test]
+fn breaks_batching_between_layouts() {
+	let (mut backend, calls) = recording_backend();
+	let material = lit_material(&mut backend);
+	let vertex = Vertex::new(Vec3::ZERO, Vec2::ZERO, WHITE);
+
+	backend.geometry(&[vertex; 3], &[0u16, 1, 2]).unwrap();
+	backend.set_material(&material);
+	backend.geometry(&lit_triangle(), &[0u16, 1, 2]).unwrap();
+	backend.pipeline(None);
+	backend.geometry(&[vertex; 3], &[0u16, 1, 2]).unwrap();
+
+	let layouts: Vec<_> = backend.draw_calls().iter().map(|dc| dc.vertex_layout.name()).collect();
+	assert_eq!(layouts, vec![std::any::type_name::<Vertex>(), std::any::type_name::<LitVertex>(), std::any::type_name::<Vertex>()]);
+
+	backend.draw(Mat4::IDENTITY);
+	assert_eq!(calls.take().iter().filter(|call| matches!(call, RecordedCall::Draw { .. })).count(), 3);
+}